@@ -0,0 +1,118 @@
+mod common;
+
+use common::Server;
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use moon::sse::{EventName, PreparedEvent, ShareableSSEMethods};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static POSITION: EventName = EventName::from_static("position");
+
+// `broadcast` encodes the `event:` line for each broadcast, `EventName` only once
+// and `PreparedEvent` encodes the whole event only once.
+fn broadcast_allocations(c: &mut Criterion<Allocations>) {
+    let server = Server::start(1_000);
+    let data = r#"{"x":10,"y":20}"#;
+    let prepared_event = PreparedEvent::new(POSITION.as_str(), data).unwrap();
+    let mut group = c.benchmark_group("broadcast_allocations");
+    group.bench_function("broadcast", |b| {
+        b.iter(|| server.sse.broadcast(POSITION.as_str(), data).unwrap())
+    });
+    group.bench_function("broadcast_named", |b| {
+        b.iter(|| server.sse.broadcast_named(&POSITION, data).unwrap())
+    });
+    group.bench_function("broadcast_prepared", |b| {
+        b.iter(|| server.sse.broadcast_prepared(&prepared_event).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_measurement(Allocations);
+    targets = broadcast_allocations
+}
+criterion_main!(benches);
+
+// ------ CountingAllocator ------
+
+// Counts allocations of all threads, including the thread reading the streams.
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+// ------ Allocations ------
+
+// Criterion reports the number of allocations instead of the time.
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> Self::Intermediate {
+        ALLOCATIONS.load(Ordering::Relaxed)
+    }
+
+    fn end(&self, started: Self::Intermediate) -> Self::Value {
+        ALLOCATIONS.load(Ordering::Relaxed) - started
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationsFormatter
+    }
+}
+
+struct AllocationsFormatter;
+
+impl ValueFormatter for AllocationsFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "allocations"
+    }
+
+    // The benchmarks don't set any throughput.
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        _throughput: &Throughput,
+        _values: &mut [f64],
+    ) -> &'static str {
+        "allocations"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocations"
+    }
+}
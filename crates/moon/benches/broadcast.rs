@@ -0,0 +1,83 @@
+mod common;
+
+use common::Server;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use moon::sse::{frame, PreparedEvent, ShareableSSEMethods};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+// A broadcast pushes the message to each connection queue on the calling thread.
+fn broadcast_latency(c: &mut Criterion) {
+    let mut group = c.benchmark_group("broadcast_latency");
+    for connection_count in [100, 1_000, 20_000] {
+        let server = Server::start(connection_count);
+        group.throughput(Throughput::Elements(connection_count as u64));
+        group.bench_function(BenchmarkId::from_parameter(connection_count), |b| {
+            b.iter(|| server.sse.broadcast("tick", "1").unwrap())
+        });
+    }
+    group.finish();
+}
+
+// Broadcasts don't hold the shard locks while they send,
+// so a `send` doesn't wait for a broadcast to all connections.
+fn send_during_broadcast(c: &mut Criterion) {
+    let server = Server::start(20_000);
+    let session_id = server.session_ids[0];
+    let broadcasting = AtomicBool::new(true);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            while broadcasting.load(Ordering::Relaxed) {
+                server.sse.broadcast("tick", "1").unwrap();
+            }
+        });
+        c.bench_function("send_during_broadcast", |b| {
+            b.iter(|| server.sse.send(&session_id, "sync", "1").unwrap())
+        });
+        broadcasting.store(false, Ordering::Relaxed);
+    });
+}
+
+// `broadcast_bytes` writes the already framed message without encoding it for each broadcast.
+fn broadcast_10k(c: &mut Criterion) {
+    let server = Server::start(10_000);
+    let data = "x".repeat(1024);
+    let message = frame("state", &data).unwrap();
+    let mut group = c.benchmark_group("broadcast_10k");
+    group.throughput(Throughput::Elements(10_000));
+    group.bench_function("broadcast", |b| {
+        b.iter(|| server.sse.broadcast("state", &data).unwrap())
+    });
+    group.bench_function("broadcast_bytes", |b| {
+        b.iter(|| server.sse.broadcast_bytes(message.clone()).unwrap())
+    });
+    group.finish();
+}
+
+// `broadcast` encodes the event each time it's called, `PreparedEvent` only once.
+fn prepared_broadcast(c: &mut Criterion) {
+    let data = "x".repeat(4096);
+    let prepared_event = PreparedEvent::new("state", &data).unwrap();
+    let mut group = c.benchmark_group("prepared_broadcast");
+    for connection_count in [1_000, 10_000] {
+        let server = Server::start(connection_count);
+        group.throughput(Throughput::Elements(connection_count as u64));
+        group.bench_function(BenchmarkId::new("broadcast", connection_count), |b| {
+            b.iter(|| server.sse.broadcast("state", &data).unwrap())
+        });
+        group.bench_function(
+            BenchmarkId::new("broadcast_prepared", connection_count),
+            |b| b.iter(|| server.sse.broadcast_prepared(&prepared_event).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    broadcast_latency,
+    send_during_broadcast,
+    broadcast_10k,
+    prepared_broadcast
+);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+// Every bench uses only a part of the helpers.
+#![allow(dead_code)]
+
+use moon::actix_web::rt::System;
+use moon::futures::task::noop_waker_ref;
+use moon::futures::Stream;
+use moon::sse::{EventStream, SSEConfig, ShareableSSE, ShareableSSEMethods, SSE};
+use moon::tokio::time::{interval, Duration};
+use moon::SessionId;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc};
+use std::task::{Context, Poll};
+use std::thread;
+
+// How often the streams are read, like by clients with a slow network.
+const READ_INTERVAL: Duration = Duration::from_millis(10);
+
+// ------ Server ------
+
+pub struct Server {
+    pub sse: ShareableSSE,
+    // the session of each connection
+    pub session_ids: Vec<SessionId>,
+}
+
+impl Server {
+    pub fn start(connection_count: usize) -> Self {
+        Self::start_with(SSEConfig::default(), connection_count)
+    }
+
+    // The actix system with the connection remover runs in its own thread,
+    // the thread reads all streams until the server is dropped.
+    pub fn start_with(config: SSEConfig, connection_count: usize) -> Self {
+        let (server_sender, server_receiver) = mpsc::channel();
+        thread::spawn(move || {
+            System::new().block_on(async move {
+                let sse = SSE::start_with(config).unwrap();
+                let mut session_ids = Vec::with_capacity(connection_count);
+                let mut event_streams = Vec::with_capacity(connection_count);
+                for _ in 0..connection_count {
+                    let (_, connection, event_stream) = sse.new_connection(None, None).unwrap();
+                    session_ids.push(connection.session_id());
+                    event_streams.push(event_stream);
+                }
+                let server = Self {
+                    sse: Arc::clone(&sse),
+                    session_ids,
+                };
+                server_sender.send(server).unwrap();
+
+                let mut read = interval(READ_INTERVAL);
+                while !event_streams.is_empty() {
+                    read.tick().await;
+                    event_streams.retain_mut(read_messages);
+                }
+            });
+        });
+        server_receiver.recv().unwrap()
+    }
+}
+
+impl Drop for Server {
+    // Ends the streams, so the reading thread stops.
+    fn drop(&mut self) {
+        self.sse.close_all("shutdown", "");
+    }
+}
+
+// Returns `false` when the stream has ended.
+fn read_messages(event_stream: &mut EventStream) -> bool {
+    let mut cx = Context::from_waker(noop_waker_ref());
+    loop {
+        match Pin::new(&mut *event_stream).poll_next(&mut cx) {
+            Poll::Ready(Some(_)) => (),
+            Poll::Ready(None) => return false,
+            Poll::Pending => return true,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::Server;
+use criterion::{criterion_group, criterion_main, Criterion};
+use moon::sse::{SSEConfig, ShareableSSEMethods};
+use std::time::{Duration, Instant};
+
+// The connection remover sweeps one shard per tick, so sends don't wait
+// for a sweep of all connections. The short interval keeps the remover busy.
+fn p99_send_latency(c: &mut Criterion) {
+    let config = SSEConfig {
+        keep_alive_interval: Duration::from_millis(100),
+        ..SSEConfig::default()
+    };
+    let server = Server::start_with(config, 20_000);
+    c.bench_function("p99_send_latency", |b| {
+        // The reported time is the 99th percentile of the sends instead of their mean.
+        b.iter_custom(|iters| {
+            let mut latencies = (0..iters)
+                .map(|iter| {
+                    let session_id = &server.session_ids[iter as usize % server.session_ids.len()];
+                    let started_at = Instant::now();
+                    server.sse.send(session_id, "sync", "1").unwrap();
+                    started_at.elapsed()
+                })
+                .collect::<Vec<_>>();
+            latencies.sort_unstable();
+            latencies[latencies.len() * 99 / 100] * iters as u32
+        })
+    });
+}
+
+criterion_group!(benches, p99_send_latency);
+criterion_main!(benches);
@@ -1,9 +1,9 @@
 use crate::actor::{ActorId, ActorInstance, Index, PVar};
-use crate::sse::ShareableSSEMethods;
+use crate::sse::{self, ShareableSSEMethods};
 use crate::MessageSSE;
 use chashmap::CHashMap;
 use futures::future::join_all;
-use moonlight::{serde_json, CorId, DownMsgTransporterForSer, Serialize, SessionId};
+use moonlight::{CorId, Serialize, SessionId};
 use once_cell::sync::Lazy;
 use std::borrow::Borrow;
 use std::cell::RefCell;
@@ -176,12 +176,18 @@ impl SessionActorInstance {
     pub async fn send_down_msg<DMsg: Serialize>(&self, down_msg: &DMsg, cor_id: CorId) {
         let session_id = self.session_id.read().unwrap();
 
-        let down_msg_transporter = DownMsgTransporterForSer { down_msg, cor_id };
-
-        #[cfg(feature = "serde")]
-        let down_msg_transporter = serde_json::to_string(&down_msg_transporter).unwrap();
-
-        self.message_sse
-            .send(&session_id, "down_msg", &down_msg_transporter);
+        match self
+            .message_sse
+            .send_down_msg(&session_id, cor_id, down_msg)
+        {
+            Ok(_) => (),
+            // The session isn't connected, e.g. its browser tab has been closed.
+            Err(sse::Error::SessionNotFound { .. }) => (),
+            // `send_down_msg` has already removed the closed connections.
+            Err(sse::Error::ConnectionClosed { .. }) => {
+                println!("DownMsg not sent, session `{session_id}` has been disconnected.")
+            }
+            Err(error) => eprintln!("Failed to send DownMsg: {error}"),
+        }
     }
 }
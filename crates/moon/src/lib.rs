@@ -58,12 +58,12 @@ mod frontend;
 mod lazy_message_writer;
 mod not;
 mod redirect;
-mod sse;
+pub mod sse;
 mod up_msg_request;
 
 use config::CONFIG;
 use lazy_message_writer::LazyMessageWriter;
-use sse::{ShareableSSE, ShareableSSEMethods, SSE};
+use sse::{ReplayBufferSize, ShareableSSE, ShareableSSEMethods, SSE};
 
 pub use actor::{
     sessions::{self, SessionActor},
@@ -79,6 +79,9 @@ pub use up_msg_request::UpMsgRequest;
 // const MAX_UP_MSG_BYTES: usize = 2 * 1_048_576;
 const MAX_UP_MSG_BYTES: usize = usize::MAX;
 
+// How long clients wait before reconnecting to the restarting server.
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+
 #[derive(Copy, Clone)]
 struct SharedData {
     backend_build_id: u128,
@@ -209,7 +212,8 @@ where
         pkg_path: "frontend/pkg",
     };
     let reload_sse = ReloadSSE(SSE::start());
-    let message_sse = MessageSSE(SSE::start());
+    let message_sse =
+        MessageSSE(SSE::start_with(ReplayBufferSize(64)).expect("message SSE config is invalid"));
     let address = SocketAddr::from(([0, 0, 0, 0], CONFIG.port));
 
     let mut lazy_message_writer = LazyMessageWriter::new();
@@ -221,6 +225,7 @@ where
     let data_up_msg_handler = web::Data::new(up_msg_handler);
     let data_reload_sse = web::Data::new(reload_sse);
     let data_message_sse = web::Data::new(message_sse);
+    let shutdown_message_sse = data_message_sse.clone();
 
     let app = Arc::new(app);
 
@@ -255,6 +260,14 @@ where
                         "message_sse/{session_id}",
                         web::get().to(message_sse_responder),
                     )
+                    .route(
+                        "message_sse/{session_id}/{connection_id}/close",
+                        web::post().to(message_sse_close_responder),
+                    )
+                    .route(
+                        "message_sse/{session_id}/{connection_id}/ack/{event_id}",
+                        web::post().to(message_sse_ack_responder),
+                    )
                     .route("reload_sse", web::get().to(reload_sse_responder))
                     .route("ping", web::to(|| async { "pong" }))
                     .route(
@@ -286,7 +299,14 @@ where
 
     // ------ Run ------
 
-    let server = server.run();
+    // Moon handles the signals to notify clients before the server stops.
+    let server = server.disable_signals().run();
+    let server_handle = server.handle();
+    actix_web::rt::spawn(async move {
+        let graceful = shutdown_signal().await;
+        shutdown_message_sse.prepare_shutdown(SHUTDOWN_GRACE);
+        server_handle.stop(graceful).await;
+    });
     if not(CONFIG.frontend_dist) {
         lazy_message_writer.write_all()?;
     }
@@ -295,6 +315,27 @@ where
     Ok(println!("Stop Moon"))
 }
 
+// Returns `true` for a graceful shutdown, the signals are handled like by actix.
+async fn shutdown_signal() -> bool {
+    #[cfg(unix)]
+    {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
+        let mut terminate =
+            signal(SignalKind::terminate()).expect("SIGTERM handler cannot be registered");
+        let mut quit = signal(SignalKind::quit()).expect("SIGQUIT handler cannot be registered");
+        tokio::select! {
+            _ = terminate.recv() => true,
+            _ = quit.recv() => false,
+            _ = actix_web::rt::signal::ctrl_c() => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = actix_web::rt::signal::ctrl_c().await;
+        false
+    }
+}
+
 async fn backend_build_id() -> u128 {
     fs::read_to_string("backend/private/build_id")
         .await
@@ -339,6 +380,7 @@ async fn up_msg_handler_responder<UPH, UPHO, UMsg>(
     req: HttpRequest,
     payload: web::Payload,
     up_msg_handler: web::Data<UPH>,
+    sse: web::Data<MessageSSE>,
 ) -> Result<HttpResponse, Error>
 where
     UPH: UpHandler<UPHO, UMsg>,
@@ -353,6 +395,8 @@ where
         cor_id: parse_cor_id(headers)?,
         auth_token: parse_auth_token(headers)?,
     };
+    // The client is alive even when its `EventSource` doesn't get anything, see `SSEConfig::max_idle`.
+    sse.touch(&up_msg_request.session_id);
     up_msg_handler.get_ref()(up_msg_request).await;
     Ok(HttpResponse::Ok().finish())
 }
@@ -406,7 +450,7 @@ fn parse_auth_token(headers: &HeaderMap) -> Result<Option<AuthToken>, Error> {
 // ------ reload_responder ------
 
 async fn reload_responder(sse: web::Data<ReloadSSE>) -> impl Responder {
-    let _ = sse.broadcast("reload", "");
+    let _ = sse.broadcast_protocol_event(ProtocolEvent::Reload, "");
     HttpResponse::Ok()
 }
 
@@ -612,7 +656,11 @@ async fn reload_sse_responder(
     sse: web::Data<ReloadSSE>,
     shared_data: web::Data<SharedData>,
 ) -> impl Responder {
-    let (connection, event_stream) = sse.new_connection(None);
+    let Ok((_, connection, event_stream)) = sse.new_connection(None, None) else {
+        return HttpResponse::InternalServerError()
+            .reason("creating SSE connection failed")
+            .finish();
+    };
     let backend_build_id = shared_data.backend_build_id.to_string();
 
     if connection
@@ -624,26 +672,66 @@ async fn reload_sse_responder(
             .finish();
     }
 
-    HttpResponse::Ok()
-        .insert_header(ContentType(mime::TEXT_EVENT_STREAM))
-        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
-        .streaming(event_stream)
+    sse::into_response(event_stream)
 }
 
 // ------ message_sse_responder ------
 
 async fn message_sse_responder(
+    req: HttpRequest,
     session_id: web::Path<String>,
     sse: web::Data<MessageSSE>,
 ) -> Result<HttpResponse, Error> {
     let session_id = session_id.parse().map_err(error::ErrorBadRequest)?;
-    let (_, event_stream) = sse.new_connection(Some(session_id));
-    SessionActor::create(session_id, MessageSSE::clone(&sse));
+    let connection = sse.new_connection_for_request(&req, Some(session_id));
+    let (connection_id, connection, event_stream) = match connection {
+        Ok(connection) => connection,
+        Err(error) => match error.connect_error() {
+            Some(connect_error) => return Ok(connect_error.to_response()),
+            None => return Err(error::ErrorInternalServerError(error)),
+        },
+    };
+    // The frontend needs the id to report the closed connection, see `message_sse_close_responder`.
+    let _ = connection.send_control(sse::protocol_frame(
+        ProtocolEvent::ConnectionId,
+        &connection_id.to_string(),
+    ));
+    // The actor is kept for a while after the connection has been lost, see `SSEConfig::session_grace`.
+    if sessions::by_session_id().get(session_id).is_none() {
+        SessionActor::create(session_id, MessageSSE::clone(&sse));
+    }
 
-    Ok(HttpResponse::Ok()
-        .insert_header(ContentType(mime::TEXT_EVENT_STREAM))
-        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
-        .streaming(event_stream))
+    Ok(sse::into_response(event_stream))
+}
+
+// ------ message_sse_close_responder ------
+
+// Called by the frontend with `navigator.sendBeacon` when the page is being closed.
+async fn message_sse_close_responder(
+    path: web::Path<(String, String)>,
+    sse: web::Data<MessageSSE>,
+) -> Result<HttpResponse, Error> {
+    let (session_id, connection_id) = path.into_inner();
+    let session_id = session_id.parse().map_err(error::ErrorBadRequest)?;
+    let connection_id = connection_id.parse().map_err(error::ErrorBadRequest)?;
+    sse.remove_closed_connection(&session_id, connection_id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// ------ message_sse_ack_responder ------
+
+// Acknowledges the event sent by `Connection::send_tracked`.
+async fn message_sse_ack_responder(
+    path: web::Path<(String, String, u64)>,
+    sse: web::Data<MessageSSE>,
+) -> Result<HttpResponse, Error> {
+    let (session_id, connection_id, event_id) = path.into_inner();
+    let session_id = session_id.parse().map_err(error::ErrorBadRequest)?;
+    let connection_id = connection_id.parse().map_err(error::ErrorBadRequest)?;
+    if !sse.ack(&session_id, connection_id, event_id) {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    Ok(HttpResponse::NoContent().finish())
 }
 
 // ------ frontend_responder ------
@@ -1,165 +1,3412 @@
 use crate::actor::{sessions, Index};
-use actix_web::web::Bytes;
-use actix_web::{rt, Error};
-use chashmap::CHashMap;
-use futures::Stream;
-use moonlight::SessionId;
-use std::cell::RefCell;
+use actix_web::{rt, web::Bytes, HttpRequest};
+use futures::{Stream, StreamExt};
+#[cfg(feature = "serde")]
+use moonlight::{serde_json, CorId, DownMsgTransporterForSer, Serialize};
+use moonlight::{ProtocolEvent, SessionId};
+use parking_lot::Mutex;
+use smallvec::{smallvec, SmallVec};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::mem;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::task::{Context, Poll};
-use std::time::Duration;
-use tokio::sync::mpsc::{error::SendError, unbounded_channel, UnboundedReceiver, UnboundedSender};
-use tokio::time::{interval_at, Instant};
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::task::JoinHandle;
+use tokio::time::{interval_at, Duration, Instant};
+use uuid::Uuid;
+
+mod ack;
+pub use ack::{AckHandle, AckOutcome};
+
+mod auth;
+pub use auth::{Auth, AuthError, AuthToken};
+
+mod base64;
+
+mod channel;
+use channel::MessageSender;
+pub use channel::{ConnectionKind, Priority, SendError};
+
+mod broker;
+pub use broker::{BroadcastTarget, Broker, BrokerMessage, MemoryBroker, OriginId};
+
+mod chunking;
+use chunking::{split_payload, split_prepared};
+
+mod config;
+pub use config::{
+    Batching, FrameSizeExceeded, FrameSizeLimit, KeepAlive, RateLimit, RateLimitExceeded,
+    ReconnectPolicy, SSEConfig, SSEConfigError,
+};
+
+mod connection;
+mod connection_builder;
+pub use connection::{Connection, ConnectionId, ConnectionInfo};
+pub use connection_builder::ConnectionBuilder;
+
+mod connect_error;
+pub use connect_error::ConnectError;
+
+mod drop_oldest;
+
+mod error;
+pub use error::Error;
+
+mod event_stream;
+pub use event_stream::EventStream;
+
+mod extensions;
+
+mod formatter;
+pub use formatter::{DefaultFrameFormatter, FrameFormatter};
+
+mod groups;
+pub use groups::ConnectionGroup;
+use groups::Groups;
+
+mod handle;
+pub use handle::{SSEHandle, TestSSE};
+
+mod hooks;
+pub use hooks::{DisconnectReason, OnConnect, OnDisconnect};
+
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "serde")]
+pub use json::SendJsonError;
+
+mod lifecycle;
+use lifecycle::Lifecycle;
+pub use lifecycle::SSELifecycleEvent;
+
+mod message;
+pub(crate) use message::protocol_frame;
+pub use message::{frame, EventName, PreparedEvent, BINARY_EVENT_SUFFIX};
+use message::{retry, MessageBody};
+
+mod outbox;
+pub use outbox::{MemoryOutbox, SessionOutbox};
+
+mod pause;
+use pause::Pause;
+
+mod rate_limit;
+use rate_limit::RateLimiter;
+
+mod replacement;
+pub use replacement::{ReplaceOutcome, SessionReplacement};
+
+mod response;
+pub use response::into_response;
+
+mod schedule;
+use schedule::Schedule;
+pub use schedule::ScheduledSend;
+
+mod sink;
+pub use sink::{FrameSink, SinkError};
+
+mod replay_buffer;
+use replay_buffer::ReplayBuffer;
+pub use replay_buffer::ReplayBufferSize;
+
+mod shard_map;
+use shard_map::ShardMap;
+
+mod stats;
+pub use stats::SSEStats;
+use stats::{ConnectionActivity, Counters};
+
+mod topics;
+use topics::Topics;
 
 pub type ShareableSSE = Arc<SSE>;
 
-// ------ Connection ------
+#[cfg(feature = "serde")]
+const DOWN_MSG_EVENT: &str = ProtocolEvent::DownMsg.as_str();
+
+// ------ last_event_id ------
 
-pub struct Connection {
-    remove_session_actor_on_remove: bool,
-    session_id: SessionId,
-    sender: UnboundedSender<Bytes>,
+/// Extracts the id of the last event received by the client.
+///
+/// Browsers send it in the `Last-Event-ID` header when `EventSource` reconnects,
+/// `ReconnectingEventSource` passes it in the `lastEventId` query parameter.
+pub fn last_event_id(req: &HttpRequest) -> Option<u64> {
+    let from_header = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|last_event_id| last_event_id.to_str().ok());
+
+    let from_query = || {
+        req.query_string()
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("lastEventId="))
+    };
+
+    from_header.or_else(from_query)?.trim().parse().ok()
 }
 
-impl Connection {
-    fn new(session_id: Option<SessionId>) -> (Arc<Connection>, EventStream) {
-        let (sender, receiver) = unbounded_channel();
-        let connection = Arc::new(Self {
-            remove_session_actor_on_remove: session_id.is_some(),
-            session_id: session_id.unwrap_or_else(SessionId::new),
-            sender,
-        });
-        (connection, EventStream(receiver))
-    }
+// ------ last_known_epoch ------
 
-    fn session_id(&self) -> SessionId {
-        self.session_id
-    }
+/// Extracts the [SSE::epoch] received by the client from the `epoch` query parameter,
+/// see [ConnectionBuilder::last_known_epoch].
+pub fn last_known_epoch(req: &HttpRequest) -> Option<u64> {
+    req.query_string()
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("epoch="))?
+        .parse()
+        .ok()
+}
 
-    pub fn send(&self, event: &str, data: &str) -> Result<(), SendError<Bytes>> {
-        let message = Bytes::from(["event: ", event, "\n", "data: ", data, "\n\n"].concat());
-        self.sender.send(message)
-    }
+// ------ BroadcastReport ------
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BroadcastReport {
+    /// Connections that have accepted the event.
+    pub delivered: usize,
+    /// Sessions with at least one connection that has refused the event.
+    pub failed: Vec<SessionId>,
 }
 
-// ------ EventStream ------
+// ------ SendOutcome ------
 
-pub struct EventStream(UnboundedReceiver<Bytes>);
+/// Returned by [send](ShareableSSEMethods::send) when the event hasn't been refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// At least one connection of the session has accepted the event.
+    Delivered,
+    /// The session isn't connected, the event waits in [SSEConfig::outbox].
+    Stored,
+}
 
-impl Stream for EventStream {
-    type Item = Result<Bytes, Error>;
+// ------ BroadcastToErrors ------
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match Pin::new(&mut self.0).poll_recv(cx) {
-            Poll::Ready(Some(bytes)) => Poll::Ready(Some(Ok(bytes))),
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
-        }
+#[derive(Debug, Default)]
+pub struct BroadcastToErrors {
+    /// Sessions without a registered connection.
+    pub not_connected: Vec<SessionId>,
+    /// Sessions whose connection refused the message.
+    pub send_failed: Vec<(SessionId, SendError)>,
+}
+
+impl BroadcastToErrors {
+    pub fn is_empty(&self) -> bool {
+        self.not_connected.is_empty() && self.send_failed.is_empty()
     }
 }
 
+const SERVER_RESTARTING_EVENT: &str = ProtocolEvent::ServerRestarting.as_str();
+const PRESENCE_JOINED_EVENT: &str = ProtocolEvent::PresenceJoined.as_str();
+const PRESENCE_LEFT_EVENT: &str = ProtocolEvent::PresenceLeft.as_str();
+
 // ------ SSE ------
 
 pub struct SSE {
-    connections: CHashMap<SessionId, Arc<Connection>>,
+    // The only long-lived strong references to connections, so a removed connection is dropped
+    // once its callers release it. Sessions, topics, groups and scheduled sends store ids
+    // and look the connections up when they're used.
+    connections: ShardMap<ConnectionId, Arc<Connection>>,
+    sessions: ShardMap<SessionId, SmallVec<[ConnectionId; 2]>>,
+    // sessions without connections kept for a while so the client can reconnect
+    disconnected_sessions: ShardMap<SessionId, Instant>,
+    // session actors removed by the remover when the session doesn't reconnect in time
+    pending_session_actor_removals: ShardMap<SessionId, Instant>,
+    config: SSEConfig,
+    replay_buffers: ShardMap<SessionId, Arc<Mutex<ReplayBuffer>>>,
+    topics: Arc<Topics>,
+    groups: Groups,
+    schedule: Arc<Schedule>,
+    closed: AtomicBool,
+    counters: Arc<Counters>,
+    remover: Mutex<Option<JoinHandle<()>>>,
+    origin: OriginId,
+    broker_relay: Mutex<Option<JoinHandle<()>>>,
+    epoch: u64,
+    lifecycle: Lifecycle,
 }
 
 impl SSE {
     pub fn start() -> ShareableSSE {
+        Self::start_with(SSEConfig::default()).expect("default SSE config is invalid")
+    }
+
+    pub fn start_with(config: impl Into<SSEConfig>) -> Result<ShareableSSE, SSEConfigError> {
+        let config = config.into();
+        config.validate()?;
+
         let sse = SSE {
-            connections: CHashMap::new(),
+            connections: ShardMap::new(),
+            sessions: ShardMap::new(),
+            disconnected_sessions: ShardMap::new(),
+            pending_session_actor_removals: ShardMap::new(),
+            config,
+            replay_buffers: ShardMap::new(),
+            topics: Arc::default(),
+            groups: Groups::default(),
+            schedule: Arc::default(),
+            closed: AtomicBool::new(false),
+            counters: Arc::default(),
+            remover: Mutex::new(None),
+            origin: OriginId::new(),
+            broker_relay: Mutex::new(None),
+            epoch: Uuid::new_v4().as_u128() as u64,
+            lifecycle: Lifecycle::default(),
         };
         let this = Arc::new(sse);
         this.spawn_connection_remover();
-        this
+        this.spawn_broker_relay();
+        Ok(this)
     }
-}
 
-// ------ ShareableSSEMethods ------
+    pub fn stats(&self) -> SSEStats {
+        let mut max_idle_duration = Duration::ZERO;
+        let mut max_queued_frames = 0;
+        for connection in self.connections_snapshot() {
+            max_idle_duration = max_idle_duration.max(connection.idle_duration());
+            max_queued_frames = max_queued_frames.max(connection.queued_frames());
+        }
+        SSEStats {
+            epoch: self.epoch,
+            ..self
+                .counters
+                .stats(self.connections.len(), max_idle_duration, max_queued_frames)
+        }
+    }
 
-pub trait ShareableSSEMethods {
-    fn spawn_connection_remover(&self);
+    /// Random id of this `SSE` instance, sent to every new connection in the `epoch` event.
+    /// A client reconnecting with a different epoch has been connected
+    /// to a previous server process, see [ConnectionBuilder::last_known_epoch].
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
 
-    fn new_connection(&self, session_id: Option<SessionId>) -> (Arc<Connection>, EventStream);
+    /// Cheap enough to be called from an admin page, the connection maps aren't locked
+    /// while the info is collected.
+    pub fn connection_info(&self) -> Vec<ConnectionInfo> {
+        self.connections_snapshot()
+            .iter()
+            .map(|connection| connection.info())
+            .collect()
+    }
 
-    fn broadcast(&self, event: &str, data: &str) -> Result<(), Vec<SendError<Bytes>>>;
+    pub fn connection_count(&self) -> usize {
+        self.connections.len()
+    }
 
-    fn send(
+    pub fn is_connected(&self, session_id: &SessionId) -> bool {
+        self.sessions.contains_key(session_id)
+    }
+
+    pub fn connection(&self, connection_id: ConnectionId) -> Option<Arc<Connection>> {
+        self.connections.get(&connection_id)
+    }
+
+    /// Calls `f` for each connection.
+    /// The connections are collected first, so `f` may call other `SSE` methods.
+    pub fn for_each_connection(&self, mut f: impl FnMut(&SessionId, &Arc<Connection>)) {
+        for connection in self.connections_snapshot() {
+            f(&connection.session_id(), &connection);
+        }
+    }
+
+    /// Ids of the sessions with at least one connection.
+    pub fn session_ids(&self) -> Vec<SessionId> {
+        self.sessions.keys()
+    }
+
+    /// Sessions announced by `presence_joined` and not yet by `presence_left`,
+    /// i.e. connected sessions and sessions that may still reconnect.
+    /// Send it to a new client so it can apply the presence events, see [SSEConfig::presence].
+    pub fn present_sessions(&self) -> Vec<SessionId> {
+        let mut session_ids = self.sessions.keys();
+        session_ids.extend(self.disconnected_sessions.keys());
+        // A reconnecting session may be in both maps for a moment.
+        session_ids.sort_unstable();
+        session_ids.dedup();
+        session_ids
+    }
+
+    /// Connections, disconnections, broadcasts and connection remover sweeps, e.g. for metrics.
+    /// Each subscriber receives all events published after it has subscribed. Up to 1024 events
+    /// wait for a slow subscriber, older events are replaced by [SSELifecycleEvent::Lagged].
+    /// Nothing is published when there aren't any subscribers.
+    pub fn events(&self) -> Pin<Box<dyn Stream<Item = SSELifecycleEvent> + Send>> {
+        self.lifecycle.subscribe()
+    }
+
+    pub fn topic_subscriber_count(&self, topic: &str) -> usize {
+        self.topics.subscriber_count(topic)
+    }
+
+    // Reserved names are rejected by the public broadcast methods.
+    pub(crate) fn broadcast_protocol_event(
+        &self,
+        event: ProtocolEvent,
+        data: &str,
+    ) -> Result<(), Vec<Error>> {
+        let event = event.as_str();
+        self.forward(event, data, || BroadcastTarget::All);
+        self.broadcast_message(&[], event, data)
+    }
+
+    fn message_body<'a>(&'a self, event: &'a str, data: &'a str) -> Result<MessageBody<'a>, Error> {
+        MessageBody::new(self.config.formatter.as_deref(), event, data)
+    }
+
+    fn protocol_message_body(&self, event: ProtocolEvent, data: &str) -> Bytes {
+        MessageBody::protocol(self.config.formatter.as_deref(), event, data).message(None)
+    }
+
+    // Allows to call user code for each connection without holding the map locks.
+    fn connections_snapshot(&self) -> Vec<Arc<Connection>> {
+        self.connections.values()
+    }
+
+    fn broadcast_message(
+        &self,
+        excluded: &[SessionId],
+        event: &str,
+        data: &str,
+    ) -> Result<(), Vec<Error>> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("sse_broadcast", event, data_len = data.len()).entered();
+        match split_payload(self.config.max_frame_size, event, data) {
+            Err(error) => Err(vec![error]),
+            Ok(None) => self.broadcast_event(excluded, &self.message_body(event, data)?),
+            Ok(Some(chunks)) => send_chunks(&chunks, |event, data| {
+                self.broadcast_event(excluded, &self.message_body(event, data)?)
+            }),
+        }
+    }
+
+    fn broadcast_event(
+        &self,
+        excluded: &[SessionId],
+        body: &MessageBody,
+    ) -> Result<(), Vec<Error>> {
+        let (_, errors) = self.broadcast_body(excluded, body);
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(errors
+            .into_iter()
+            .map(|(session_id, error)| Error::from_send_error(session_id, error))
+            .collect())
+    }
+
+    fn send_message(
         &self,
         session_id: &SessionId,
         event: &str,
         data: &str,
-    ) -> Option<Result<(), SendError<Bytes>>>;
+    ) -> Result<SendOutcome, Error> {
+        match split_payload(self.config.max_frame_size, event, data)? {
+            None => self.send_event(session_id, event, data),
+            Some(chunks) => chunks
+                .iter()
+                .try_fold(SendOutcome::Delivered, |_, (event, data)| {
+                    self.send_event(session_id, event, data)
+                }),
+        }
+    }
 
-    // @TODO why is it a dead code since Rust 1.78.0?
-    #[allow(dead_code)]
-    fn remove_connection(&self, session_id: &SessionId);
-}
+    fn send_event(
+        &self,
+        session_id: &SessionId,
+        event: &str,
+        data: &str,
+    ) -> Result<SendOutcome, Error> {
+        let Some(results) = self.send_body_to_session(session_id, &self.message_body(event, data)?)
+        else {
+            if let Some(outbox) = &self.config.outbox {
+                outbox.push(*session_id, event, data);
+                return Ok(SendOutcome::Stored);
+            }
+            #[cfg(feature = "tracing")]
+            tracing::debug!(%session_id, event, "SSE message not sent, session not found");
+            return Err(Error::SessionNotFound {
+                session_id: *session_id,
+            });
+        };
+        #[cfg(feature = "tracing")]
+        {
+            let failed = results.iter().filter(|result| result.is_err()).count();
+            tracing::debug!(
+                %session_id,
+                event,
+                data_len = data.len(),
+                sent = results.len() - failed,
+                failed,
+                "SSE message sent"
+            );
+        }
+        first_success(results)
+            .map(|()| SendOutcome::Delivered)
+            .map_err(|error| Error::from_send_error(*session_id, error))
+    }
 
-impl ShareableSSEMethods for ShareableSSE {
-    fn spawn_connection_remover(&self) {
-        let this = self.clone();
-        rt::spawn(async move {
-            let mut interval = interval_at(Instant::now(), Duration::from_secs(10));
-            loop {
-                interval.tick().await;
-                this.connections.retain(|session_id, connection| {
-                    let active = connection.send("ping", "").is_ok();
-                    if !active && connection.remove_session_actor_on_remove {
-                        if let Some(session_actor) = sessions::by_session_id().get(session_id) {
-                            session_actor.remove();
-                        }
+    // Messages are sent without holding the map locks so `send` calls aren't blocked.
+    // Returns the number of connections that have accepted the message and the errors.
+    fn broadcast_body(
+        &self,
+        excluded: &[SessionId],
+        body: &MessageBody,
+    ) -> (usize, Vec<(SessionId, SendError)>) {
+        // The replay buffer sends the message to all connections of the session at once.
+        let mut replayed_sessions = HashSet::new();
+        let receivers = self
+            .connections_snapshot()
+            .into_iter()
+            .filter(|connection| {
+                let session_id = connection.session_id();
+                if excluded.contains(&session_id) {
+                    return false;
+                }
+                connection.replay_buffer().is_none() || replayed_sessions.insert(session_id)
+            })
+            .collect::<Vec<_>>();
+
+        // Sending is only a push to the connection queues, spawning threads would cost more.
+        let (delivered, errors) = self.send_body_to_receivers(&receivers, body);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            receivers = receivers.len(),
+            failed = errors.len(),
+            "SSE broadcast sent"
+        );
+        self.broadcast_completed(delivered, errors.len());
+        (delivered, errors)
+    }
+
+    fn broadcast_completed(&self, delivered: usize, failed: usize) {
+        self.lifecycle
+            .publish(|| SSELifecycleEvent::BroadcastCompleted { delivered, failed });
+    }
+
+    fn send_body_to_receivers(
+        &self,
+        receivers: &[Arc<Connection>],
+        body: &MessageBody,
+    ) -> (usize, Vec<(SessionId, SendError)>) {
+        let mut delivered = 0;
+        let mut errors = Vec::new();
+        for connection in receivers {
+            let results = match connection.replay_buffer() {
+                Some(replay_buffer) => self.send_to_all(replay_buffer, body),
+                None => vec![connection.send_body(body)],
+            };
+            let session_id = connection.session_id();
+            for result in results {
+                match result {
+                    Ok(()) => delivered += 1,
+                    Err(error) => errors.push((session_id, error)),
+                }
+            }
+        }
+        (delivered, errors)
+    }
+
+    fn broadcast_to_event<'a>(
+        &self,
+        session_ids: impl IntoIterator<Item = &'a SessionId>,
+        event: &str,
+        data: &str,
+    ) -> Result<(), Vec<Error>> {
+        let mut errors = self.broadcast_to_body(session_ids, &self.message_body(event, data)?);
+        if let Some(outbox) = &self.config.outbox {
+            for session_id in errors.not_connected.drain(..) {
+                outbox.push(session_id, event, data);
+            }
+        }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        let not_connected = errors
+            .not_connected
+            .into_iter()
+            .map(|session_id| Error::SessionNotFound { session_id });
+        let send_failed = errors
+            .send_failed
+            .into_iter()
+            .map(|(session_id, error)| Error::from_send_error(session_id, error));
+        Err(not_connected.chain(send_failed).collect())
+    }
+
+    fn broadcast_to_body<'a>(
+        &self,
+        session_ids: impl IntoIterator<Item = &'a SessionId>,
+        body: &MessageBody,
+    ) -> BroadcastToErrors {
+        let mut errors = BroadcastToErrors::default();
+        let mut receivers = 0;
+        for session_id in session_ids {
+            let Some(results) = self.send_body_to_session(session_id, body) else {
+                errors.not_connected.push(*session_id);
+                continue;
+            };
+            receivers += results.len();
+            errors.send_failed.extend(
+                results
+                    .into_iter()
+                    .filter_map(Result::err)
+                    .map(|error| (*session_id, error)),
+            );
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            receivers,
+            failed = errors.send_failed.len(),
+            not_connected = errors.not_connected.len(),
+            "SSE message sent to sessions"
+        );
+        let failed = errors.send_failed.len();
+        self.broadcast_completed(receivers - failed, failed);
+        errors
+    }
+
+    // Sends the body to all connections of the session,
+    // returns `None` if the session doesn't have any connection.
+    fn send_body_to_session(
+        &self,
+        session_id: &SessionId,
+        body: &MessageBody,
+    ) -> Option<Vec<Result<(), SendError>>> {
+        let connection_ids = self.sessions.get(session_id)?;
+        let connections = connection_ids
+            .iter()
+            .filter_map(|connection_id| self.connection(*connection_id))
+            .collect::<SmallVec<[_; 2]>>();
+        if let Some(replay_buffer) = connections.first()?.replay_buffer() {
+            return Some(self.send_to_all(replay_buffer, body));
+        }
+        let results = connections
+            .iter()
+            .map(|connection| connection.send_body(body))
+            .collect();
+        Some(results)
+    }
+
+    fn send_to_all(
+        &self,
+        replay_buffer: &Mutex<ReplayBuffer>,
+        body: &MessageBody,
+    ) -> Vec<Result<(), SendError>> {
+        let results = replay_buffer.lock().send(body);
+        for result in &results {
+            self.counters.message_sent(result.is_ok());
+        }
+        results
+    }
+
+    fn create_connection(
+        self: &Arc<Self>,
+        builder: ConnectionBuilder,
+    ) -> Result<(ConnectionId, Arc<Connection>, EventStream), Error> {
+        let (connection_id, connection, event_stream) =
+            self.create_connection_with_sink(builder, None)?;
+        let event_stream = event_stream.expect("connection without a sink has an EventStream");
+        Ok((connection_id, connection, event_stream))
+    }
+
+    // The connection gets an `EventStream` only when it doesn't have a `sink`.
+    fn create_connection_with_sink(
+        self: &Arc<Self>,
+        builder: ConnectionBuilder,
+        sink: Option<Arc<dyn FrameSink>>,
+    ) -> Result<(ConnectionId, Arc<Connection>, Option<EventStream>), Error> {
+        let ConnectionBuilder {
+            sse: _,
+            session_id,
+            last_event_id,
+            last_known_epoch,
+            connection_kind,
+            keep_session_actor,
+            extensions,
+            init,
+        } = builder;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "sse_new_connection",
+            session_id = session_id.map(tracing::field::display)
+        )
+        .entered();
+
+        if self.closed.load(Ordering::Relaxed) {
+            return Err(Error::ShuttingDown);
+        }
+        if let Some(max_connections) = self.config.max_connections {
+            let connected_session =
+                session_id.is_some_and(|session_id| self.sessions.contains_key(&session_id));
+            if !connected_session && self.connections.len() >= max_connections {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(max_connections, "SSE connection limit reached");
+                return Err(Error::ConnectionLimitReached {
+                    max_connections,
+                    retry_after: self.config.connection_limit_retry_after,
+                });
+            }
+        }
+
+        if let Some(session_id) = session_id {
+            match &self.config.reconnect_policy {
+                ReconnectPolicy::KeepAll => (),
+                ReconnectPolicy::ReplaceOld { superseded_event } => {
+                    self.close_session_connections(&session_id, superseded_event.as_deref());
+                }
+                ReconnectPolicy::RejectNew => {
+                    if self.sessions.contains_key(&session_id) {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("SSE connection rejected, the session is connected");
+                        return Err(Error::ConnectionRejected { session_id });
                     }
-                    active
+                }
+            }
+        }
+
+        let connection_id = ConnectionId::new();
+        let (control_sender, sender, high_priority_sender, receivers) = match sink {
+            Some(sink) => (
+                Arc::clone(&sink),
+                MessageSender::Sink(Arc::clone(&sink)),
+                MessageSender::Sink(sink),
+                None,
+            ),
+            None => {
+                let (control_sender, control_receiver) = unbounded_channel::<Bytes>();
+                let (sender, receiver) = channel::channel(connection_kind, &self.counters);
+                let (high_priority_sender, high_priority_receiver) =
+                    channel::channel(connection_kind.high_priority(), &self.counters);
+                (
+                    Arc::new(control_sender) as Arc<dyn FrameSink>,
+                    sender,
+                    high_priority_sender,
+                    Some((control_receiver, high_priority_receiver, receiver)),
+                )
+            }
+        };
+
+        // Broadcasts are held until the initial events have been sent.
+        let sender = if init.is_some() {
+            sender.held()
+        } else {
+            sender
+        };
+
+        let epoch = self.epoch.to_string();
+        let _ = control_sender.try_send(self.protocol_message_body(ProtocolEvent::Epoch, &epoch));
+        if last_known_epoch.is_some_and(|last_known_epoch| last_known_epoch != self.epoch) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                last_known_epoch,
+                "SSE client state reset, the server has restarted"
+            );
+            let _ =
+                control_sender.try_send(self.protocol_message_body(ProtocolEvent::StateReset, ""));
+        }
+        if let Some(milliseconds) = self.config.initial_retry {
+            let _ = control_sender.try_send(retry(milliseconds));
+        }
+
+        let replay_buffer = match session_id {
+            Some(session_id) if self.config.replay_buffer_size.0 > 0 => {
+                let replay_buffer = self.replay_buffers.get(&session_id);
+                let replay_buffer = replay_buffer.unwrap_or_else(|| {
+                    let replay_buffer = Arc::new(Mutex::new(ReplayBuffer::new(
+                        self.config.replay_buffer_size.0,
+                        self.config.formatter.clone(),
+                    )));
+                    self.replay_buffers
+                        .insert(session_id, Arc::clone(&replay_buffer));
+                    replay_buffer
                 });
+                // Missed messages are sent before any new message because
+                // all session messages go through the locked replay buffer.
+                replay_buffer
+                    .lock()
+                    .replay(last_event_id, connection_id, sender.clone());
+                Some(replay_buffer)
+            }
+            _ => None,
+        };
+
+        let activity = Arc::<ConnectionActivity>::default();
+        let pause = Arc::new(Pause::new(
+            self.config.pause_buffer_size,
+            Arc::clone(&self.counters),
+        ));
+        let connection = Connection::new(
+            connection_id,
+            session_id,
+            session_id.is_some() && !keep_session_actor,
+            extensions,
+            control_sender,
+            sender,
+            high_priority_sender,
+            replay_buffer,
+            Arc::clone(&self.topics),
+            Arc::clone(&self.counters),
+            self.config.batching.is_some(),
+            Arc::clone(&activity),
+            Arc::clone(&pause),
+            self.config.formatter.clone(),
+            self.config.max_frame_size,
+            self.config.ack_timeout,
+            self.epoch,
+            Arc::downgrade(self),
+        );
+        // Stored messages are sent before the connection starts receiving live messages.
+        if let (Some(session_id), Some(outbox)) = (session_id, &self.config.outbox) {
+            outbox::flush(&**outbox, session_id, &connection);
+        }
+        let session_id = connection.session_id();
+        self.connections
+            .insert(connection_id, Arc::clone(&connection));
+        let was_disconnected = self.disconnected_sessions.remove(&session_id).is_some();
+        self.pending_session_actor_removals.remove(&session_id);
+        let mut has_other_connections = false;
+        self.sessions.upsert(
+            session_id,
+            || smallvec![connection_id],
+            |connection_ids| {
+                has_other_connections = true;
+                connection_ids.push(connection_id);
+            },
+        );
+        // `init` runs once the connection is registered, so broadcasts issued meanwhile
+        // aren't lost, they're held and written after the initial events.
+        if let Some(init) = init {
+            for (event, data) in init(&connection) {
+                let result = Error::check_event_name(&event)
+                    .and_then(|()| connection.send_initial(&event, &data));
+                if let Err(_error) = result {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %_error, event, "SSE initial event not sent");
+                }
             }
+            connection.release_held_messages();
+        }
+        let reconnect = was_disconnected || has_other_connections;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%connection_id, reconnect, "SSE connection created");
+        if !reconnect {
+            self.broadcast_presence(PRESENCE_JOINED_EVENT, &session_id);
+        }
+        self.lifecycle
+            .publish(|| SSELifecycleEvent::Connected { session_id });
+        if let Some(on_connect) = &self.config.on_connect {
+            on_connect(session_id, Arc::clone(&connection), reconnect);
+        }
+        let Some((control_receiver, high_priority_receiver, receiver)) = receivers else {
+            return Ok((connection_id, connection, None));
+        };
+        let rate_limiter = self.config.rate_limit.map(|rate_limit| {
+            RateLimiter::new(
+                rate_limit,
+                Arc::clone(&activity),
+                Arc::clone(&self.counters),
+            )
         });
+        let event_stream = EventStream::new(
+            control_receiver,
+            high_priority_receiver,
+            receiver,
+            rate_limiter,
+            activity,
+            pause,
+            Arc::downgrade(self),
+            connection_id,
+            self.config.batching,
+        );
+        Ok((connection_id, connection, Some(event_stream)))
     }
 
-    fn new_connection(&self, session_id: Option<SessionId>) -> (Arc<Connection>, EventStream) {
-        let (connection, event_stream) = Connection::new(session_id);
-        self.connections
-            .insert(connection.session_id(), connection.clone());
-        (connection, event_stream)
+    // Returns the number of removed connections.
+    fn ping_connections(&self, shard: usize) -> usize {
+        self.send_keep_alives(shard);
+        self.reap_connections(shard)
     }
 
-    fn broadcast(&self, event: &str, data: &str) -> Result<(), Vec<SendError<Bytes>>> {
-        let errors = RefCell::new(Vec::new());
-        self.connections.retain(|_, connection| {
-            if let Err(error) = connection.send(event, data) {
-                errors.borrow_mut().push(error);
+    // Connections with recently written events are alive, they don't need the keep-alive.
+    // A closed connection is always pinged so its keep-alive fails even when it was busy.
+    fn send_keep_alives(&self, shard: usize) {
+        let keep_alive_interval = self.config.keep_alive_interval;
+        for connection in self.connections.shard_values(shard) {
+            let busy = connection
+                .last_send_at()
+                .is_some_and(|last_send_at| last_send_at.elapsed() < keep_alive_interval);
+            if busy && !connection.is_closed() {
+                connection.keep_alive_not_needed();
+            } else {
+                connection.send_keep_alive(&self.config.keep_alive);
             }
-            true
+        }
+    }
+
+    // Removes connections that haven't accepted `keep_alive_failure_threshold` keep-alives
+    // in a row, returns the number of removed connections.
+    fn reap_connections(&self, shard: usize) -> usize {
+        let failure_threshold = self.config.keep_alive_failure_threshold;
+        let removed_connections = self.remove_shard_connections(shard, |connection| {
+            connection.failed_keep_alives() >= failure_threshold
         });
-        let errors = errors.into_inner();
-        if errors.is_empty() {
-            return Ok(());
+        let removed = removed_connections.len();
+        // Cleanup and callbacks run after the map shards are unlocked.
+        for connection in removed_connections {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                session_id = %connection.session_id(),
+                connection_id = %connection.id(),
+                "SSE connection removed, keep-alive ping failed"
+            );
+            self.counters.connection_removed_by_ping();
+            self.connection_removed(&connection, DisconnectReason::PingFailed);
         }
-        Err(errors)
+        removed
     }
 
-    fn send(
+    // The shard is locked only to take the snapshot and to remove the connections,
+    // `remove` runs without the lock so the shard's `send`s and `new_connection`s don't wait.
+    fn remove_shard_connections(
         &self,
-        session_id: &SessionId,
-        event: &str,
-        data: &str,
-    ) -> Option<Result<(), SendError<Bytes>>> {
-        // @TODO Last-Event-Id
+        shard: usize,
+        remove: impl Fn(&Connection) -> bool,
+    ) -> Vec<Arc<Connection>> {
         self.connections
-            .get(session_id)
-            .map(|connection| connection.send(event, data))
+            .shard_values(shard)
+            .into_iter()
+            .filter(|connection| remove(connection))
+            .filter_map(|connection| self.connections.remove(&connection.id()))
+            .collect()
     }
 
-    fn remove_connection(&self, session_id: &SessionId) {
-        let connection = self.connections.remove(session_id);
+    // Removes the session connections with a closed `EventStream` or sink
+    // without waiting for the connection remover.
+    fn remove_closed_session_connections(&self, session_id: &SessionId) {
+        let connection_ids = self.sessions.get(session_id).unwrap_or_default();
+        for connection_id in connection_ids {
+            let closed = self
+                .connection(connection_id)
+                .is_some_and(|connection| connection.is_closed());
+            if !closed {
+                continue;
+            }
+            if let Some(connection) = self.connections.remove(&connection_id) {
+                self.connection_removed(&connection, DisconnectReason::StreamDropped);
+            }
+        }
+    }
+
+    pub(crate) fn remove_dropped_connection(&self, connection_id: ConnectionId) {
+        if let Some(connection) = self.connections.remove(&connection_id) {
+            self.connection_removed(&connection, DisconnectReason::StreamDropped);
+        }
+    }
 
-        if let Some(connection) = connection {
+    // Has to be called when the connection is no longer in `connections`.
+    fn connection_removed(&self, connection: &Connection, reason: DisconnectReason) {
+        let session_id = connection.session_id();
+        // Before a removed session drops its replay buffer.
+        connection.close_acks();
+        if let Some(replay_buffer) = connection.replay_buffer() {
+            replay_buffer.lock().remove_sender(connection.id());
+        }
+        let mut last_connection = false;
+        self.sessions.alter(session_id, |connection_ids| {
+            let mut connection_ids = connection_ids?;
+            connection_ids.retain(|connection_id| *connection_id != connection.id());
+            last_connection = connection_ids.is_empty();
+            (!last_connection).then_some(connection_ids)
+        });
+        if last_connection {
+            if matches!(
+                reason,
+                DisconnectReason::PingFailed
+                    | DisconnectReason::StreamDropped
+                    | DisconnectReason::Superseded
+            ) {
+                // The client may reconnect soon, the remover removes the session
+                // once `session_grace` has elapsed.
+                self.disconnected_sessions
+                    .insert(session_id, Instant::now());
+            } else {
+                self.session_removed(&session_id);
+            }
             if connection.remove_session_actor_on_remove {
-                if let Some(session_actor) = sessions::by_session_id().get(session_id) {
-                    session_actor.remove();
+                let may_reconnect = matches!(
+                    reason,
+                    DisconnectReason::PingFailed
+                        | DisconnectReason::IdleTimeout
+                        | DisconnectReason::StreamDropped
+                        | DisconnectReason::Superseded
+                );
+                if may_reconnect && !self.config.session_grace.is_zero() {
+                    self.pending_session_actor_removals
+                        .insert(session_id, Instant::now());
+                } else {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(%session_id, ?reason, "removing session actor");
+                    remove_session_actor(&session_id);
+                }
+            }
+        }
+        self.lifecycle
+            .publish(|| SSELifecycleEvent::Disconnected { session_id, reason });
+        if let Some(on_disconnect) = &self.config.on_disconnect {
+            on_disconnect(session_id, reason);
+        }
+    }
+
+    // Only the caller that removes the connection from the map reports it, e.g. not the remover.
+    fn close_connection(
+        &self,
+        connection_id: ConnectionId,
+        final_event: Option<(&str, &str)>,
+    ) -> bool {
+        let Some(connection) = self.connections.remove(&connection_id) else {
+            return false;
+        };
+        if let Some((event, data)) = final_event {
+            let _ = connection.send(event, data);
+        }
+        connection.close_senders();
+        self.connection_removed(&connection, DisconnectReason::Closed);
+        true
+    }
+
+    // The session state is kept for the new connection.
+    fn close_session_connections(&self, session_id: &SessionId, superseded_event: Option<&str>) {
+        let connection_ids = self.sessions.get(session_id).unwrap_or_default();
+
+        for connection_id in connection_ids {
+            if let Some(connection) = self.connections.remove(&connection_id) {
+                if let Some(superseded_event) = superseded_event {
+                    let _ = connection.send(superseded_event, "");
+                }
+                connection.close_senders();
+                self.connection_removed(&connection, DisconnectReason::Superseded);
+            }
+        }
+    }
+
+    fn remove_expired_session_actors(&self) {
+        let session_grace = self.config.session_grace;
+        let expired_sessions = self
+            .pending_session_actor_removals
+            .retain(|_, disconnected_at| disconnected_at.elapsed() < session_grace);
+        for (session_id, _) in expired_sessions {
+            if !self.sessions.contains_key(&session_id) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(%session_id, "removing session actor, session grace has elapsed");
+                remove_session_actor(&session_id);
+            }
+        }
+    }
+
+    fn spawn_broker_relay(self: &Arc<Self>) {
+        let Some(broker) = &self.config.broker else {
+            return;
+        };
+        let mut messages = broker.subscribe();
+        // The task mustn't keep `SSE` alive.
+        let weak_this = Arc::downgrade(self);
+        let broker_relay = rt::spawn(async move {
+            while let Some(message) = messages.next().await {
+                let Some(this) = weak_this.upgrade() else {
+                    break;
+                };
+                // The own messages have been sent to the local connections already.
+                if message.origin != this.origin {
+                    this.relay(&message);
                 }
             }
+        });
+        *self.broker_relay.lock() = Some(broker_relay);
+    }
+
+    fn forward(&self, event: &str, data: &str, target: impl FnOnce() -> BroadcastTarget) {
+        if let Some(broker) = &self.config.broker {
+            broker.publish(BrokerMessage {
+                origin: self.origin,
+                event: event.to_owned(),
+                data: data.to_owned(),
+                target: target(),
+            });
+        }
+    }
+
+    // Sessions connected to other instances are skipped, so they aren't stored in the outbox.
+    fn relay(&self, message: &BrokerMessage) {
+        let (event, data) = (message.event.as_str(), message.data.as_str());
+        let local_sessions = |session_ids: &[SessionId]| {
+            session_ids
+                .iter()
+                .filter(|session_id| self.sessions.contains_key(session_id))
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        let _ = match &message.target {
+            BroadcastTarget::All => self.broadcast_message(&[], event, data),
+            BroadcastTarget::Except(excluded) => self.broadcast_message(excluded, event, data),
+            BroadcastTarget::Sessions(session_ids) => {
+                self.broadcast_to_local(&local_sessions(session_ids), event, data)
+            }
+            BroadcastTarget::Topic(topic) => {
+                self.broadcast_to_local(&self.topics.subscribers(topic), event, data)
+            }
+        };
+    }
+
+    fn broadcast_to_local<'a>(
+        &self,
+        session_ids: impl IntoIterator<Item = &'a SessionId>,
+        event: &str,
+        data: &str,
+    ) -> Result<(), Vec<Error>> {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("sse_broadcast_to", event, data_len = data.len()).entered();
+        match split_payload(self.config.max_frame_size, event, data) {
+            Err(error) => Err(vec![error]),
+            Ok(None) => self.broadcast_to_event(session_ids, event, data),
+            Ok(Some(chunks)) => {
+                let session_ids = session_ids.into_iter().collect::<Vec<_>>();
+                send_chunks(&chunks, |event, data| {
+                    self.broadcast_to_event(session_ids.iter().copied(), event, data)
+                })
+            }
+        }
+    }
+
+    fn session_removed(&self, session_id: &SessionId) {
+        self.disconnected_sessions.remove(session_id);
+        self.replay_buffers.remove(session_id);
+        self.topics.unsubscribe_all(session_id);
+        self.groups.remove_session(session_id);
+        self.schedule.remove_session(session_id);
+        self.broadcast_presence(PRESENCE_LEFT_EVENT, session_id);
+    }
+
+    // The session doesn't receive its own presence events.
+    fn broadcast_presence(&self, event: &str, session_id: &SessionId) {
+        if !self.config.presence {
+            return;
+        }
+        let data = session_id.to_string();
+        let excluded = [*session_id];
+        self.forward(event, &data, || BroadcastTarget::Except(excluded.to_vec()));
+        let _ = self.broadcast_message(&excluded, event, &data);
+    }
+}
+
+fn remove_session_actor(session_id: &SessionId) {
+    if let Some(session_actor) = sessions::by_session_id().get(session_id) {
+        session_actor.remove();
+    }
+}
+
+impl Drop for SSE {
+    fn drop(&mut self) {
+        if let Some(remover) = self.remover.get_mut().take() {
+            remover.abort();
+        }
+        if let Some(broker_relay) = self.broker_relay.get_mut().take() {
+            broker_relay.abort();
         }
     }
 }
+
+// Returns `Ok` if at least one connection of the session has accepted the message.
+fn first_success(results: Vec<Result<(), SendError>>) -> Result<(), SendError> {
+    let mut last_error = None;
+    for result in results {
+        match result {
+            Ok(()) => return Ok(()),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error.unwrap_or(SendError::ConnectionClosed(Bytes::new())))
+}
+
+// The deprecated `send_legacy` methods return `None` for sessions without connections.
+fn legacy_send_result(
+    result: Result<SendOutcome, Error>,
+    event: &str,
+    data: &str,
+) -> Option<Result<(), SendError>> {
+    match result {
+        Ok(SendOutcome::Delivered) => Some(Ok(())),
+        Ok(SendOutcome::Stored) | Err(Error::SessionNotFound { .. }) => None,
+        Err(error) => Some(Err(
+            error.into_send_error(frame(event, data).unwrap_or_default())
+        )),
+    }
+}
+
+// Errors without a session mean that nothing has been sent,
+// so the deprecated `broadcast*_legacy` methods report them for all target sessions.
+fn legacy_send_errors(
+    errors: Vec<Error>,
+    target_sessions: impl FnOnce() -> Vec<SessionId>,
+    message: impl FnOnce() -> Bytes,
+) -> Vec<(SessionId, SendError)> {
+    if errors.is_empty() {
+        return Vec::new();
+    }
+    let message = message();
+    if errors.iter().any(|error| error.session_id().is_none()) {
+        return target_sessions()
+            .into_iter()
+            .map(|session_id| (session_id, SendError::ConnectionClosed(message.clone())))
+            .collect();
+    }
+    errors
+        .into_iter()
+        .filter_map(|error| Some((error.session_id()?, error.into_send_error(message.clone()))))
+        .collect()
+}
+
+// Sends all chunks even when some of them fail, the client drops incomplete events.
+fn send_chunks(
+    chunks: &[(&str, String)],
+    mut send: impl FnMut(&str, &str) -> Result<(), Vec<Error>>,
+) -> Result<(), Vec<Error>> {
+    let mut errors = Vec::new();
+    for (event, data) in chunks {
+        if let Err(chunk_errors) = send(event, data) {
+            errors.extend(chunk_errors);
+        }
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(errors)
+}
+
+// ------ ShareableSSEMethods ------
+
+pub trait ShareableSSEMethods {
+    fn spawn_connection_remover(&self);
+
+    /// Stops the background task removing dead connections.
+    /// [close_all](Self::close_all) stops it as well.
+    fn stop(&self);
+
+    /// Configures the connection, e.g. to keep the session actor when the session disconnects.
+    fn connection_builder(&self) -> ConnectionBuilder<'_>;
+
+    fn new_connection(
+        &self,
+        session_id: Option<SessionId>,
+        last_event_id: Option<u64>,
+    ) -> Result<(ConnectionId, Arc<Connection>, EventStream), Error>;
+
+    fn new_connection_with_kind(
+        &self,
+        session_id: Option<SessionId>,
+        last_event_id: Option<u64>,
+        connection_kind: ConnectionKind,
+    ) -> Result<(ConnectionId, Arc<Connection>, EventStream), Error>;
+
+    /// Sends the events returned by `init` before the connection starts receiving
+    /// broadcasts, e.g. the initial state of the session. `init` runs once the connection
+    /// is registered, broadcasts issued while it runs are delivered after its events.
+    /// The held broadcasts keep their event ids, so they may be lower than the ids of the initial events.
+    fn new_connection_with_init<'a>(
+        &'a self,
+        session_id: Option<SessionId>,
+        init: impl FnOnce(&Connection) -> Vec<(String, String)> + 'a,
+    ) -> Result<(ConnectionId, Arc<Connection>, EventStream), Error>;
+
+    /// Authenticates the request with [SSEConfig::auth] before creating the connection,
+    /// the `last_event_id` is taken from the request.
+    fn new_connection_for_request(
+        &self,
+        req: &HttpRequest,
+        session_id: Option<SessionId>,
+    ) -> Result<(ConnectionId, Arc<Connection>, EventStream), Error>;
+
+    /// Creates a connection passing its messages to the `sink` instead of an [EventStream],
+    /// e.g. to write them to a response of another HTTP server.
+    /// [SSEConfig::rate_limit] and [SSEConfig::batching] are applied only by `EventStream`.
+    /// The connection remover removes the connection once the sink is closed.
+    fn new_connection_with_sink(
+        &self,
+        session_id: Option<SessionId>,
+        last_event_id: Option<u64>,
+        sink: Arc<dyn FrameSink>,
+    ) -> Result<(ConnectionId, Arc<Connection>), Error>;
+
+    fn broadcast(&self, event: impl AsRef<str>, data: impl AsRef<str>) -> Result<(), Vec<Error>>;
+
+    /// Errors without a session, e.g. [Error::ReservedEventName],
+    /// are reported for all connected sessions.
+    #[deprecated(note = "use `broadcast` returning `sse::Error`s")]
+    fn broadcast_legacy(&self, event: &str, data: &str) -> Result<(), Vec<(SessionId, SendError)>>;
+
+    /// Like [broadcast](Self::broadcast), but reports which sessions haven't received the event.
+    fn broadcast_report(&self, event: impl AsRef<str>, data: impl AsRef<str>) -> BroadcastReport;
+
+    /// Broadcasts to all connections except the `excluded` sessions.
+    fn broadcast_except(
+        &self,
+        excluded: &[SessionId],
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<(), Vec<Error>>;
+
+    /// Sends the event to each connection accepted by the `predicate`.
+    /// The `predicate` must not call `SSE` methods.
+    fn broadcast_where(
+        &self,
+        predicate: impl Fn(&SessionId, &Connection) -> bool,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<(), Vec<Error>>;
+
+    /// Sends the data returned by `f` to each connection, skips connections with `None`.
+    /// `f` is called without any `SSE` lock held, so it may call `SSE` methods.
+    fn send_each<F>(&self, f: F, event: &str) -> Result<(), Vec<Error>>
+    where
+        F: for<'a> Fn(&'a SessionId, &'a Connection) -> Option<Cow<'a, str>>;
+
+    #[cfg(feature = "serde")]
+    fn broadcast_json<T: Serialize + ?Sized>(
+        &self,
+        event: &str,
+        data: &T,
+    ) -> Result<(), Vec<Error>>;
+
+    /// Sessions without a connection get the message later through [SSEConfig::outbox],
+    /// they are reported as [Error::SessionNotFound] when there isn't any outbox.
+    fn broadcast_to<'a>(
+        &self,
+        session_ids: impl IntoIterator<Item = &'a SessionId>,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<(), Vec<Error>>;
+
+    /// Errors without a session, e.g. [Error::ReservedEventName],
+    /// are reported for all `session_ids`.
+    #[deprecated(note = "use `broadcast_to` returning `sse::Error`s")]
+    fn broadcast_to_legacy<'a>(
+        &self,
+        session_ids: impl IntoIterator<Item = &'a SessionId>,
+        event: &str,
+        data: &str,
+    ) -> Result<(), BroadcastToErrors>;
+
+    /// Sends the final event to all connections, ends their `EventStream`s
+    /// and stops the connection remover. Returns the number of closed connections.
+    fn close_all(&self, event: &str, data: &str) -> usize;
+
+    /// Sends `retry` with the `grace` period so clients don't reconnect while the server
+    /// is restarting, then closes all connections like [close_all](Self::close_all)
+    /// with the `server_restarting` event and `grace` in milliseconds as data.
+    /// It doesn't wait for the clients. Returns the number of closed connections.
+    fn prepare_shutdown(&self, grace: Duration) -> usize;
+
+    /// Sends the already encoded message, e.g. created by [frame], to all connections.
+    /// The message doesn't get an event id and isn't stored in replay buffers.
+    fn broadcast_bytes(&self, frame: Bytes) -> Result<(), Vec<Error>>;
+
+    /// Like [broadcast](Self::broadcast), but the event is encoded only once by
+    /// [PreparedEvent::new] even when it's broadcast many times.
+    /// Unlike [broadcast_bytes](Self::broadcast_bytes), messages get event ids
+    /// and are stored in replay buffers.
+    fn broadcast_prepared(&self, prepared_event: &PreparedEvent) -> Result<(), Vec<Error>>;
+
+    /// Like [broadcast](Self::broadcast), but the `event:` line is copied from the [EventName].
+    fn broadcast_named(&self, event: &EventName, data: impl AsRef<str>) -> Result<(), Vec<Error>>;
+
+    /// Like [Connection::send_binary], the data is encoded only once.
+    fn broadcast_binary(&self, event: &str, data: &[u8]) -> Result<(), Vec<Error>>;
+
+    /// Sets how long all clients wait before reconnecting, in milliseconds.
+    fn broadcast_retry(&self, milliseconds: u32) -> Result<(), Vec<Error>>;
+
+    fn publish(
+        &self,
+        topic: &str,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<(), Vec<Error>>;
+
+    /// Creates an empty group with members managed by the server, see [ConnectionGroup].
+    fn group(&self) -> ConnectionGroup;
+
+    /// Sends the message to all connections of the session or stores it
+    /// in [SSEConfig::outbox] when the session isn't connected.
+    /// A session without a connection and outbox is reported as [Error::SessionNotFound],
+    /// [Error::ConnectionClosed] means that the session connections have been lost.
+    fn send(
+        &self,
+        session_id: &SessionId,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<SendOutcome, Error>;
+
+    /// Returns `None` when the session isn't connected.
+    #[deprecated(note = "use `send` returning `sse::Error`")]
+    fn send_legacy(
+        &self,
+        session_id: &SessionId,
+        event: &str,
+        data: &str,
+    ) -> Option<Result<(), SendError>>;
+
+    /// Like [send](Self::send), see [Priority] for the differences of high priority events.
+    fn send_with_priority(
+        &self,
+        session_id: &SessionId,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+        priority: Priority,
+    ) -> Result<SendOutcome, Error>;
+
+    /// Sends the event with [send](Self::send) after the delay unless it's cancelled.
+    /// The send is dropped when the session is removed or when it isn't connected
+    /// or reconnecting now. Due sends are sent by the connection remover,
+    /// i.e. up to a fraction of [SSEConfig::keep_alive_interval] late.
+    fn send_after(
+        &self,
+        session_id: &SessionId,
+        delay: Duration,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> ScheduledSend;
+
+    #[cfg(feature = "serde")]
+    fn send_json<T: Serialize + ?Sized>(
+        &self,
+        session_id: &SessionId,
+        event: &str,
+        data: &T,
+    ) -> Result<SendOutcome, Error>;
+
+    #[cfg(feature = "serde")]
+    #[deprecated(note = "use `send_json` returning `sse::Error`")]
+    fn send_json_legacy<T: Serialize + ?Sized>(
+        &self,
+        session_id: &SessionId,
+        event: &str,
+        data: &T,
+    ) -> Option<Result<(), SendJsonError>>;
+
+    /// Sends the `down_msg` event in the format expected by Zoon's `Connection`.
+    /// The session connections are removed right away on [Error::ConnectionClosed].
+    #[cfg(feature = "serde")]
+    fn send_down_msg<DMsg: Serialize>(
+        &self,
+        session_id: &SessionId,
+        cor_id: CorId,
+        down_msg: &DMsg,
+    ) -> Result<SendOutcome, Error>;
+
+    #[cfg(feature = "serde")]
+    fn broadcast_down_msg<DMsg: Serialize>(
+        &self,
+        cor_id: CorId,
+        down_msg: &DMsg,
+    ) -> Result<(), Vec<Error>>;
+
+    fn remove_connection(&self, session_id: &SessionId);
+
+    /// Creates the `new_connection` and then sends the final [SessionReplacement::event]
+    /// to the connections of the old session and closes them,
+    /// so one of the sessions is always reachable. The old session is removed like by [kick](Self::kick).
+    fn replace_session(
+        &self,
+        old_session_id: &SessionId,
+        new_connection: ConnectionBuilder,
+        replacement: SessionReplacement,
+    ) -> Result<ReplaceOutcome, Error>;
+
+    /// Closes all connections of the session and removes the session state.
+    /// Returns `false` if the session wasn't connected.
+    fn kick(&self, session_id: &SessionId) -> bool;
+
+    /// Like [Connection::close] for all connections of the session.
+    /// Returns `false` if the session wasn't connected.
+    fn close(&self, session_id: &SessionId, final_event: Option<(&str, &str)>) -> bool;
+
+    /// Reports client activity of the session, e.g. a received `UpMsg`,
+    /// to keep its connections open with [SSEConfig::max_idle].
+    fn touch(&self, session_id: &SessionId);
+
+    /// Removes the connection closed by the client, e.g. reported by `navigator.sendBeacon`,
+    /// without waiting for a failed keep-alive message.
+    /// Nothing is removed when the session has a newer connection, because the client
+    /// has already reconnected, or when the connection doesn't belong to the session.
+    fn remove_closed_connection(&self, session_id: &SessionId, connection_id: ConnectionId)
+        -> bool;
+
+    /// Resolves the [AckHandle] of the event sent by [Connection::send_tracked].
+    /// Returns `false` when the event isn't waiting for the acknowledgement
+    /// or when the connection doesn't belong to the session.
+    fn ack(&self, session_id: &SessionId, connection_id: ConnectionId, event_id: u64) -> bool;
+}
+
+impl ShareableSSEMethods for ShareableSSE {
+    fn spawn_connection_remover(&self) {
+        // The task mustn't keep `SSE` alive.
+        let weak_this = Arc::downgrade(self);
+        let keep_alive_interval = self.config.keep_alive_interval;
+        let session_grace = self.config.session_grace;
+        // One shard is swept per tick so `send`s don't wait for a pass over all connections,
+        // each connection is still checked once per `keep_alive_interval`.
+        let shard_count = self.connections.shard_count();
+        let tick_period = (keep_alive_interval / shard_count as u32).max(Duration::from_millis(1));
+        let remover = rt::spawn(async move {
+            let mut interval = interval_at(Instant::now(), tick_period);
+            let mut shard = 0;
+            // Reported by `SSELifecycleEvent::SweepCompleted` after the last shard.
+            let mut sweep_removed = 0;
+            let mut sweep_duration = Duration::ZERO;
+            loop {
+                interval.tick().await;
+                let Some(this) = weak_this.upgrade() else {
+                    break;
+                };
+                if this.closed.load(Ordering::Relaxed) {
+                    break;
+                }
+                let tick_started_at = Instant::now();
+                if let Some(max_idle) = this.config.max_idle {
+                    let idle_connections = this.remove_shard_connections(shard, |connection| {
+                        connection.idle_duration() >= max_idle
+                    });
+                    sweep_removed += idle_connections.len();
+                    for connection in idle_connections {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(
+                            session_id = %connection.session_id(),
+                            connection_id = %connection.id(),
+                            "SSE connection closed, no client activity"
+                        );
+                        connection.close_senders();
+                        this.connection_removed(&connection, DisconnectReason::IdleTimeout);
+                    }
+                }
+                sweep_removed += this.ping_connections(shard);
+                for connection in this.connections.shard_values(shard) {
+                    connection.expire_acks();
+                }
+                for (session_id, event, data) in this.schedule.due() {
+                    let _ = this.send(&session_id, event, data);
+                }
+                shard = (shard + 1) % shard_count;
+                if shard != 0 {
+                    sweep_duration += tick_started_at.elapsed();
+                    continue;
+                }
+                let expired_sessions = this
+                    .disconnected_sessions
+                    .retain(|_, disconnected_at| disconnected_at.elapsed() < session_grace);
+                for (session_id, _) in expired_sessions {
+                    if !this.sessions.contains_key(&session_id) {
+                        this.session_removed(&session_id);
+                    }
+                }
+                this.remove_expired_session_actors();
+                let removed = mem::take(&mut sweep_removed);
+                let duration = mem::take(&mut sweep_duration) + tick_started_at.elapsed();
+                this.lifecycle
+                    .publish(|| SSELifecycleEvent::SweepCompleted { removed, duration });
+            }
+        });
+        if let Some(previous_remover) = self.remover.lock().replace(remover) {
+            previous_remover.abort();
+        }
+    }
+
+    fn stop(&self) {
+        if let Some(remover) = &*self.remover.lock() {
+            remover.abort();
+        }
+    }
+
+    fn new_connection(
+        &self,
+        session_id: Option<SessionId>,
+        last_event_id: Option<u64>,
+    ) -> Result<(ConnectionId, Arc<Connection>, EventStream), Error> {
+        self.new_connection_with_kind(session_id, last_event_id, self.config.connection_kind)
+    }
+
+    fn connection_builder(&self) -> ConnectionBuilder<'_> {
+        ConnectionBuilder::new(self)
+    }
+
+    fn new_connection_with_kind(
+        &self,
+        session_id: Option<SessionId>,
+        last_event_id: Option<u64>,
+        connection_kind: ConnectionKind,
+    ) -> Result<(ConnectionId, Arc<Connection>, EventStream), Error> {
+        let mut builder = self.connection_builder().connection_kind(connection_kind);
+        builder.session_id = session_id;
+        builder.last_event_id = last_event_id;
+        self.create_connection(builder)
+    }
+
+    fn new_connection_with_init<'a>(
+        &'a self,
+        session_id: Option<SessionId>,
+        init: impl FnOnce(&Connection) -> Vec<(String, String)> + 'a,
+    ) -> Result<(ConnectionId, Arc<Connection>, EventStream), Error> {
+        let mut builder = self.connection_builder();
+        builder.session_id = session_id;
+        builder.init = Some(Box::new(init));
+        self.create_connection(builder)
+    }
+
+    fn new_connection_for_request(
+        &self,
+        req: &HttpRequest,
+        session_id: Option<SessionId>,
+    ) -> Result<(ConnectionId, Arc<Connection>, EventStream), Error> {
+        // Nothing is changed for a rejected client, e.g. its session connections are kept.
+        let mut builder = self.connection_builder();
+        if let Some(auth) = &self.config.auth {
+            builder = builder.metadata(auth(req, session_id).map_err(Error::Unauthorized)?);
+        }
+        builder.session_id = session_id;
+        builder.last_event_id = last_event_id(req);
+        builder.last_known_epoch = last_known_epoch(req);
+        self.create_connection(builder)
+    }
+
+    fn new_connection_with_sink(
+        &self,
+        session_id: Option<SessionId>,
+        last_event_id: Option<u64>,
+        sink: Arc<dyn FrameSink>,
+    ) -> Result<(ConnectionId, Arc<Connection>), Error> {
+        let mut builder = self.connection_builder();
+        builder.session_id = session_id;
+        builder.last_event_id = last_event_id;
+        let (connection_id, connection, _) =
+            self.create_connection_with_sink(builder, Some(sink))?;
+        Ok((connection_id, connection))
+    }
+
+    fn broadcast(&self, event: impl AsRef<str>, data: impl AsRef<str>) -> Result<(), Vec<Error>> {
+        self.broadcast_except(&[], event, data)
+    }
+
+    fn broadcast_legacy(&self, event: &str, data: &str) -> Result<(), Vec<(SessionId, SendError)>> {
+        let errors = self.broadcast(event, data).err().unwrap_or_default();
+        let errors = legacy_send_errors(
+            errors,
+            || self.session_ids(),
+            || frame(event, data).unwrap_or_default(),
+        );
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(errors)
+    }
+
+    fn broadcast_report(&self, event: impl AsRef<str>, data: impl AsRef<str>) -> BroadcastReport {
+        let (event, data) = (event.as_ref(), data.as_ref());
+        let rejected = || BroadcastReport {
+            delivered: 0,
+            failed: self.session_ids(),
+        };
+        if Error::check_event_name(event).is_err() {
+            return rejected();
+        }
+        self.forward(event, data, || BroadcastTarget::All);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("sse_broadcast", event, data_len = data.len()).entered();
+        let (delivered, errors) = match split_payload(self.config.max_frame_size, event, data) {
+            Err(_) => return rejected(),
+            Ok(None) => match self.message_body(event, data) {
+                Ok(body) => self.broadcast_body(&[], &body),
+                Err(_) => return rejected(),
+            },
+            Ok(Some(chunks)) => {
+                let mut delivered = 0;
+                let mut errors = Vec::new();
+                for (event, data) in &chunks {
+                    let Ok(body) = self.message_body(event, data) else {
+                        return rejected();
+                    };
+                    let (chunk_delivered, chunk_errors) = self.broadcast_body(&[], &body);
+                    // Connections that have received the final `chunk_end` event.
+                    delivered = chunk_delivered;
+                    errors.extend(chunk_errors);
+                }
+                (delivered, errors)
+            }
+        };
+        let mut failed = Vec::new();
+        if !errors.is_empty() {
+            let mut failed_sessions = HashSet::new();
+            failed = errors
+                .into_iter()
+                .map(|(session_id, _)| session_id)
+                .filter(|session_id| failed_sessions.insert(*session_id))
+                .collect();
+        }
+        BroadcastReport { delivered, failed }
+    }
+
+    fn broadcast_except(
+        &self,
+        excluded: &[SessionId],
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<(), Vec<Error>> {
+        let (event, data) = (event.as_ref(), data.as_ref());
+        Error::check_event_name(event)?;
+        self.forward(event, data, || match excluded {
+            [] => BroadcastTarget::All,
+            excluded => BroadcastTarget::Except(excluded.to_vec()),
+        });
+        self.broadcast_message(excluded, event, data)
+    }
+
+    fn broadcast_where(
+        &self,
+        predicate: impl Fn(&SessionId, &Connection) -> bool,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<(), Vec<Error>> {
+        let event = event.as_ref();
+        Error::check_event_name(event)?;
+        let body = self.message_body(event, data.as_ref())?;
+        let mut errors = Vec::new();
+        self.for_each_connection(|session_id, connection| {
+            if predicate(session_id, connection) {
+                if let Err(error) = connection.send_body(&body) {
+                    errors.push(connection.error(error));
+                }
+            }
+        });
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(errors)
+    }
+
+    fn send_each<F>(&self, f: F, event: &str) -> Result<(), Vec<Error>>
+    where
+        F: for<'a> Fn(&'a SessionId, &'a Connection) -> Option<Cow<'a, str>>,
+    {
+        Error::check_event_name(event)?;
+        let mut errors = Vec::new();
+        for connection in self.connections_snapshot() {
+            let session_id = connection.session_id();
+            let Some(data) = f(&session_id, &connection) else {
+                continue;
+            };
+            if let Err(error) = connection.send_body(&self.message_body(event, &data)?) {
+                errors.push(connection.error(error));
+            }
+        }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(errors)
+    }
+
+    #[cfg(feature = "serde")]
+    fn broadcast_json<T: Serialize + ?Sized>(
+        &self,
+        event: &str,
+        data: &T,
+    ) -> Result<(), Vec<Error>> {
+        let data =
+            serde_json::to_string(data).map_err(|error| vec![Error::Serialization(error)])?;
+        self.broadcast(event, &data)
+    }
+
+    fn broadcast_to<'a>(
+        &self,
+        session_ids: impl IntoIterator<Item = &'a SessionId>,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<(), Vec<Error>> {
+        let (event, data) = (event.as_ref(), data.as_ref());
+        Error::check_event_name(event)?;
+        if self.config.broker.is_none() {
+            return self.broadcast_to_local(session_ids, event, data);
+        }
+        let session_ids = session_ids.into_iter().copied().collect::<Vec<_>>();
+        let result = self.broadcast_to_local(&session_ids, event, data);
+        self.forward(event, data, || BroadcastTarget::Sessions(session_ids));
+        result
+    }
+
+    fn broadcast_to_legacy<'a>(
+        &self,
+        session_ids: impl IntoIterator<Item = &'a SessionId>,
+        event: &str,
+        data: &str,
+    ) -> Result<(), BroadcastToErrors> {
+        let session_ids = session_ids.into_iter().copied().collect::<Vec<_>>();
+        let errors = self
+            .broadcast_to(&session_ids, event, data)
+            .err()
+            .unwrap_or_default();
+        let (not_connected, errors): (Vec<_>, Vec<_>) = errors
+            .into_iter()
+            .partition(|error| matches!(error, Error::SessionNotFound { .. }));
+        let broadcast_to_errors = BroadcastToErrors {
+            not_connected: not_connected.iter().filter_map(Error::session_id).collect(),
+            send_failed: legacy_send_errors(
+                errors,
+                || session_ids,
+                || frame(event, data).unwrap_or_default(),
+            ),
+        };
+        if broadcast_to_errors.is_empty() {
+            return Ok(());
+        }
+        Err(broadcast_to_errors)
+    }
+
+    fn close_all(&self, event: &str, data: &str) -> usize {
+        self.closed.store(true, Ordering::Relaxed);
+        self.stop();
+        if let Some(broker_relay) = self.broker_relay.lock().take() {
+            broker_relay.abort();
+        }
+        self.schedule.clear();
+        let connections = self.connections.clear();
+        let mut closed_connections = 0;
+        for (_, connection) in connections {
+            let _ = connection.send_unchecked(event, data);
+            connection.close_senders();
+            self.connection_removed(&connection, DisconnectReason::ServerShutdown);
+            closed_connections += 1;
+        }
+        for (session_id, _) in self.disconnected_sessions.clear() {
+            self.session_removed(&session_id);
+        }
+        // The remover is stopped, so it wouldn't remove them.
+        for (session_id, _) in self.pending_session_actor_removals.clear() {
+            remove_session_actor(&session_id);
+        }
+        closed_connections
+    }
+
+    fn prepare_shutdown(&self, grace: Duration) -> usize {
+        let milliseconds = u32::try_from(grace.as_millis()).unwrap_or(u32::MAX);
+        let _ = self.broadcast_retry(milliseconds);
+        self.close_all(SERVER_RESTARTING_EVENT, &milliseconds.to_string())
+    }
+
+    fn broadcast_bytes(&self, frame: Bytes) -> Result<(), Vec<Error>> {
+        let mut errors = Vec::new();
+        self.for_each_connection(|_, connection| {
+            if let Err(error) = connection.send_frame(frame.clone()) {
+                errors.push(connection.error(error));
+            }
+        });
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(errors)
+    }
+
+    fn broadcast_prepared(&self, prepared_event: &PreparedEvent) -> Result<(), Vec<Error>> {
+        if self.config.broker.is_some() {
+            let (event, data) = prepared_event.decode();
+            self.forward(&event, &data, || BroadcastTarget::All);
+        }
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("sse_broadcast", body_len = prepared_event.body().len()).entered();
+        match split_prepared(self.config.max_frame_size, prepared_event) {
+            Err(error) => Err(vec![error]),
+            Ok(None) => self.broadcast_event(
+                &[],
+                &MessageBody::prepared(self.config.formatter.as_deref(), prepared_event),
+            ),
+            Ok(Some(chunks)) => send_chunks(&chunks, |event, data| {
+                self.broadcast_event(&[], &self.message_body(event, data)?)
+            }),
+        }
+    }
+
+    fn broadcast_named(&self, event: &EventName, data: impl AsRef<str>) -> Result<(), Vec<Error>> {
+        let data = data.as_ref();
+        Error::check_event_name(event.as_str())?;
+        self.forward(event.as_str(), data, || BroadcastTarget::All);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "sse_broadcast",
+            event = event.as_str(),
+            data_len = data.len()
+        )
+        .entered();
+        match split_payload(self.config.max_frame_size, event.as_str(), data) {
+            Err(error) => Err(vec![error]),
+            Ok(None) => self.broadcast_event(
+                &[],
+                &MessageBody::named(self.config.formatter.as_deref(), event, data),
+            ),
+            Ok(Some(chunks)) => send_chunks(&chunks, |event, data| {
+                self.broadcast_event(&[], &self.message_body(event, data)?)
+            }),
+        }
+    }
+
+    fn broadcast_binary(&self, event: &str, data: &[u8]) -> Result<(), Vec<Error>> {
+        Error::check_event_name(event)?;
+        if self.config.broker.is_some() {
+            let event = [event, BINARY_EVENT_SUFFIX].concat();
+            self.forward(&event, &base64::encode(data), || BroadcastTarget::All);
+        }
+        let exceeds_limit = self
+            .config
+            .max_frame_size
+            .is_some_and(|limit| base64::encoded_len(data.len()) > limit.max_bytes);
+        if exceeds_limit {
+            let event = [event, BINARY_EVENT_SUFFIX].concat();
+            return self.broadcast_message(&[], &event, &base64::encode(data));
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("sse_broadcast", event, data_len = data.len()).entered();
+        let body = MessageBody::binary(self.config.formatter.as_deref(), event, data)?;
+        self.broadcast_event(&[], &body)
+    }
+
+    fn broadcast_retry(&self, milliseconds: u32) -> Result<(), Vec<Error>> {
+        let message = retry(milliseconds);
+        let mut errors = Vec::new();
+        self.for_each_connection(|_, connection| {
+            if let Err(error) = connection.send_control(message.clone()) {
+                errors.push(connection.error(error));
+            }
+        });
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(errors)
+    }
+
+    fn publish(
+        &self,
+        topic: &str,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<(), Vec<Error>> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("sse_publish", topic).entered();
+        let (event, data) = (event.as_ref(), data.as_ref());
+        Error::check_event_name(event)?;
+        self.forward(event, data, || BroadcastTarget::Topic(topic.to_owned()));
+        let subscribers = self.topics.subscribers(topic);
+        self.broadcast_to_local(&subscribers, event, data)
+    }
+
+    fn group(&self) -> ConnectionGroup {
+        ConnectionGroup::new(self)
+    }
+
+    fn send(
+        &self,
+        session_id: &SessionId,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<SendOutcome, Error> {
+        let event = event.as_ref();
+        Error::check_event_name(event)?;
+        self.send_message(session_id, event, data.as_ref())
+    }
+
+    fn send_legacy(
+        &self,
+        session_id: &SessionId,
+        event: &str,
+        data: &str,
+    ) -> Option<Result<(), SendError>> {
+        legacy_send_result(self.send(session_id, event, data), event, data)
+    }
+
+    fn send_with_priority(
+        &self,
+        session_id: &SessionId,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+        priority: Priority,
+    ) -> Result<SendOutcome, Error> {
+        Error::check_event_name(event.as_ref())?;
+        let connections = self
+            .sessions
+            .get(session_id)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|connection_id| self.connection(*connection_id))
+            .collect::<SmallVec<[_; 2]>>();
+        if priority == Priority::Normal || connections.is_empty() {
+            // The message is stored in the outbox or `SessionNotFound` is returned.
+            return self.send(session_id, event, data);
+        }
+        let message = self
+            .message_body(event.as_ref(), data.as_ref())?
+            .message(None);
+        let results = connections
+            .iter()
+            .map(|connection| connection.send_high_priority(message.clone()))
+            .collect();
+        first_success(results)
+            .map(|()| SendOutcome::Delivered)
+            .map_err(|error| Error::from_send_error(*session_id, error))
+    }
+
+    fn send_after(
+        &self,
+        session_id: &SessionId,
+        delay: Duration,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> ScheduledSend {
+        // The session is kept while its client is reconnecting.
+        let known_session = self.sessions.contains_key(session_id)
+            || self.disconnected_sessions.contains_key(session_id);
+        if !known_session || self.closed.load(Ordering::Relaxed) {
+            return ScheduledSend::finished();
+        }
+        self.schedule
+            .insert(*session_id, delay, event.as_ref(), data.as_ref())
+    }
+
+    #[cfg(feature = "serde")]
+    fn send_json<T: Serialize + ?Sized>(
+        &self,
+        session_id: &SessionId,
+        event: &str,
+        data: &T,
+    ) -> Result<SendOutcome, Error> {
+        let data = serde_json::to_string(data).map_err(Error::Serialization)?;
+        self.send(session_id, event, &data)
+    }
+
+    #[cfg(feature = "serde")]
+    fn send_json_legacy<T: Serialize + ?Sized>(
+        &self,
+        session_id: &SessionId,
+        event: &str,
+        data: &T,
+    ) -> Option<Result<(), SendJsonError>> {
+        let data = match serde_json::to_string(data) {
+            Ok(data) => data,
+            Err(error) => return Some(Err(SendJsonError::Serialization(error))),
+        };
+        legacy_send_result(self.send(session_id, event, &data), event, &data)
+            .map(|result| result.map_err(SendJsonError::Send))
+    }
+
+    #[cfg(feature = "serde")]
+    fn send_down_msg<DMsg: Serialize>(
+        &self,
+        session_id: &SessionId,
+        cor_id: CorId,
+        down_msg: &DMsg,
+    ) -> Result<SendOutcome, Error> {
+        let down_msg_transporter = DownMsgTransporterForSer { down_msg, cor_id };
+        let data = serde_json::to_string(&down_msg_transporter).map_err(Error::Serialization)?;
+        let result = self.send_message(session_id, DOWN_MSG_EVENT, &data);
+        if let Err(Error::ConnectionClosed { .. }) = result {
+            self.remove_closed_session_connections(session_id);
+        }
+        result
+    }
+
+    #[cfg(feature = "serde")]
+    fn broadcast_down_msg<DMsg: Serialize>(
+        &self,
+        cor_id: CorId,
+        down_msg: &DMsg,
+    ) -> Result<(), Vec<Error>> {
+        let down_msg_transporter = DownMsgTransporterForSer { down_msg, cor_id };
+        let data = serde_json::to_string(&down_msg_transporter)
+            .map_err(|error| vec![Error::Serialization(error)])?;
+        self.broadcast_protocol_event(ProtocolEvent::DownMsg, &data)
+    }
+
+    fn remove_connection(&self, session_id: &SessionId) {
+        let connection_ids = self.sessions.get(session_id).unwrap_or_default();
+
+        for connection_id in connection_ids {
+            if let Some(connection) = self.connections.remove(&connection_id) {
+                self.connection_removed(&connection, DisconnectReason::ExplicitRemove);
+            }
+        }
+        if self.disconnected_sessions.remove(session_id).is_some() {
+            self.session_removed(session_id);
+        }
+    }
+
+    fn replace_session(
+        &self,
+        old_session_id: &SessionId,
+        new_connection: ConnectionBuilder,
+        replacement: SessionReplacement,
+    ) -> Result<ReplaceOutcome, Error> {
+        let old_connections = self
+            .sessions
+            .get(old_session_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|connection_id| self.connection(connection_id))
+            .collect::<Vec<_>>();
+        // The old connections are closed only when the new one has been registered.
+        let (connection, event_stream) = new_connection.connect()?;
+        let new_session_id = connection.session_id();
+        if replacement.transfer_state && new_session_id != *old_session_id {
+            for topic in self.topics.session_topics(old_session_id) {
+                self.topics.subscribe(&topic, new_session_id);
+            }
+            self.groups.replace_session(old_session_id, new_session_id);
+            for old_connection in &old_connections {
+                connection.copy_extensions_from(old_connection);
+            }
+        }
+        let mut replaced = false;
+        for old_connection in old_connections {
+            if self.connections.remove(&old_connection.id()).is_some() {
+                let _ = old_connection.send_unchecked(&replacement.event, "");
+                old_connection.close_senders();
+                self.connection_removed(&old_connection, DisconnectReason::Replaced);
+                replaced = true;
+            }
+        }
+        Ok(ReplaceOutcome {
+            replaced,
+            connection,
+            event_stream,
+        })
+    }
+
+    fn kick(&self, session_id: &SessionId) -> bool {
+        let connection_ids = self.sessions.get(session_id).unwrap_or_default();
+        let mut kicked = false;
+        for connection_id in connection_ids {
+            if let Some(connection) = self.connections.remove(&connection_id) {
+                connection.close_senders();
+                self.connection_removed(&connection, DisconnectReason::Kicked);
+                kicked = true;
+            }
+        }
+        kicked
+    }
+
+    fn close(&self, session_id: &SessionId, final_event: Option<(&str, &str)>) -> bool {
+        let mut closed = false;
+        for connection_id in self.sessions.get(session_id).unwrap_or_default() {
+            closed |= self.close_connection(connection_id, final_event);
+        }
+        closed
+    }
+
+    fn touch(&self, session_id: &SessionId) {
+        for connection_id in self.sessions.get(session_id).unwrap_or_default() {
+            if let Some(connection) = self.connection(connection_id) {
+                connection.touch();
+            }
+        }
+    }
+
+    fn remove_closed_connection(
+        &self,
+        session_id: &SessionId,
+        connection_id: ConnectionId,
+    ) -> bool {
+        let Some(connection_ids) = self.sessions.get(session_id) else {
+            return false;
+        };
+        if !connection_ids.contains(&connection_id)
+            || connection_ids.iter().any(|id| *id > connection_id)
+        {
+            return false;
+        }
+        let Some(connection) = self.connections.remove(&connection_id) else {
+            return false;
+        };
+        connection.close_senders();
+        self.connection_removed(&connection, DisconnectReason::ClientClosed);
+        true
+    }
+
+    fn ack(&self, session_id: &SessionId, connection_id: ConnectionId, event_id: u64) -> bool {
+        match self.connection(connection_id) {
+            Some(connection) if connection.session_id() == *session_id => connection.ack(event_id),
+            _ => false,
+        }
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::rt as actix_rt;
+    use futures::{Stream, StreamExt};
+    use message::message;
+    use std::pin::Pin;
+    use std::sync::{mpsc, Barrier};
+    use std::task::Context;
+    use std::thread;
+
+    // The `id:` line is written as its own chunk, it's joined with the rest of the message.
+    async fn next_message(event_stream: &mut EventStream) -> Option<Bytes> {
+        let chunk = event_stream.next().await?.unwrap();
+        if !chunk.starts_with(b"id: ") || chunk.ends_with(b"\n\n") {
+            return Some(chunk);
+        }
+        let body = event_stream.next().await.unwrap().unwrap();
+        Some(Bytes::from([&chunk[..], &body[..]].concat()))
+    }
+
+    const HOSTILE_EVENT: &str = "chat\ndata: evil\n\nevent: admin_grant";
+
+    // Sends a reserved and a multi-line event name.
+    fn send_rejected_names<T>(
+        send: impl Fn(&str) -> Result<T, Vec<Error>>,
+    ) -> [Result<T, Vec<Error>>; 2] {
+        [send("down_msg"), send(HOSTILE_EVENT)]
+    }
+
+    fn assert_rejected<T>(results: [Result<T, Vec<Error>>; 2], connection: &Connection) {
+        let [reserved, hostile] = results;
+        assert!(matches!(
+            reserved.err().unwrap_or_default()[..],
+            [Error::ReservedEventName(ProtocolEvent::DownMsg)]
+        ));
+        assert!(matches!(
+            hostile.err().unwrap_or_default()[..],
+            [Error::InvalidEventName(_)]
+        ));
+        assert_eq!(connection.queued_frames(), 0);
+    }
+
+    // Skips the keep-alives sent by the connection remover.
+    async fn next_event(event_stream: &mut EventStream) -> Option<Bytes> {
+        loop {
+            let message = next_message(event_stream).await?;
+            if message != KeepAlive::default().message(None).unwrap() {
+                return Some(message);
+            }
+        }
+    }
+
+    // Every connection starts with the `epoch` event.
+    async fn skip_epoch(event_stream: &mut EventStream) {
+        let epoch = next_message(event_stream).await.unwrap();
+        assert_eq!(message::event_name(&epoch), ProtocolEvent::Epoch.as_str());
+    }
+
+    #[actix_rt::test]
+    async fn test_stop_connection_remover() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+
+        // ------ ACT ------
+        sse.stop();
+
+        // ------ ASSERT ------
+        let remover = sse.remover.lock().take().unwrap();
+        assert!(remover.await.unwrap_err().is_cancelled());
+    }
+
+    #[actix_rt::test]
+    async fn test_close_all_stops_connection_remover() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, _, _event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let closed_connections = sse.close_all("shutdown", "");
+
+        // ------ ASSERT ------
+        assert_eq!(closed_connections, 1);
+        assert_eq!(sse.connection_count(), 0);
+        let remover = sse.remover.lock().take().unwrap();
+        assert!(remover.await.unwrap_err().is_cancelled());
+    }
+
+    #[actix_rt::test]
+    async fn test_remove_closed_connection_keeps_newer_connection() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let session_id = SessionId::new();
+        let (old_id, _, _old_event_stream) = sse.new_connection(Some(session_id), None).unwrap();
+        let (new_id, _, _new_event_stream) = sse.new_connection(Some(session_id), None).unwrap();
+
+        // ------ ACT ------
+        let old_removed = sse.remove_closed_connection(&session_id, old_id);
+        let new_removed = sse.remove_closed_connection(&session_id, new_id);
+
+        // ------ ASSERT ------
+        assert!(!old_removed);
+        assert!(new_removed);
+        assert_eq!(sse.connection_count(), 1);
+        assert!(!sse.remove_closed_connection(&SessionId::new(), old_id));
+    }
+
+    #[actix_rt::test]
+    async fn test_reconnect_cancels_session_actor_removal() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let session_id = SessionId::new();
+        let (connection_id, connection, _old_event_stream) =
+            sse.new_connection(Some(session_id), None).unwrap();
+        sse.connections.remove(&connection_id);
+        sse.connection_removed(&connection, DisconnectReason::PingFailed);
+        assert!(sse.pending_session_actor_removals.contains_key(&session_id));
+        assert!(sse.disconnected_sessions.contains_key(&session_id));
+
+        // ------ ACT ------
+        let (_, _, _new_event_stream) = sse.new_connection(Some(session_id), None).unwrap();
+
+        // ------ ASSERT ------
+        assert!(!sse.pending_session_actor_removals.contains_key(&session_id));
+    }
+
+    #[actix_rt::test]
+    async fn test_connection_builder_keeps_session_actor() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let session_id = SessionId::new();
+
+        // ------ ACT ------
+        let (connection, _event_stream) = sse
+            .connection_builder()
+            .session_id(session_id)
+            .keep_session_actor_on_disconnect()
+            .metadata(7_u32)
+            .connect()
+            .unwrap();
+        sse.connections.remove(&connection.id());
+        sse.connection_removed(&connection, DisconnectReason::PingFailed);
+
+        // ------ ASSERT ------
+        assert_eq!(connection.session_id(), session_id);
+        assert_eq!(connection.extension::<u32>().as_deref(), Some(&7));
+        assert!(!sse.pending_session_actor_removals.contains_key(&session_id));
+    }
+
+    #[actix_rt::test]
+    async fn test_high_priority_message_overtakes_queued_messages() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, mut event_stream) = sse.new_connection(None, None).unwrap();
+        skip_epoch(&mut event_stream).await;
+        connection.send("sync", "1").unwrap();
+
+        // ------ ACT ------
+        connection
+            .send_with_priority("logout", "", Priority::High)
+            .unwrap();
+
+        // ------ ASSERT ------
+        let first_message = next_message(&mut event_stream).await.unwrap();
+        let second_message = next_message(&mut event_stream).await.unwrap();
+        assert_eq!(first_message, message(None, "logout", ""));
+        assert_eq!(second_message, message(Some(1), "sync", "1"));
+    }
+
+    #[actix_rt::test]
+    async fn test_ping_only_idle_connections() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, busy_connection, mut busy_event_stream) = sse.new_connection(None, None).unwrap();
+        let (_, _, mut idle_event_stream) = sse.new_connection(None, None).unwrap();
+        skip_epoch(&mut busy_event_stream).await;
+        skip_epoch(&mut idle_event_stream).await;
+        busy_connection.send("sync", "1").unwrap();
+        next_message(&mut busy_event_stream).await.unwrap();
+
+        // ------ ACT ------
+        for shard in 0..sse.connections.shard_count() {
+            sse.ping_connections(shard);
+        }
+
+        // ------ ASSERT ------
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        assert!(Pin::new(&mut busy_event_stream)
+            .poll_next(&mut cx)
+            .is_pending());
+        assert_eq!(
+            next_message(&mut idle_event_stream).await.unwrap(),
+            KeepAlive::default().message(None).unwrap()
+        );
+        assert_eq!(sse.connections.len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_removed_session_leaves_group() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let group = sse.group();
+        let (connection_id, connection, mut event_stream) = sse.new_connection(None, None).unwrap();
+        skip_epoch(&mut event_stream).await;
+        let session_id = connection.session_id();
+        assert!(group.add(&session_id));
+        assert!(!group.add(&SessionId::new()));
+        group.send("team", "hello").unwrap();
+
+        // ------ ACT ------
+        sse.connections.remove(&connection_id);
+        sse.connection_removed(&connection, DisconnectReason::ClientClosed);
+
+        // ------ ASSERT ------
+        assert_eq!(
+            next_message(&mut event_stream).await.unwrap(),
+            message(Some(1), "team", "hello")
+        );
+        assert!(group.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_shutdown() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, _, mut event_stream) = sse.new_connection(None, None).unwrap();
+        skip_epoch(&mut event_stream).await;
+
+        // ------ ACT ------
+        let closed_connections = sse.prepare_shutdown(Duration::from_secs(5));
+
+        // ------ ASSERT ------
+        let mut messages = Vec::new();
+        while let Some(message) = next_message(&mut event_stream).await {
+            messages.push(message);
+        }
+        assert_eq!(closed_connections, 1);
+        assert_eq!(
+            messages,
+            [
+                retry(5000),
+                message(Some(1), SERVER_RESTARTING_EVENT, "5000")
+            ]
+        );
+        assert_eq!(sse.prepare_shutdown(Duration::from_secs(5)), 0);
+    }
+
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<Bytes>>);
+
+    impl FrameSink for RecordingSink {
+        fn try_send(&self, frame: Bytes) -> Result<(), SinkError> {
+            self.0.lock().push(frame);
+            Ok(())
+        }
+
+        fn is_closed(&self) -> bool {
+            false
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_connection_with_sink() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let sink = Arc::<RecordingSink>::default();
+        let (_, connection) = sse
+            .new_connection_with_sink(None, None, Arc::clone(&sink) as Arc<dyn FrameSink>)
+            .unwrap();
+
+        // ------ ACT ------
+        connection.send("sync", "1").unwrap();
+        connection.send_comment("hello").unwrap();
+
+        // ------ ASSERT ------
+        assert_eq!(
+            *sink.0.lock(),
+            [
+                message(
+                    None,
+                    ProtocolEvent::Epoch.as_str(),
+                    &sse.epoch().to_string()
+                ),
+                message(Some(1), "sync", "1"),
+                message::comment("hello")
+            ]
+        );
+        assert!(!connection.is_closed());
+    }
+
+    #[actix_rt::test]
+    async fn test_replace_session() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, old_connection, mut old_event_stream) = sse.new_connection(None, None).unwrap();
+        skip_epoch(&mut old_event_stream).await;
+        let old_session_id = old_connection.session_id();
+        old_connection.subscribe("news");
+        old_connection.set_extension(7_u32);
+        let replacement = SessionReplacement {
+            transfer_state: true,
+            ..SessionReplacement::default()
+        };
+
+        // ------ ACT ------
+        let outcome = sse
+            .replace_session(&old_session_id, sse.connection_builder(), replacement)
+            .unwrap();
+
+        // ------ ASSERT ------
+        assert!(outcome.replaced);
+        assert_eq!(
+            next_message(&mut old_event_stream).await.unwrap(),
+            message(Some(1), "session_replaced", "")
+        );
+        assert!(old_event_stream.next().await.is_none());
+        let new_session_id = outcome.connection.session_id();
+        assert_eq!(sse.topics.subscribers("news"), [new_session_id]);
+        assert_eq!(outcome.connection.extension::<u32>().as_deref(), Some(&7));
+        assert!(!sse.is_connected(&old_session_id));
+    }
+
+    #[actix_rt::test]
+    async fn test_broadcast_through_broker() {
+        // ------ ARRANGE ------
+        let broker: Arc<dyn Broker> = Arc::new(MemoryBroker::default());
+        let config = || SSEConfig {
+            broker: Some(Arc::clone(&broker)),
+            ..SSEConfig::default()
+        };
+        let (sse_a, sse_b) = (
+            SSE::start_with(config()).unwrap(),
+            SSE::start_with(config()).unwrap(),
+        );
+        let (_, _, mut event_stream_a) = sse_a.new_connection(None, None).unwrap();
+        let (_, _, mut event_stream_b) = sse_b.new_connection(None, None).unwrap();
+        skip_epoch(&mut event_stream_a).await;
+        skip_epoch(&mut event_stream_b).await;
+
+        // ------ ACT ------
+        sse_a.broadcast("news", "1").unwrap();
+        let relayed_message = next_message(&mut event_stream_b).await.unwrap();
+        sse_b.broadcast("news", "2").unwrap();
+
+        // ------ ASSERT ------
+        assert_eq!(relayed_message, message(Some(1), "news", "1"));
+        // The own broadcast isn't relayed back.
+        assert_eq!(
+            next_message(&mut event_stream_a).await.unwrap(),
+            message(Some(1), "news", "1")
+        );
+        assert_eq!(
+            next_message(&mut event_stream_a).await.unwrap(),
+            message(Some(2), "news", "2")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_presence_events() {
+        // ------ ARRANGE ------
+        let sse = SSE::start_with(SSEConfig {
+            presence: true,
+            keep_alive_interval: Duration::from_millis(10),
+            session_grace: Duration::from_millis(50),
+            ..SSEConfig::default()
+        })
+        .unwrap();
+        let (_, _, mut event_stream) = sse.new_connection(None, None).unwrap();
+        skip_epoch(&mut event_stream).await;
+        let session_id = SessionId::new();
+
+        // ------ ACT ------
+        let (_, _, flapping_event_stream) = sse.new_connection(Some(session_id), None).unwrap();
+        let present_sessions = sse.present_sessions();
+        drop(flapping_event_stream);
+        // The reconnect within the grace isn't reported.
+        let (_, _, reconnected_event_stream) = sse.new_connection(Some(session_id), None).unwrap();
+        drop(reconnected_event_stream);
+
+        // ------ ASSERT ------
+        assert_eq!(present_sessions.len(), 2);
+        assert!(present_sessions.contains(&session_id));
+        assert_eq!(
+            next_event(&mut event_stream).await.unwrap(),
+            message(Some(1), "presence_joined", &session_id.to_string())
+        );
+        assert_eq!(
+            next_event(&mut event_stream).await.unwrap(),
+            message(Some(2), "presence_left", &session_id.to_string())
+        );
+        assert_eq!(sse.present_sessions().len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_tracked_event_is_replayed_after_reconnect() {
+        // ------ ARRANGE ------
+        let sse = SSE::start_with(ReplayBufferSize(10)).unwrap();
+        let session_id = SessionId::new();
+        let (connection_id, connection, event_stream) =
+            sse.new_connection(Some(session_id), None).unwrap();
+        let acked = connection.send_tracked("billing", "1").unwrap();
+        let not_acked = connection.send_tracked("billing", "2").unwrap();
+
+        // ------ ACT ------
+        let acks = [
+            sse.ack(&session_id, connection_id, acked.event_id()),
+            sse.ack(&SessionId::new(), connection_id, not_acked.event_id()),
+        ];
+        // The client disconnects without acking, the session waits for its reconnect.
+        drop(event_stream);
+        let (_, _, mut event_stream) = sse.new_connection(Some(session_id), Some(2)).unwrap();
+        skip_epoch(&mut event_stream).await;
+
+        // ------ ASSERT ------
+        assert_eq!(acks, [true, false]);
+        assert_eq!(acked.await, AckOutcome::Acked);
+        assert_eq!(not_acked.await, AckOutcome::ConnectionClosed);
+        assert_eq!(
+            next_message(&mut event_stream).await.unwrap(),
+            message(Some(3), "billing", "2")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_send_rejects_protocol_events() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        let session_id = connection.session_id();
+
+        // ------ ACT ------
+        let session_result = sse.send(&session_id, "down_msg", "{}");
+        let connection_result = connection.send("chunk_end", "{}");
+
+        // ------ ASSERT ------
+        assert!(matches!(
+            session_result,
+            Err(Error::ReservedEventName(ProtocolEvent::DownMsg))
+        ));
+        assert!(matches!(
+            connection_result,
+            Err(Error::ReservedEventName(ProtocolEvent::ChunkEnd))
+        ));
+        assert!(sse.send(&session_id, "down_msgs", "{}").is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_send_rejects_event_names_with_line_breaks() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        let session_id = connection.session_id();
+        let hostile_event = "chat\ndata: evil\n\nevent: admin_grant";
+
+        // ------ ACT ------
+        let results = [
+            sse.send(&session_id, hostile_event, "").map(drop),
+            connection.send(hostile_event, ""),
+            connection.send("chat\r", ""),
+            connection.send_binary(hostile_event, b"evil"),
+            connection.send_tracked(hostile_event, "").map(drop),
+        ];
+
+        // ------ ASSERT ------
+        for result in results {
+            assert!(matches!(result, Err(Error::InvalidEventName(_))));
+        }
+        assert_eq!(connection.queued_frames(), 0);
+        assert!(connection.send("chat: admin_grant", "").is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_broadcast_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let broadcast = send_rejected_names(|event| sse.broadcast(event, "1"));
+        let broadcast_except = send_rejected_names(|event| sse.broadcast_except(&[], event, "1"));
+
+        // ------ ASSERT ------
+        assert_rejected(broadcast, &connection);
+        assert_rejected(broadcast_except, &connection);
+    }
+
+    #[actix_rt::test]
+    async fn test_broadcast_report_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let reports = [
+            sse.broadcast_report("down_msg", "1"),
+            sse.broadcast_report(HOSTILE_EVENT, "1"),
+        ];
+
+        // ------ ASSERT ------
+        for report in reports {
+            assert_eq!(report.delivered, 0);
+            assert_eq!(report.failed, [connection.session_id()]);
+        }
+        assert_eq!(connection.queued_frames(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_broadcast_to_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        let session_id = connection.session_id();
+
+        // ------ ACT ------
+        let results = send_rejected_names(|event| sse.broadcast_to([&session_id], event, "1"));
+
+        // ------ ASSERT ------
+        assert_rejected(results, &connection);
+    }
+
+    #[actix_rt::test]
+    async fn test_publish_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        connection.subscribe("prices");
+
+        // ------ ACT ------
+        let results = send_rejected_names(|event| sse.publish("prices", event, "1"));
+
+        // ------ ASSERT ------
+        assert_rejected(results, &connection);
+    }
+
+    #[actix_rt::test]
+    async fn test_broadcast_where_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let results = send_rejected_names(|event| sse.broadcast_where(|_, _| true, event, "1"));
+
+        // ------ ASSERT ------
+        assert_rejected(results, &connection);
+    }
+
+    #[actix_rt::test]
+    async fn test_send_each_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let results = send_rejected_names(|event| sse.send_each(|_, _| Some("1".into()), event));
+
+        // ------ ASSERT ------
+        assert_rejected(results, &connection);
+    }
+
+    #[cfg(feature = "serde")]
+    #[actix_rt::test]
+    async fn test_broadcast_json_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let results = send_rejected_names(|event| sse.broadcast_json(event, &[1, 2]));
+
+        // ------ ASSERT ------
+        assert_rejected(results, &connection);
+    }
+
+    #[actix_rt::test]
+    async fn test_broadcast_binary_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let results = send_rejected_names(|event| sse.broadcast_binary(event, b"1"));
+
+        // ------ ASSERT ------
+        assert_rejected(results, &connection);
+    }
+
+    #[actix_rt::test]
+    async fn test_group_send_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        let group = sse.group();
+        group.add(&connection.session_id());
+
+        // ------ ACT ------
+        let results = send_rejected_names(|event| group.send(event, "1"));
+
+        // ------ ASSERT ------
+        assert_rejected(results, &connection);
+    }
+
+    #[actix_rt::test]
+    async fn test_broadcast_named_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let names = [EventName::new("down_msg"), EventName::new(HOSTILE_EVENT)];
+        // `from_static` only checks line breaks in the const context.
+        let broadcast = sse.broadcast_named(&EventName::from_static("down_msg"), "1");
+
+        // ------ ASSERT ------
+        assert!(matches!(
+            names,
+            [
+                Err(Error::ReservedEventName(ProtocolEvent::DownMsg)),
+                Err(Error::InvalidEventName(_))
+            ]
+        ));
+        assert!(matches!(
+            broadcast.unwrap_err()[..],
+            [Error::ReservedEventName(ProtocolEvent::DownMsg)]
+        ));
+        assert_eq!(connection.queued_frames(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_sse_handle_rejects_event_names() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        let handle: &dyn SSEHandle = &*sse;
+
+        // ------ ACT ------
+        let broadcast = send_rejected_names(|event| handle.broadcast(event, "1"));
+        let send = send_rejected_names(|event| {
+            handle
+                .send(&connection.session_id(), event, "1")
+                .map_err(|error| vec![error])
+        });
+
+        // ------ ASSERT ------
+        assert_rejected(broadcast, &connection);
+        assert_rejected(send, &connection);
+    }
+
+    #[actix_rt::test]
+    async fn test_close_connection_with_final_event() {
+        // ------ ARRANGE ------
+        let disconnects = Arc::new(Mutex::new(Vec::new()));
+        let on_disconnect: OnDisconnect = {
+            let disconnects = Arc::clone(&disconnects);
+            Arc::new(move |_, reason| disconnects.lock().push(reason))
+        };
+        let sse = SSE::start_with(SSEConfig {
+            on_disconnect: Some(on_disconnect),
+            ..SSEConfig::default()
+        })
+        .unwrap();
+        let (_, connection, mut event_stream) = sse.new_connection(None, None).unwrap();
+        skip_epoch(&mut event_stream).await;
+
+        // ------ ACT ------
+        let closed = [
+            connection.close(Some(("bye", "1"))),
+            connection.close(Some(("bye", "2"))),
+        ];
+
+        // ------ ASSERT ------
+        assert_eq!(closed, [true, false]);
+        assert_eq!(
+            next_message(&mut event_stream).await.unwrap(),
+            message(Some(1), "bye", "1")
+        );
+        assert!(event_stream.next().await.is_none());
+        assert_eq!(*disconnects.lock(), [DisconnectReason::Closed]);
+        assert_eq!(sse.connection_count(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_remove_connection() {
+        // ------ ARRANGE ------
+        let disconnects = Arc::new(Mutex::new(Vec::new()));
+        let on_disconnect: OnDisconnect = {
+            let disconnects = Arc::clone(&disconnects);
+            Arc::new(move |_, reason| disconnects.lock().push(reason))
+        };
+        let sse = SSE::start_with(SSEConfig {
+            on_disconnect: Some(on_disconnect),
+            ..SSEConfig::default()
+        })
+        .unwrap();
+        let (_, connection, mut event_stream) = sse.new_connection(None, None).unwrap();
+        skip_epoch(&mut event_stream).await;
+
+        // ------ ACT ------
+        let session_id = connection.session_id();
+        drop(connection);
+        sse.remove_connection(&session_id);
+
+        // ------ ASSERT ------
+        assert!(event_stream.next().await.is_none());
+        assert_eq!(*disconnects.lock(), [DisconnectReason::ExplicitRemove]);
+        assert_eq!(sse.connection_count(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_queued_frames() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, mut event_stream) = sse.new_connection(None, None).unwrap();
+        skip_epoch(&mut event_stream).await;
+        connection.send("sync", "1").unwrap();
+        connection
+            .send_with_priority("logout", "", Priority::High)
+            .unwrap();
+
+        // ------ ACT ------
+        let queued_frames = connection.queued_frames();
+        let max_queued_frames = sse.stats().max_queued_frames;
+        next_message(&mut event_stream).await.unwrap();
+
+        // ------ ASSERT ------
+        assert_eq!([queued_frames, max_queued_frames], [2, 2]);
+        assert_eq!(connection.queued_frames(), 1);
+        assert_eq!(connection.queued_messages(), Some(1));
+    }
+
+    #[actix_rt::test]
+    async fn test_removed_session_drops_scheduled_sends() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        let session_id = connection.session_id();
+        let reminder = sse.send_after(&session_id, Duration::from_secs(30), "reminder", "");
+        let warning = sse.send_after(&session_id, Duration::from_secs(60), "logout_soon", "");
+
+        // ------ ACT ------
+        let cancelled = reminder.cancel();
+        sse.kick(&session_id);
+
+        // ------ ASSERT ------
+        assert!(cancelled);
+        assert!(!warning.is_pending());
+        assert!(!sse
+            .send_after(&session_id, Duration::ZERO, "reminder", "")
+            .is_pending());
+    }
+
+    #[actix_rt::test]
+    async fn test_removed_connection_is_dropped() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let group = sse.group();
+        let (_, connection, event_stream) = sse.new_connection(None, None).unwrap();
+        let session_id = connection.session_id();
+        connection.subscribe("news");
+        assert!(group.add(&session_id));
+        let ack = connection.send_tracked("billing", "1").unwrap();
+        let reminder = sse.send_after(&session_id, Duration::from_secs(60), "reminder", "");
+        let weak_connection = Arc::downgrade(&connection);
+        drop(connection);
+
+        // ------ ACT ------
+        drop(event_stream);
+        let strong_count_while_reconnecting = weak_connection.strong_count();
+        // The remover removes the session when the client doesn't reconnect in time.
+        sse.disconnected_sessions.remove(&session_id);
+        sse.session_removed(&session_id);
+
+        // ------ ASSERT ------
+        assert_eq!(strong_count_while_reconnecting, 0);
+        assert_eq!(ack.await, AckOutcome::ConnectionClosed);
+        assert!(!reminder.is_pending());
+        assert!(group.is_empty());
+        assert_eq!(sse.topic_subscriber_count("news"), 0);
+        assert_eq!(sse.replay_buffers.len(), 0);
+        assert_eq!(sse.sessions.len(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_state_reset_after_restart() {
+        // ------ ARRANGE ------
+        let (previous_sse, sse) = (SSE::start(), SSE::start());
+        let epoch = sse.epoch().to_string();
+        let connect = |last_known_epoch| {
+            sse.connection_builder()
+                .session_id(SessionId::new())
+                .last_known_epoch(last_known_epoch)
+                .connect()
+                .unwrap()
+        };
+
+        // ------ ACT ------
+        let (_, mut restarted_event_stream) = connect(previous_sse.epoch());
+        let (connection, mut event_stream) = connect(sse.epoch());
+
+        // ------ ASSERT ------
+        assert_ne!(previous_sse.epoch(), sse.epoch());
+        assert_eq!(
+            next_message(&mut restarted_event_stream).await.unwrap(),
+            message(None, ProtocolEvent::Epoch.as_str(), &epoch)
+        );
+        assert_eq!(
+            next_message(&mut restarted_event_stream).await.unwrap(),
+            message(None, ProtocolEvent::StateReset.as_str(), "")
+        );
+        skip_epoch(&mut event_stream).await;
+        connection.send("sync", "1").unwrap();
+        assert_eq!(
+            next_message(&mut event_stream).await.unwrap(),
+            message(Some(1), "sync", "1")
+        );
+        assert_eq!(connection.info().epoch, sse.epoch());
+        assert_eq!(sse.stats().epoch, sse.epoch());
+    }
+
+    #[actix_rt::test]
+    async fn test_connect_errors() {
+        // ------ ARRANGE ------
+        let sse = SSE::start_with(SSEConfig {
+            max_connections: Some(1),
+            connection_limit_retry_after: Duration::from_secs(5),
+            reconnect_policy: ReconnectPolicy::RejectNew,
+            ..SSEConfig::default()
+        })
+        .unwrap();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let connect_error = |session_id| {
+            sse.new_connection(session_id, None)
+                .err()
+                .and_then(|error| error.connect_error())
+        };
+        let connect_errors = [
+            connect_error(None),
+            connect_error(Some(connection.session_id())),
+        ];
+        sse.close_all("bye", "");
+        let shutting_down_error = connect_error(None);
+
+        // ------ ASSERT ------
+        assert_eq!(
+            connect_errors,
+            [
+                Some(ConnectError::TooManyConnections {
+                    retry_after: Duration::from_secs(5)
+                }),
+                Some(ConnectError::SessionReplacedRejected),
+            ]
+        );
+        assert_eq!(shutting_down_error, Some(ConnectError::ShuttingDown));
+        let session_id = connection.session_id();
+        assert_eq!(Error::SessionNotFound { session_id }.connect_error(), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_lifecycle_events() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (mut events_a, mut events_b) = (sse.events(), sse.events());
+
+        // ------ ACT ------
+        let (_, connection, event_stream) = sse.new_connection(None, None).unwrap();
+        let session_id = connection.session_id();
+        sse.broadcast("news", "1").unwrap();
+        drop(event_stream);
+
+        // ------ ASSERT ------
+        let expected_events = [
+            SSELifecycleEvent::Connected { session_id },
+            SSELifecycleEvent::BroadcastCompleted {
+                delivered: 1,
+                failed: 0,
+            },
+            SSELifecycleEvent::Disconnected {
+                session_id,
+                reason: DisconnectReason::StreamDropped,
+            },
+        ];
+        for events in [&mut events_a, &mut events_b] {
+            for expected_event in &expected_events {
+                assert_eq!(events.next().await.as_ref(), Some(expected_event));
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct FlakySink(AtomicBool);
+
+    impl FrameSink for FlakySink {
+        fn try_send(&self, frame: Bytes) -> Result<(), SinkError> {
+            if self.0.load(Ordering::Relaxed) {
+                return Err(SinkError::Full(frame));
+            }
+            Ok(())
+        }
+
+        fn is_closed(&self) -> bool {
+            false
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_transient_keep_alive_failure_keeps_connection() {
+        // ------ ARRANGE ------
+        let sse = SSE::start_with(SSEConfig {
+            keep_alive_failure_threshold: 2,
+            ..SSEConfig::default()
+        })
+        .unwrap();
+        let sink = Arc::<FlakySink>::default();
+        let (_, connection) = sse
+            .new_connection_with_sink(None, None, Arc::clone(&sink) as Arc<dyn FrameSink>)
+            .unwrap();
+        let ping_all = || {
+            for shard in 0..sse.connections.shard_count() {
+                sse.ping_connections(shard);
+            }
+        };
+
+        // ------ ACT ------
+        sink.0.store(true, Ordering::Relaxed);
+        ping_all();
+        let connected_after_failure = sse.is_connected(&connection.session_id());
+        sink.0.store(false, Ordering::Relaxed);
+        ping_all();
+        sink.0.store(true, Ordering::Relaxed);
+        ping_all();
+        let connected_after_recovery = sse.is_connected(&connection.session_id());
+        ping_all();
+
+        // ------ ASSERT ------
+        assert!(connected_after_failure);
+        assert!(connected_after_recovery);
+        assert!(!sse.is_connected(&connection.session_id()));
+        assert_eq!(sse.stats().connections_removed_by_ping, 1);
+    }
+
+    #[actix_rt::test]
+    #[allow(deprecated)]
+    async fn test_broadcast_to_reports_not_connected_and_failed_sessions() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, connected, _connected_event_stream) = sse.new_connection(None, None).unwrap();
+        let (_, full, _full_event_stream) = sse
+            .new_connection_with_kind(None, None, ConnectionKind::Bounded(1))
+            .unwrap();
+        full.send("news", "0").unwrap();
+        let unknown_session_id = SessionId::new();
+        let session_ids = [
+            connected.session_id(),
+            full.session_id(),
+            unknown_session_id,
+        ];
+
+        // ------ ACT ------
+        let errors = sse.broadcast_to(&session_ids, "news", "1").unwrap_err();
+        let legacy_errors = sse
+            .broadcast_to_legacy(&session_ids, "news", "2")
+            .unwrap_err();
+
+        // ------ ASSERT ------
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|error| matches!(
+            error,
+            Error::SessionNotFound { session_id } if *session_id == unknown_session_id
+        )));
+        assert!(errors.iter().any(|error| matches!(
+            error,
+            Error::QueueFull { session_id } if *session_id == full.session_id()
+        )));
+        assert_eq!(legacy_errors.not_connected, [unknown_session_id]);
+        assert!(matches!(
+            &legacy_errors.send_failed[..],
+            [(session_id, SendError::QueueFull(_))] if *session_id == full.session_id()
+        ));
+        assert_eq!(connected.queued_frames(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_reaped_connection_leaves_topic() {
+        // ------ ARRANGE ------
+        let sse = SSE::start_with(SSEConfig {
+            keep_alive_interval: Duration::from_millis(10),
+            session_grace: Duration::from_millis(20),
+            ..SSEConfig::default()
+        })
+        .unwrap();
+        let sink = Arc::<FlakySink>::default();
+        let (_, connection) = sse
+            .new_connection_with_sink(None, None, Arc::clone(&sink) as Arc<dyn FrameSink>)
+            .unwrap();
+        connection.subscribe("prices");
+        let mut lifecycle_events = sse.events();
+
+        // ------ ACT ------
+        let subscribers_before_failure = sse.topic_subscriber_count("prices");
+        sink.0.store(true, Ordering::Relaxed);
+        let removed = actix_rt::time::timeout(Duration::from_secs(5), async {
+            while sse.topic_subscriber_count("prices") > 0 {
+                lifecycle_events.next().await;
+            }
+        })
+        .await;
+
+        // ------ ASSERT ------
+        assert_eq!(subscribers_before_failure, 1);
+        assert!(removed.is_ok());
+        assert_eq!(sse.stats().connections_removed_by_ping, 1);
+        assert!(sse.publish("prices", "price", "1").is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_bounded_queue_full_exempts_control_messages() {
+        // ------ ARRANGE ------
+        let sse = SSE::start_with(ConnectionKind::Bounded(1)).unwrap();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        let session_id = connection.session_id();
+
+        // ------ ACT ------
+        let queued = connection.send("news", "1");
+        let full = connection.send("news", "2");
+        let broadcast = sse.broadcast("news", "3");
+        connection.send_keep_alive(&KeepAlive::default());
+        let retry = sse.broadcast_retry(1000);
+
+        // ------ ASSERT ------
+        assert!(queued.is_ok());
+        assert!(matches!(full, Err(Error::QueueFull { .. })));
+        assert!(matches!(
+            broadcast.unwrap_err()[..],
+            [Error::QueueFull { session_id: failed_session_id }] if failed_session_id == session_id
+        ));
+        assert_eq!(connection.failed_keep_alives(), 0);
+        assert!(retry.is_ok());
+        assert_eq!(connection.queued_frames(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_init_events_before_broadcasts() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let session_id = SessionId::new();
+
+        // ------ ACT ------
+        let (_, _connection, mut event_stream) = sse
+            .new_connection_with_init(Some(session_id), |_| {
+                // Delivered after the initial events.
+                sse.broadcast("update", "0").unwrap();
+                vec![
+                    ("snapshot".to_owned(), "1".to_owned()),
+                    ("snapshot".to_owned(), "2".to_owned()),
+                ]
+            })
+            .unwrap();
+        sse.broadcast("update", "3").unwrap();
+
+        // ------ ASSERT ------
+        skip_epoch(&mut event_stream).await;
+        for (id, event, data) in [
+            (2, "snapshot", "1"),
+            (3, "snapshot", "2"),
+            (1, "update", "0"),
+            (4, "update", "3"),
+        ] {
+            assert_eq!(
+                next_message(&mut event_stream).await.unwrap(),
+                message(Some(id), event, data)
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    struct ClosedSink;
+
+    #[cfg(feature = "serde")]
+    impl FrameSink for ClosedSink {
+        fn try_send(&self, frame: Bytes) -> Result<(), SinkError> {
+            Err(SinkError::Closed(frame))
+        }
+
+        fn is_closed(&self) -> bool {
+            true
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[actix_rt::test]
+    async fn test_send_outcomes() {
+        // ------ ARRANGE ------
+        let sse = SSE::start_with(SSEConfig {
+            outbox: Some(Arc::new(MemoryOutbox::new(10))),
+            ..SSEConfig::default()
+        })
+        .unwrap();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        let (_, closed_connection) = sse
+            .new_connection_with_sink(None, None, Arc::new(ClosedSink))
+            .unwrap();
+        let closed_session_id = closed_connection.session_id();
+
+        // ------ ACT ------
+        let delivered = sse.send(&connection.session_id(), "chat", "1");
+        let stored = sse.send(&SessionId::new(), "chat", "2");
+        let closed = sse.send_down_msg(&closed_session_id, CorId::new(), &"3");
+
+        // ------ ASSERT ------
+        assert!(matches!(delivered, Ok(SendOutcome::Delivered)));
+        assert!(matches!(stored, Ok(SendOutcome::Stored)));
+        assert!(matches!(closed, Err(Error::ConnectionClosed { .. })));
+        // Removed without waiting for the connection remover.
+        assert!(!sse.is_connected(&closed_session_id));
+    }
+
+    #[actix_rt::test]
+    async fn test_operations_dont_wait_for_each_other() {
+        // ------ ARRANGE ------
+        const TIMEOUT: Duration = Duration::from_secs(5);
+        let sse = &SSE::start();
+        let (_, connection, _event_stream) = sse.new_connection(None, None).unwrap();
+        let session_id = connection.session_id();
+        let shard = (0..sse.connections.shard_count())
+            .find(|shard| {
+                sse.connections
+                    .shard_values(*shard)
+                    .iter()
+                    .any(|shard_connection| shard_connection.id() == connection.id())
+            })
+            .unwrap();
+        let (sweep_blocked_sender, sweep_blocked) = mpsc::channel();
+        let (sent_sender, sent) = mpsc::channel();
+
+        // ------ ACT ------
+        // The sweep's callback blocks until the `send` to the connection in the same shard finishes,
+        // a sweep holding the shard lock would make the callback time out.
+        let (sent_during_sweep, send_result) = thread::scope(|scope| {
+            let sweep = scope.spawn(move || {
+                let sent_during_sweep = AtomicBool::new(false);
+                sse.remove_shard_connections(shard, |_| {
+                    let _ = sweep_blocked_sender.send(());
+                    let sent = sent.recv_timeout(TIMEOUT).is_ok();
+                    sent_during_sweep.store(sent, Ordering::Relaxed);
+                    false
+                });
+                sent_during_sweep.into_inner()
+            });
+            let send = scope.spawn(move || {
+                sweep_blocked.recv_timeout(TIMEOUT).unwrap();
+                let result = sse.send(&session_id, "tick", "1");
+                let _ = sent_sender.send(());
+                result
+            });
+            (sweep.join().unwrap(), send.join().unwrap())
+        });
+
+        // ------ ASSERT ------
+        assert!(sent_during_sweep);
+        assert!(matches!(send_result, Ok(SendOutcome::Delivered)));
+        assert!(sse.is_connected(&session_id));
+    }
+
+    #[actix_rt::test]
+    async fn test_concurrent_connect_remove_broadcast() {
+        // ------ ARRANGE ------
+        const ROUNDS: usize = 500;
+        let sse = &SSE::start();
+        let session_ids = (0..6).map(|_| SessionId::new()).collect::<Vec<_>>();
+        let (dropped_sessions, rest) = session_ids.split_at(2);
+        let (kicked_sessions, kept_sessions) = rest.split_at(2);
+        let barrier = &Barrier::new(4);
+
+        // ------ ACT ------
+        let kept_event_streams = thread::scope(|scope| {
+            scope.spawn(move || {
+                barrier.wait();
+                for round in 0..ROUNDS {
+                    let session_id = dropped_sessions[round % dropped_sessions.len()];
+                    drop(sse.new_connection(Some(session_id), None).unwrap());
+                }
+            });
+            scope.spawn(move || {
+                barrier.wait();
+                for round in 0..ROUNDS {
+                    let session_id = kicked_sessions[round % kicked_sessions.len()];
+                    let (_, _, _event_stream) = sse.new_connection(Some(session_id), None).unwrap();
+                    sse.kick(&session_id);
+                }
+            });
+            scope.spawn(move || {
+                barrier.wait();
+                for _ in 0..ROUNDS {
+                    let _ = sse.broadcast("tick", "1");
+                }
+            });
+            let keep = scope.spawn(move || {
+                barrier.wait();
+                (0..ROUNDS / 10)
+                    .map(|round| {
+                        let session_id = kept_sessions[round % kept_sessions.len()];
+                        let (_, _, event_stream) =
+                            sse.new_connection(Some(session_id), None).unwrap();
+                        event_stream
+                    })
+                    .collect::<Vec<_>>()
+            });
+            keep.join().unwrap()
+        });
+
+        // ------ ASSERT ------
+        let mut session_connections = 0;
+        sse.sessions.for_each(|session_id, connection_ids| {
+            assert!(kept_sessions.contains(session_id));
+            for connection_id in connection_ids {
+                let connection = sse.connections.get(connection_id).unwrap();
+                assert_eq!(connection.session_id(), *session_id);
+            }
+            session_connections += connection_ids.len();
+        });
+        for connection in sse.connections.values() {
+            let connection_ids = sse.sessions.get(&connection.session_id()).unwrap();
+            assert!(connection_ids.contains(&connection.id()));
+        }
+        assert_eq!(sse.connections.len(), kept_event_streams.len());
+        assert_eq!(session_connections, kept_event_streams.len());
+    }
+
+    #[actix_rt::test]
+    #[allow(deprecated)]
+    async fn test_legacy_methods_forward_to_new_methods() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let session_id = SessionId::new();
+        let unknown_session_id = SessionId::new();
+        let (_, connection, event_stream) = sse.new_connection(Some(session_id), None).unwrap();
+
+        // ------ ACT ------
+        let sent = sse.send_legacy(&session_id, "news", "1");
+        let not_connected = sse.send_legacy(&unknown_session_id, "news", "1");
+        let broadcast = sse.broadcast_legacy("news", "1");
+        let broadcast_to = sse.broadcast_to_legacy([&session_id, &unknown_session_id], "news", "1");
+        let reserved = sse.broadcast_legacy("down_msg", "1");
+        drop(event_stream);
+        let closed = connection.send_legacy("news", "1");
+
+        // ------ ASSERT ------
+        assert!(matches!(sent, Some(Ok(()))));
+        assert!(not_connected.is_none());
+        assert!(broadcast.is_ok());
+        let broadcast_to = broadcast_to.unwrap_err();
+        assert_eq!(broadcast_to.not_connected, [unknown_session_id]);
+        assert!(broadcast_to.send_failed.is_empty());
+        assert!(matches!(
+            &reserved.unwrap_err()[..],
+            [(failed_session_id, SendError::ConnectionClosed(_))] if *failed_session_id == session_id
+        ));
+        assert!(matches!(
+            closed,
+            Err(SendError::ConnectionClosed(message)) if message == frame("news", "1").unwrap()
+        ));
+    }
+}
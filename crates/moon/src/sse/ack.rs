@@ -0,0 +1,192 @@
+use super::Error;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use tokio::time::{Duration, Instant};
+
+// ------ AckOutcome ------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckOutcome {
+    Acked,
+    /// The event hasn't been acknowledged within [SSEConfig::ack_timeout](super::SSEConfig::ack_timeout).
+    TimedOut,
+    ConnectionClosed,
+}
+
+// ------ AckHandle ------
+
+/// Returned by [Connection::send_tracked](super::Connection::send_tracked),
+/// resolves when the client acknowledges the event or when it can't be acknowledged anymore.
+pub struct AckHandle {
+    event_id: u64,
+    state: Arc<Mutex<AckState>>,
+}
+
+#[derive(Default)]
+struct AckState {
+    outcome: Option<AckOutcome>,
+    waker: Option<Waker>,
+}
+
+impl AckHandle {
+    /// The id the client sends to the ack endpoint, i.e. `lastEventId` of the event.
+    pub fn event_id(&self) -> u64 {
+        self.event_id
+    }
+}
+
+impl Future for AckHandle {
+    type Output = AckOutcome;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        match state.outcome {
+            Some(outcome) => Poll::Ready(outcome),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+fn resolve(state: &Mutex<AckState>, outcome: AckOutcome) {
+    let mut state = state.lock();
+    state.outcome = Some(outcome);
+    let waker = state.waker.take();
+    drop(state);
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+// ------ PendingAcks ------
+
+// Tracked events of one connection by their ids.
+#[derive(Default)]
+pub(crate) struct PendingAcks(Mutex<HashMap<u64, PendingAck>>);
+
+struct PendingAck {
+    deadline: Instant,
+    event: String,
+    data: String,
+    state: Arc<Mutex<AckState>>,
+}
+
+impl PendingAcks {
+    // `send` returns the id of the sent event.
+    pub(crate) fn track(
+        &self,
+        event: &str,
+        data: &str,
+        timeout: Duration,
+        send: impl FnOnce() -> Result<u64, Error>,
+    ) -> Result<AckHandle, Error> {
+        // Locked while sending, so the ack can't come before the event is tracked.
+        let mut pending_acks = self.0.lock();
+        let event_id = send()?;
+        let state = Arc::<Mutex<AckState>>::default();
+        pending_acks.insert(
+            event_id,
+            PendingAck {
+                deadline: Instant::now() + timeout,
+                event: event.to_owned(),
+                data: data.to_owned(),
+                state: Arc::clone(&state),
+            },
+        );
+        Ok(AckHandle { event_id, state })
+    }
+
+    // Returns `false` when the event isn't tracked, e.g. when it has been already acked.
+    pub(crate) fn ack(&self, event_id: u64) -> bool {
+        let Some(pending_ack) = self.0.lock().remove(&event_id) else {
+            return false;
+        };
+        resolve(&pending_ack.state, AckOutcome::Acked);
+        true
+    }
+
+    // Returns the events and data of the expired events, the oldest first.
+    pub(crate) fn expire(&self) -> Vec<(String, String)> {
+        let now = Instant::now();
+        let mut expired = Vec::new();
+        self.0.lock().retain(|event_id, pending_ack| {
+            let is_pending = now < pending_ack.deadline;
+            if !is_pending {
+                expired.push((
+                    *event_id,
+                    pending_ack.event.clone(),
+                    pending_ack.data.clone(),
+                ));
+                resolve(&pending_ack.state, AckOutcome::TimedOut);
+            }
+            is_pending
+        });
+        sorted_messages(expired)
+    }
+
+    // Like `expire`, but for all events.
+    pub(crate) fn close(&self) -> Vec<(String, String)> {
+        let pending_acks = mem::take(&mut *self.0.lock());
+        let closed = pending_acks
+            .into_iter()
+            .map(|(event_id, pending_ack)| {
+                resolve(&pending_ack.state, AckOutcome::ConnectionClosed);
+                (event_id, pending_ack.event, pending_ack.data)
+            })
+            .collect();
+        sorted_messages(closed)
+    }
+}
+
+fn sorted_messages(mut messages: Vec<(u64, String, String)>) -> Vec<(String, String)> {
+    messages.sort_unstable_by_key(|(event_id, _, _)| *event_id);
+    messages
+        .into_iter()
+        .map(|(_, event, data)| (event, data))
+        .collect()
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::task::noop_waker_ref;
+
+    #[test]
+    fn test_pending_acks() {
+        // ------ ARRANGE ------
+        let pending_acks = PendingAcks::default();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut acked = pending_acks
+            .track("billing", "1", Duration::from_secs(60), || Ok(1))
+            .unwrap();
+        let mut expired = pending_acks
+            .track("billing", "2", Duration::ZERO, || Ok(2))
+            .unwrap();
+
+        // ------ ACT ------
+        let acks = [pending_acks.ack(1), pending_acks.ack(1)];
+        let expired_messages = pending_acks.expire();
+
+        // ------ ASSERT ------
+        assert_eq!(acks, [true, false]);
+        assert_eq!(expired_messages, [("billing".to_owned(), "2".to_owned())]);
+        assert_eq!(
+            Pin::new(&mut acked).poll(&mut cx),
+            Poll::Ready(AckOutcome::Acked)
+        );
+        assert_eq!(
+            Pin::new(&mut expired).poll(&mut cx),
+            Poll::Ready(AckOutcome::TimedOut)
+        );
+        assert!(pending_acks.close().is_empty());
+    }
+}
@@ -0,0 +1,34 @@
+use actix_web::HttpRequest;
+use moonlight::SessionId;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+// ------ Auth ------
+
+/// Called before a connection is created by
+/// [new_connection_for_request](super::ShareableSSEMethods::new_connection_for_request).
+/// The request is rejected when it returns an error, otherwise the returned token
+/// is available as `connection.extension::<AuthToken>()`.
+pub type Auth =
+    Arc<dyn Fn(&HttpRequest, Option<SessionId>) -> Result<AuthToken, AuthError> + Send + Sync>;
+
+// ------ AuthToken ------
+
+/// Identifies the authenticated client, e.g. by a user id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuthToken(pub Cow<'static, str>);
+
+// ------ AuthError ------
+
+#[derive(Debug)]
+pub struct AuthError(pub Cow<'static, str>);
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SSE authentication failed: {}", self.0)
+    }
+}
+
+impl Error for AuthError {}
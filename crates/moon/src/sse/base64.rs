@@ -0,0 +1,59 @@
+// Standard base64 alphabet with padding, decodable by `atob` in browsers.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encoded_len(data_len: usize) -> usize {
+    data_len.div_ceil(3) * 4
+}
+
+// Appends the encoded data to `output`, so the caller can encode directly into a message.
+pub(crate) fn encode_into(output: &mut String, data: &[u8]) {
+    output.reserve(encoded_len(data.len()));
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let indices = [
+            bytes[0] >> 2,
+            (bytes[0] & 0b11) << 4 | bytes[1] >> 4,
+            (bytes[1] & 0b1111) << 2 | bytes[2] >> 6,
+            bytes[2] & 0b11_1111,
+        ];
+        for (position, index) in indices.into_iter().enumerate() {
+            if position <= chunk.len() {
+                output.push(char::from(ALPHABET[usize::from(index)]));
+            } else {
+                output.push('=');
+            }
+        }
+    }
+}
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut output = String::new();
+    encode_into(&mut output, data);
+    output
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        // ------ ARRANGE ------
+        let inputs: [&[u8]; 5] = [b"", b"f", b"fo", b"foo", &[0xfb, 0xff, 0x00, 0x10]];
+
+        // ------ ACT ------
+        let outputs = inputs.map(encode);
+
+        // ------ ASSERT ------
+        assert_eq!(outputs, ["", "Zg==", "Zm8=", "Zm9v", "+/8AEA=="]);
+        for (input, output) in inputs.iter().zip(&outputs) {
+            assert_eq!(encoded_len(input.len()), output.len());
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use futures::stream::{self, Stream};
+use moonlight::SessionId;
+use parking_lot::Mutex;
+use std::pin::Pin;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use uuid::Uuid;
+
+// ------ Broker ------
+
+/// Connects `SSE` instances running in other workers or processes, see [SSEConfig::broker](super::SSEConfig::broker).
+/// Broadcasts are published to the broker and messages published by the other instances
+/// are sent to the local connections. Implement it e.g. with Redis pub/sub.
+///
+/// Only broadcasts are forwarded, i.e. `broadcast`, `broadcast_except`, `broadcast_to`,
+/// `publish`, their JSON variants, `broadcast_prepared`, `broadcast_named` and `broadcast_binary`.
+/// Their errors describe only the local connections.
+pub trait Broker: Send + Sync {
+    fn publish(&self, message: BrokerMessage);
+
+    /// Messages published by all instances including this one,
+    /// the own messages are skipped by their [BrokerMessage::origin].
+    fn subscribe(&self) -> Pin<Box<dyn Stream<Item = BrokerMessage> + Send>>;
+}
+
+// ------ BrokerMessage ------
+
+#[derive(Debug, Clone)]
+pub struct BrokerMessage {
+    pub origin: OriginId,
+    pub event: String,
+    /// Binary data is forwarded base64-encoded, with the `-b64` event suffix.
+    pub data: String,
+    pub target: BroadcastTarget,
+}
+
+// ------ BroadcastTarget ------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BroadcastTarget {
+    All,
+    Except(Vec<SessionId>),
+    Sessions(Vec<SessionId>),
+    Topic(String),
+}
+
+// ------ OriginId ------
+
+/// Identifies the `SSE` instance that has published the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OriginId(Uuid);
+
+impl OriginId {
+    pub(crate) fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl From<Uuid> for OriginId {
+    fn from(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+}
+
+impl From<OriginId> for Uuid {
+    fn from(origin: OriginId) -> Self {
+        origin.0
+    }
+}
+
+// ------ MemoryBroker ------
+
+/// In-process [Broker], e.g. for `SSE` instances of multiple actix workers.
+#[derive(Default)]
+pub struct MemoryBroker {
+    subscribers: Mutex<Vec<UnboundedSender<BrokerMessage>>>,
+}
+
+impl Broker for MemoryBroker {
+    fn publish(&self, message: BrokerMessage) {
+        self.subscribers
+            .lock()
+            .retain(|subscriber| subscriber.send(message.clone()).is_ok());
+    }
+
+    fn subscribe(&self) -> Pin<Box<dyn Stream<Item = BrokerMessage> + Send>> {
+        let (sender, mut receiver) = unbounded_channel();
+        self.subscribers.lock().push(sender);
+        Box::pin(stream::poll_fn(move |cx| receiver.poll_recv(cx)))
+    }
+}
@@ -0,0 +1,265 @@
+use super::drop_oldest::{drop_oldest_channel, DropOldestReceiver, DropOldestSender};
+use super::message::Frame;
+use super::sink::FrameSink;
+use super::stats::Counters;
+use actix_web::web::Bytes;
+use parking_lot::Mutex;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc::{
+    self, error::TrySendError, unbounded_channel, Receiver, Sender, UnboundedReceiver,
+    UnboundedSender,
+};
+
+// ------ ConnectionKind ------
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionKind {
+    #[default]
+    Unbounded,
+    /// At most the given number of messages may wait for a slow client,
+    /// sending another one fails with [SendError::QueueFull].
+    /// Keep-alive messages don't count toward the limit.
+    Bounded(usize),
+    /// At most `keep` messages wait for a slow client, sending another one evicts
+    /// the oldest waiting message, counted in [SSEStats::dropped_messages](super::SSEStats::dropped_messages).
+    /// Evicted events leave gaps in the event ids received by the client
+    /// and they are still replayed after a reconnect.
+    /// High priority and keep-alive messages are never evicted.
+    DropOldest { keep: usize },
+}
+
+impl ConnectionKind {
+    pub(crate) fn high_priority(self) -> Self {
+        match self {
+            Self::DropOldest { keep } => Self::Bounded(keep),
+            kind => kind,
+        }
+    }
+}
+
+// ------ Priority ------
+
+/// High priority events overtake the events waiting in the queue of the connection,
+/// events of the same priority are written in order.
+/// High priority events don't get an event id and aren't replayed after a reconnect,
+/// otherwise the client's `Last-Event-ID` could skip the overtaken events.
+/// They aren't rate limited and their queue has the same capacity as the normal one,
+/// but they're never evicted by [ConnectionKind::DropOldest].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    #[default]
+    Normal,
+    High,
+}
+
+// ------ SendError ------
+
+#[derive(Debug)]
+pub enum SendError {
+    ConnectionClosed(Bytes),
+    QueueFull(Bytes),
+}
+
+impl SendError {
+    pub fn into_message(self) -> Bytes {
+        match self {
+            Self::ConnectionClosed(message) | Self::QueueFull(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConnectionClosed(_) => write!(f, "SSE connection is closed"),
+            Self::QueueFull(_) => write!(f, "SSE connection queue is full"),
+        }
+    }
+}
+
+impl Error for SendError {}
+
+// ------ channel ------
+
+pub(crate) fn channel(
+    kind: ConnectionKind,
+    counters: &Arc<Counters>,
+) -> (MessageSender, MessageReceiver) {
+    match kind {
+        ConnectionKind::Unbounded => {
+            let (sender, receiver) = unbounded_channel();
+            let queued_messages = Arc::<AtomicUsize>::default();
+            (
+                MessageSender::Unbounded(sender, Arc::clone(&queued_messages)),
+                MessageReceiver::Unbounded(receiver, queued_messages),
+            )
+        }
+        ConnectionKind::Bounded(capacity) => {
+            // Tokio panics on zero capacity.
+            let (sender, receiver) = mpsc::channel(capacity.max(1));
+            (
+                MessageSender::Bounded(sender),
+                MessageReceiver::Bounded(receiver),
+            )
+        }
+        ConnectionKind::DropOldest { keep } => {
+            let (sender, receiver) = drop_oldest_channel(keep, Arc::clone(counters));
+            (
+                MessageSender::DropOldest(sender),
+                MessageReceiver::DropOldest(receiver),
+            )
+        }
+    }
+}
+
+// ------ MessageSender ------
+
+#[derive(Clone)]
+pub(crate) enum MessageSender {
+    // Tokio doesn't count the messages in unbounded channels.
+    Unbounded(UnboundedSender<Frame>, Arc<AtomicUsize>),
+    Bounded(Sender<Frame>),
+    DropOldest(DropOldestSender),
+    Sink(Arc<dyn FrameSink>),
+    Held(Arc<HeldSender>),
+}
+
+impl MessageSender {
+    // Holds the messages until `HeldSender::release`, see `HeldSender`.
+    pub(crate) fn held(self) -> Self {
+        Self::Held(Arc::new(HeldSender {
+            sender: self,
+            released: AtomicBool::new(false),
+            messages: Mutex::new(Some(Vec::new())),
+        }))
+    }
+
+    // Replaces a sender to end the `EventStream` once the queued messages are sent.
+    pub(crate) fn closed() -> Self {
+        let (sender, _) = unbounded_channel();
+        Self::Unbounded(sender, Arc::default())
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        match self {
+            Self::Unbounded(sender, _) => sender.is_closed(),
+            Self::Bounded(sender) => sender.is_closed(),
+            Self::DropOldest(sender) => sender.is_closed(),
+            Self::Sink(sink) => sink.is_closed(),
+            Self::Held(held) => held.sender.is_closed(),
+        }
+    }
+
+    pub(crate) fn queued_messages(&self) -> Option<usize> {
+        match self {
+            Self::Unbounded(_, queued_messages) => Some(queued_messages.load(Ordering::Relaxed)),
+            Self::Sink(_) => None,
+            Self::Bounded(sender) => Some(sender.max_capacity() - sender.capacity()),
+            Self::DropOldest(sender) => Some(sender.queued_messages()),
+            Self::Held(held) => held.sender.queued_messages(),
+        }
+    }
+
+    // Sinks get the `id:` line and the body joined into one frame.
+    pub(crate) fn send(&self, message: impl Into<Frame>) -> Result<(), SendError> {
+        let message = message.into();
+        match self {
+            Self::Unbounded(sender, queued_messages) => {
+                // Incremented before sending, so the receiver never decrements below zero.
+                queued_messages.fetch_add(1, Ordering::Relaxed);
+                sender.send(message).map_err(|error| {
+                    queued_messages.fetch_sub(1, Ordering::Relaxed);
+                    SendError::ConnectionClosed(error.0.into_bytes())
+                })
+            }
+            Self::Bounded(sender) => sender.try_send(message).map_err(|error| match error {
+                TrySendError::Full(message) => SendError::QueueFull(message.into_bytes()),
+                TrySendError::Closed(message) => SendError::ConnectionClosed(message.into_bytes()),
+            }),
+            Self::DropOldest(sender) => sender
+                .send(message)
+                .map_err(|message| SendError::ConnectionClosed(message.into_bytes())),
+            Self::Sink(sink) => sink.try_send(message.into_bytes()).map_err(SendError::from),
+            Self::Held(held) => held.send(message),
+        }
+    }
+
+    // Like `send`, but the message isn't held by `HeldSender`.
+    pub(crate) fn send_now(&self, message: impl Into<Frame>) -> Result<(), SendError> {
+        match self {
+            Self::Held(held) => held.sender.send(message),
+            sender => sender.send(message),
+        }
+    }
+
+    // Sends the held messages, returns the ones that couldn't be sent.
+    pub(crate) fn release(&self) -> Vec<SendError> {
+        match self {
+            Self::Held(held) => held.release(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+// ------ HeldSender ------
+
+// Broadcasts reaching a connection while its initial events are being prepared
+// wait here, so they're written after the initial events sent by `send_now`.
+pub(crate) struct HeldSender {
+    sender: MessageSender,
+    released: AtomicBool,
+    messages: Mutex<Option<Vec<Frame>>>,
+}
+
+impl HeldSender {
+    fn send(&self, message: Frame) -> Result<(), SendError> {
+        if !self.released.load(Ordering::Acquire) {
+            if let Some(messages) = self.messages.lock().as_mut() {
+                messages.push(message);
+                return Ok(());
+            }
+        }
+        self.sender.send(message)
+    }
+
+    // Messages sent during the release wait for the lock, so they can't overtake the held ones.
+    fn release(&self) -> Vec<SendError> {
+        let mut messages = self.messages.lock();
+        let errors = messages
+            .take()
+            .into_iter()
+            .flatten()
+            .filter_map(|message| self.sender.send(message).err())
+            .collect();
+        self.released.store(true, Ordering::Release);
+        errors
+    }
+}
+
+// ------ MessageReceiver ------
+
+pub(crate) enum MessageReceiver {
+    Unbounded(UnboundedReceiver<Frame>, Arc<AtomicUsize>),
+    Bounded(Receiver<Frame>),
+    DropOldest(DropOldestReceiver),
+}
+
+impl MessageReceiver {
+    pub(crate) fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<Frame>> {
+        match self {
+            Self::Unbounded(receiver, queued_messages) => {
+                let message = receiver.poll_recv(cx);
+                if let Poll::Ready(Some(_)) = message {
+                    queued_messages.fetch_sub(1, Ordering::Relaxed);
+                }
+                message
+            }
+            Self::Bounded(receiver) => receiver.poll_recv(cx),
+            Self::DropOldest(receiver) => receiver.poll_recv(cx),
+        }
+    }
+}
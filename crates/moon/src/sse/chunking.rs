@@ -0,0 +1,220 @@
+use super::config::{FrameSizeExceeded, FrameSizeLimit};
+use super::{Error, PreparedEvent};
+use moonlight::ProtocolEvent;
+use std::fmt::Write;
+use std::iter;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub(crate) const CHUNK_EVENT: &str = ProtocolEvent::Chunk.as_str();
+pub(crate) const CHUNK_END_EVENT: &str = ProtocolEvent::ChunkEnd.as_str();
+
+// The chunk without the event name and the data, with the longest `u64` numbers.
+const ENVELOPE_BYTES: usize = r#"{"id":,"seq":,"total":,"event":,"data":""}"#.len() + 3 * 20;
+
+// ------ split_payload ------
+
+// Returns `None` when the data doesn't exceed the limit and can be sent as one event.
+pub(crate) fn split_payload(
+    limit: Option<FrameSizeLimit>,
+    event: &str,
+    data: &str,
+) -> Result<Option<Vec<(&'static str, String)>>, Error> {
+    let Some(limit) = limit else {
+        return Ok(None);
+    };
+    if data.len() <= limit.max_bytes {
+        return Ok(None);
+    }
+    match limit.on_exceeded {
+        FrameSizeExceeded::Reject => Err(Error::PayloadTooLarge {
+            size: data.len(),
+            max_frame_size: limit.max_bytes,
+        }),
+        FrameSizeExceeded::Chunk => {
+            // The name is sent in the chunk data, it's checked like the names of other events.
+            Error::check_line_breaks(event)?;
+            Ok(Some(chunks(limit.max_bytes, event, data)))
+        }
+    }
+}
+
+// Like `split_payload`, the body is always longer than the data,
+// so the event is decoded only when its body exceeds the limit.
+pub(crate) fn split_prepared(
+    limit: Option<FrameSizeLimit>,
+    prepared_event: &PreparedEvent,
+) -> Result<Option<Vec<(&'static str, String)>>, Error> {
+    match limit {
+        Some(limit) if prepared_event.body().len() > limit.max_bytes => {
+            let (event, data) = prepared_event.decode();
+            split_payload(Some(limit), &event, &data)
+        }
+        _ => Ok(None),
+    }
+}
+
+// Every chunk fits into `max_bytes` once the fragment is escaped and wrapped in the envelope,
+// unless the limit is smaller than the envelope with one character.
+fn chunks(max_bytes: usize, event: &str, data: &str) -> Vec<(&'static str, String)> {
+    // Chunks of different events sent at the same time may be interleaved.
+    static LAST_ID: AtomicU64 = AtomicU64::new(0);
+    let id = LAST_ID.fetch_add(1, Ordering::Relaxed) + 1;
+
+    let envelope_bytes = ENVELOPE_BYTES + json_string_len(event);
+    let fragments = fragments(data, max_bytes.saturating_sub(envelope_bytes)).collect::<Vec<_>>();
+    let total = fragments.len();
+    let mut chunks = fragments
+        .into_iter()
+        .enumerate()
+        .map(|(seq, fragment)| {
+            let mut chunk = String::with_capacity(envelope_bytes + json_string_len(fragment));
+            let _ = write!(chunk, r#"{{"id":{id},"seq":{seq},"total":{total},"event":"#);
+            push_json_string(&mut chunk, event);
+            chunk.push_str(r#","data":"#);
+            push_json_string(&mut chunk, fragment);
+            chunk.push('}');
+            (CHUNK_EVENT, chunk)
+        })
+        .collect::<Vec<_>>();
+
+    let mut chunk_end = format!(r#"{{"id":{id},"event":"#);
+    push_json_string(&mut chunk_end, event);
+    chunk_end.push('}');
+    chunks.push((CHUNK_END_EVENT, chunk_end));
+    chunks
+}
+
+// Splits the text into parts of at most `max_bytes` once they're escaped by `push_json_string`,
+// without splitting characters.
+fn fragments(text: &str, max_bytes: usize) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    iter::from_fn(move || {
+        let mut chars = rest.char_indices();
+        // The first character is taken even when the limit is smaller than the character.
+        let (_, first_char) = chars.next()?;
+        let mut escaped_len = json_char_len(first_char);
+        let end = chars
+            .find(|(_, char)| {
+                escaped_len += json_char_len(*char);
+                escaped_len > max_bytes
+            })
+            .map_or(rest.len(), |(index, _)| index);
+        let (fragment, tail) = rest.split_at(end);
+        rest = tail;
+        Some(fragment)
+    })
+}
+
+fn push_json_string(json: &mut String, text: &str) {
+    json.push('"');
+    for char in text.chars() {
+        match char {
+            '"' => json.push_str(r#"\""#),
+            '\\' => json.push_str(r"\\"),
+            '\n' => json.push_str(r"\n"),
+            '\r' => json.push_str(r"\r"),
+            '\t' => json.push_str(r"\t"),
+            char if char < ' ' => {
+                let _ = write!(json, r"\u{:04x}", u32::from(char));
+            }
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+}
+
+// The length of the text encoded by `push_json_string`, including the quotes.
+fn json_string_len(text: &str) -> usize {
+    text.chars().map(json_char_len).sum::<usize>() + 2
+}
+
+fn json_char_len(char: char) -> usize {
+    match char {
+        '"' | '\\' | '\n' | '\r' | '\t' => 2,
+        char if char < ' ' => 6,
+        char => char.len_utf8(),
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sse::message::message;
+
+    #[test]
+    fn test_chunk_large_payload() {
+        // ------ ARRANGE ------
+        // 4 bytes of the escaped data fit into every chunk.
+        let limit = FrameSizeLimit {
+            max_bytes: ENVELOPE_BYTES + r#""file""#.len() + 4,
+            on_exceeded: FrameSizeExceeded::Chunk,
+        };
+
+        let data = format!("abc\"é\nxy{}", "z".repeat(limit.max_bytes));
+
+        // ------ ACT ------
+        let chunks = split_payload(Some(limit), "file", &data).unwrap().unwrap();
+
+        // ------ ASSERT ------
+        let id = chunks[0].1[6..].split(',').next().unwrap().to_owned();
+        assert_eq!(chunks.len(), 3 + limit.max_bytes / 4 + 1);
+        assert_eq!(
+            chunks[..3],
+            [
+                (
+                    CHUNK_EVENT,
+                    format!(r#"{{"id":{id},"seq":0,"total":31,"event":"file","data":"abc"}}"#)
+                ),
+                (
+                    CHUNK_EVENT,
+                    format!(r#"{{"id":{id},"seq":1,"total":31,"event":"file","data":"\"é"}}"#)
+                ),
+                (
+                    CHUNK_EVENT,
+                    format!(r#"{{"id":{id},"seq":2,"total":31,"event":"file","data":"\nxy"}}"#)
+                ),
+            ]
+        );
+        assert_eq!(
+            chunks[30..],
+            [
+                (
+                    CHUNK_EVENT,
+                    format!(r#"{{"id":{id},"seq":30,"total":31,"event":"file","data":"zzzz"}}"#)
+                ),
+                (CHUNK_END_EVENT, format!(r#"{{"id":{id},"event":"file"}}"#)),
+            ]
+        );
+        assert!(split_payload(Some(limit), "file", "abcd")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_escaped_chunks_fit_into_limit() {
+        // ------ ARRANGE ------
+        let max_bytes = 256;
+        let limit = FrameSizeLimit {
+            max_bytes,
+            on_exceeded: FrameSizeExceeded::Chunk,
+        };
+        // The escaped characters are 2 or 6 times longer than the raw ones.
+        let data = "\"\\\u{1}é\n".repeat(200);
+        // `id`, `event` and `data` field names of the frame with the longest `u64` id.
+        let header_budget = message(Some(u64::MAX), CHUNK_EVENT, "").len();
+
+        // ------ ACT ------
+        let chunks = split_payload(Some(limit), "file\"", &data)
+            .unwrap()
+            .unwrap();
+
+        // ------ ASSERT ------
+        assert!(chunks.len() > data.len() / max_bytes);
+        for (event, chunk) in chunks {
+            assert!(chunk.len() <= max_bytes, "{chunk}");
+            assert!(message(Some(u64::MAX), event, &chunk).len() <= max_bytes + header_budget);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use super::auth::Auth;
+use super::broker::Broker;
+use super::hooks::{OnConnect, OnDisconnect};
+use super::message::{comment, MessageBody};
+use super::outbox::SessionOutbox;
+use super::{ConnectionKind, FrameFormatter, ReplayBufferSize};
+use actix_web::web::Bytes;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ------ SSEConfig ------
+
+#[derive(Clone)]
+pub struct SSEConfig {
+    pub replay_buffer_size: ReplayBufferSize,
+    pub connection_kind: ConnectionKind,
+    /// How often the keep-alive message is sent to detect dead connections.
+    /// It's skipped for connections that have written an event to the client during the interval.
+    pub keep_alive_interval: Duration,
+    pub keep_alive: KeepAlive,
+    /// The connection is removed when it hasn't accepted so many keep-alive messages in a row.
+    /// `1` removes it after the first failed keep-alive.
+    pub keep_alive_failure_threshold: u32,
+    /// Reconnection delay in milliseconds sent to clients as the first message.
+    pub initial_retry: Option<u32>,
+    pub reconnect_policy: ReconnectPolicy,
+    /// How long the session actor and the session state, e.g. the replay buffer and topic
+    /// subscriptions, are kept after the last connection of the session has been lost,
+    /// e.g. while a laptop sleeps. A reconnect within the grace keeps them.
+    /// The state is removed by the first sweep after the grace, see `keep_alive_interval`.
+    /// `Duration::ZERO` removes the actor together with the connection.
+    pub session_grace: Duration,
+    /// Connections without any client activity, reported by
+    /// [touch](super::ShareableSSEMethods::touch), for so long are closed, even when
+    /// keep-alive messages are accepted, e.g. by a proxy. Watch
+    /// [ConnectionInfo::idle_duration](super::ConnectionInfo::idle_duration)
+    /// or [SSEStats::max_idle_duration](super::SSEStats::max_idle_duration) to choose it.
+    pub max_idle: Option<Duration>,
+    /// New sessions are refused with [`Error::ConnectionLimitReached`](super::Error::ConnectionLimitReached)
+    /// when there are already so many connections. Connected sessions may still reconnect.
+    pub max_connections: Option<usize>,
+    /// Sent to clients refused because of `max_connections` in the `Retry-After` header,
+    /// see [ConnectError::to_response](super::ConnectError::to_response).
+    pub connection_limit_retry_after: Duration,
+    /// Messages are written in bigger chunks, `None` writes each message immediately.
+    pub batching: Option<Batching>,
+    /// Events with bigger `data` are rejected or split. Keep-alives aren't limited.
+    /// It applies to `send`, `broadcast`, `broadcast_except`, `broadcast_to`, `publish`,
+    /// their JSON variants and `Connection::send`.
+    pub max_frame_size: Option<FrameSizeLimit>,
+    /// Limits how many events per second each connection writes, keep-alives aren't limited.
+    pub rate_limit: Option<RateLimit>,
+    /// How many events wait for [Connection::resume](super::Connection::resume)
+    /// of a paused connection, the oldest ones are dropped.
+    pub pause_buffer_size: usize,
+    /// How long events sent by [Connection::send_tracked](super::Connection::send_tracked)
+    /// wait for the acknowledgement. They're checked every `keep_alive_interval`.
+    pub ack_timeout: Duration,
+    /// Stores messages sent to sessions without a connection. The outbox is flushed
+    /// to the next connection of the session before live messages and before `on_connect`.
+    pub outbox: Option<Arc<dyn SessionOutbox>>,
+    /// Forwards broadcasts to `SSE` instances in other workers or processes,
+    /// `None` keeps them local. See [Broker](super::Broker).
+    pub broker: Option<Arc<dyn Broker>>,
+    /// Broadcasts `presence_joined` when a new session connects and `presence_left`
+    /// when it's removed, i.e. not after a reconnect within `session_grace`.
+    /// Their data is the session id, parsable by `SessionId::from_str`.
+    /// See [present_sessions](super::SSE::present_sessions).
+    pub presence: bool,
+    pub auth: Option<Auth>,
+    /// Encodes all events including keep-alive events, `None` uses [DefaultFrameFormatter](super::DefaultFrameFormatter).
+    pub formatter: Option<Arc<dyn FrameFormatter>>,
+    pub on_connect: Option<OnConnect>,
+    pub on_disconnect: Option<OnDisconnect>,
+}
+
+impl Default for SSEConfig {
+    fn default() -> Self {
+        Self {
+            replay_buffer_size: ReplayBufferSize::default(),
+            connection_kind: ConnectionKind::default(),
+            keep_alive_interval: Duration::from_secs(10),
+            keep_alive: KeepAlive::default(),
+            keep_alive_failure_threshold: 1,
+            initial_retry: None,
+            reconnect_policy: ReconnectPolicy::default(),
+            session_grace: Duration::from_secs(60),
+            max_idle: None,
+            max_connections: None,
+            connection_limit_retry_after: Duration::from_secs(10),
+            batching: None,
+            max_frame_size: None,
+            rate_limit: None,
+            pause_buffer_size: 1000,
+            ack_timeout: Duration::from_secs(30),
+            outbox: None,
+            broker: None,
+            presence: false,
+            auth: None,
+            formatter: None,
+            on_connect: None,
+            on_disconnect: None,
+        }
+    }
+}
+
+impl fmt::Debug for SSEConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SSEConfig")
+            .field("replay_buffer_size", &self.replay_buffer_size)
+            .field("connection_kind", &self.connection_kind)
+            .field("keep_alive_interval", &self.keep_alive_interval)
+            .field("keep_alive", &self.keep_alive)
+            .field(
+                "keep_alive_failure_threshold",
+                &self.keep_alive_failure_threshold,
+            )
+            .field("initial_retry", &self.initial_retry)
+            .field("reconnect_policy", &self.reconnect_policy)
+            .field("session_grace", &self.session_grace)
+            .field("max_idle", &self.max_idle)
+            .field("max_connections", &self.max_connections)
+            .field(
+                "connection_limit_retry_after",
+                &self.connection_limit_retry_after,
+            )
+            .field("batching", &self.batching)
+            .field("max_frame_size", &self.max_frame_size)
+            .field("rate_limit", &self.rate_limit)
+            .field("pause_buffer_size", &self.pause_buffer_size)
+            .field("ack_timeout", &self.ack_timeout)
+            .field("outbox", &self.outbox.is_some())
+            .field("broker", &self.broker.is_some())
+            .field("presence", &self.presence)
+            .field("auth", &self.auth.is_some())
+            .field("formatter", &self.formatter.is_some())
+            .field("on_connect", &self.on_connect.is_some())
+            .field("on_disconnect", &self.on_disconnect.is_some())
+            .finish()
+    }
+}
+
+impl SSEConfig {
+    pub(crate) fn validate(&self) -> Result<(), SSEConfigError> {
+        if self.keep_alive_interval.is_zero() {
+            return Err(SSEConfigError::ZeroKeepAliveInterval);
+        }
+        if self.keep_alive_failure_threshold == 0 {
+            return Err(SSEConfigError::ZeroKeepAliveFailureThreshold);
+        }
+        if let Some(RateLimit {
+            max_events_per_second: 0,
+            ..
+        }) = self.rate_limit
+        {
+            return Err(SSEConfigError::ZeroRateLimit);
+        }
+        if let Some(FrameSizeLimit { max_bytes: 0, .. }) = self.max_frame_size {
+            return Err(SSEConfigError::ZeroMaxFrameSize);
+        }
+        if self.max_idle.is_some_and(|max_idle| max_idle.is_zero()) {
+            return Err(SSEConfigError::ZeroMaxIdle);
+        }
+        if let KeepAlive::Event(event) = &self.keep_alive {
+            if event.contains(['\r', '\n']) {
+                return Err(SSEConfigError::InvalidKeepAliveEvent(event.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<ReplayBufferSize> for SSEConfig {
+    fn from(replay_buffer_size: ReplayBufferSize) -> Self {
+        Self {
+            replay_buffer_size,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<ConnectionKind> for SSEConfig {
+    fn from(connection_kind: ConnectionKind) -> Self {
+        Self {
+            connection_kind,
+            ..Self::default()
+        }
+    }
+}
+
+// ------ KeepAlive ------
+
+#[derive(Debug, Clone)]
+pub enum KeepAlive {
+    /// SSE comment line, silently discarded by browsers.
+    Comment(Cow<'static, str>),
+    /// Regular event with empty data, visible to `EventSource` listeners.
+    Event(Cow<'static, str>),
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self::Comment(Cow::Borrowed("keep-alive"))
+    }
+}
+
+impl KeepAlive {
+    // The event name is checked by `SSEConfig::validate`.
+    pub(crate) fn message(
+        &self,
+        formatter: Option<&dyn FrameFormatter>,
+    ) -> Result<Bytes, super::Error> {
+        match self {
+            Self::Comment(text) => Ok(comment(text)),
+            Self::Event(event) => {
+                MessageBody::new(formatter, event, "").map(|body| body.message(None))
+            }
+        }
+    }
+}
+
+// ------ Batching ------
+
+/// Messages are collected and written as one chunk when the `window` since the first
+/// collected message elapses or when the batch has at least `max_bytes`.
+#[derive(Debug, Clone, Copy)]
+pub struct Batching {
+    pub window: Duration,
+    pub max_bytes: usize,
+}
+
+impl Default for Batching {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(5),
+            max_bytes: 16 * 1024,
+        }
+    }
+}
+
+// ------ FrameSizeLimit ------
+
+#[derive(Debug, Clone, Copy)]
+pub struct FrameSizeLimit {
+    /// The maximum length of the event `data`.
+    pub max_bytes: usize,
+    pub on_exceeded: FrameSizeExceeded,
+}
+
+/// What happens with events over the [FrameSizeLimit].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FrameSizeExceeded {
+    /// Sending fails with [Error::PayloadTooLarge](super::Error::PayloadTooLarge).
+    #[default]
+    Reject,
+    /// The data is sent in `chunk` events with JSON `{"id", "seq", "total", "event", "data"}`,
+    /// each at most `max_bytes` long including the escaped data, followed by a `chunk_end` event
+    /// with `{"id", "event"}`. The client joins the `data` of the chunks with the same `id`.
+    Chunk,
+}
+
+// ------ RateLimit ------
+
+/// Token bucket allowing bursts of up to `max_events_per_second` events.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub max_events_per_second: u32,
+    pub on_exceeded: RateLimitExceeded,
+}
+
+/// What happens with events over the [RateLimit].
+/// They are counted in [SSEStats::rate_limited_messages](super::SSEStats::rate_limited_messages)
+/// and [Connection::rate_limited_messages](super::Connection::rate_limited_messages).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitExceeded {
+    /// The events are discarded.
+    Drop,
+    /// Only the newest event of each event name waits until the limit allows to send it.
+    #[default]
+    Coalesce,
+}
+
+// ------ ReconnectPolicy ------
+
+/// What happens when a session with a connection opens another one.
+#[derive(Debug, Clone, Default)]
+pub enum ReconnectPolicy {
+    /// All connections of the session, e.g. browser tabs, receive the session messages.
+    #[default]
+    KeepAll,
+    /// The previous connections are closed, optionally after sending the given event to them.
+    ReplaceOld {
+        superseded_event: Option<Cow<'static, str>>,
+    },
+    /// The new connection is refused with [`Error::ConnectionRejected`](super::Error::ConnectionRejected).
+    RejectNew,
+}
+
+// ------ SSEConfigError ------
+
+#[derive(Debug)]
+pub enum SSEConfigError {
+    ZeroKeepAliveInterval,
+    ZeroKeepAliveFailureThreshold,
+    ZeroRateLimit,
+    ZeroMaxIdle,
+    ZeroMaxFrameSize,
+    /// The [KeepAlive::Event] name contains a line break.
+    InvalidKeepAliveEvent(String),
+}
+
+impl fmt::Display for SSEConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroKeepAliveInterval => write!(f, "SSE keep-alive interval cannot be zero"),
+            Self::ZeroKeepAliveFailureThreshold => {
+                write!(f, "SSE keep-alive failure threshold cannot be zero")
+            }
+            Self::ZeroRateLimit => write!(f, "SSE rate limit cannot be zero events per second"),
+            Self::ZeroMaxIdle => write!(f, "SSE max idle duration cannot be zero"),
+            Self::ZeroMaxFrameSize => write!(f, "SSE max frame size cannot be zero bytes"),
+            Self::InvalidKeepAliveEvent(event) => {
+                write!(
+                    f,
+                    "SSE keep-alive event name {event:?} contains a line break"
+                )
+            }
+        }
+    }
+}
+
+impl Error for SSEConfigError {}
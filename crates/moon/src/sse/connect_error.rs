@@ -0,0 +1,139 @@
+use actix_web::http::header::{ContentType, RETRY_AFTER};
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+// ------ ConnectError ------
+
+/// Why the connection hasn't been created, see [Error::connect_error](super::Error::connect_error).
+/// [to_response](Self::to_response) tells the client whether it should back off,
+/// log in again or give up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectError {
+    /// See [SSEConfig::max_connections](super::SSEConfig::max_connections).
+    TooManyConnections {
+        retry_after: Duration,
+    },
+    Unauthorized,
+    /// The session is connected in another tab and
+    /// [ReconnectPolicy::RejectNew](super::ReconnectPolicy::RejectNew) is set.
+    SessionReplacedRejected,
+    /// The connections have been closed by [close_all](super::ShareableSSEMethods::close_all).
+    ShuttingDown,
+}
+
+impl ConnectError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::TooManyConnections { .. } | Self::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::SessionReplacedRejected => StatusCode::CONFLICT,
+        }
+    }
+
+    /// The response with the status code, the `Retry-After` header when the client
+    /// should try again later and the JSON body, e.g.
+    /// `{"error":"too_many_connections","action":"backoff","retry_after":10}`.
+    /// The `action` is `backoff`, `login` or `give_up`.
+    pub fn to_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let Self::TooManyConnections { retry_after } = self {
+            response.insert_header((RETRY_AFTER, retry_after_seconds(*retry_after).to_string()));
+        }
+        response
+            .content_type(ContentType::json())
+            .body(self.json_body())
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::TooManyConnections { .. } => "too_many_connections",
+            Self::Unauthorized => "unauthorized",
+            Self::SessionReplacedRejected => "session_replaced_rejected",
+            Self::ShuttingDown => "shutting_down",
+        }
+    }
+
+    fn action(&self) -> &'static str {
+        match self {
+            // The server is restarting, the new process accepts the connection.
+            Self::TooManyConnections { .. } | Self::ShuttingDown => "backoff",
+            Self::Unauthorized => "login",
+            Self::SessionReplacedRejected => "give_up",
+        }
+    }
+
+    // Only fixed strings and numbers, so it doesn't need `serde`.
+    fn json_body(&self) -> String {
+        let (code, action) = (self.code(), self.action());
+        match self {
+            Self::TooManyConnections { retry_after } => {
+                let retry_after = retry_after_seconds(*retry_after);
+                format!(r#"{{"error":"{code}","action":"{action}","retry_after":{retry_after}}}"#)
+            }
+            _ => format!(r#"{{"error":"{code}","action":"{action}"}}"#),
+        }
+    }
+}
+
+// `Retry-After` has whole seconds, the client shouldn't come back too early.
+fn retry_after_seconds(retry_after: Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyConnections { retry_after } => {
+                write!(f, "too many SSE connections, retry after {retry_after:?}")
+            }
+            Self::Unauthorized => write!(f, "SSE request is unauthorized"),
+            Self::SessionReplacedRejected => {
+                write!(f, "the session already has an SSE connection")
+            }
+            Self::ShuttingDown => write!(f, "SSE is shutting down"),
+        }
+    }
+}
+
+impl Error for ConnectError {}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_body() {
+        // ------ ARRANGE ------
+        let errors = [
+            ConnectError::TooManyConnections {
+                retry_after: Duration::from_millis(2500),
+            },
+            ConnectError::Unauthorized,
+            ConnectError::SessionReplacedRejected,
+            ConnectError::ShuttingDown,
+        ];
+
+        // ------ ACT ------
+        let bodies = errors.map(|error| error.json_body());
+
+        // ------ ASSERT ------
+        assert_eq!(
+            bodies,
+            [
+                r#"{"error":"too_many_connections","action":"backoff","retry_after":3}"#,
+                r#"{"error":"unauthorized","action":"login"}"#,
+                r#"{"error":"session_replaced_rejected","action":"give_up"}"#,
+                r#"{"error":"shutting_down","action":"backoff"}"#,
+            ]
+        );
+        assert_eq!(
+            errors.map(|error| error.status_code().as_u16()),
+            [503, 401, 409, 503]
+        );
+    }
+}
@@ -0,0 +1,613 @@
+use super::ack::{AckHandle, PendingAcks};
+use super::base64;
+use super::channel::{MessageSender, Priority, SendError};
+use super::chunking::{split_payload, split_prepared};
+use super::config::{FrameSizeLimit, KeepAlive};
+use super::error::Error;
+use super::extensions::Extensions;
+#[cfg(feature = "serde")]
+use super::json::SendJsonError;
+use super::message::{
+    comment, frame, retry, EventName, MessageBody, PreparedEvent, BINARY_EVENT_SUFFIX,
+};
+use super::pause::Pause;
+use super::replay_buffer::ReplayBuffer;
+use super::sink::FrameSink;
+use super::stats::{ConnectionActivity, Counters};
+use super::topics::Topics;
+use super::{FrameFormatter, SSE};
+use actix_web::web::Bytes;
+use moonlight::SessionId;
+#[cfg(feature = "serde")]
+use moonlight::{serde_json, Serialize};
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use tokio::time::{Duration, Instant};
+
+// ------ ConnectionId ------
+
+/// Identifies one `EventSource` of the session, e.g. a browser tab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConnectionId(u64);
+
+impl ConnectionId {
+    pub(crate) fn new() -> Self {
+        static LAST_ID: AtomicU64 = AtomicU64::new(0);
+        Self(LAST_ID.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+}
+
+impl fmt::Display for ConnectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for ConnectionId {
+    type Err = ParseIntError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        id.parse().map(Self)
+    }
+}
+
+// ------ ConnectionInfo ------
+
+/// Snapshot of the connection state, e.g. for an admin page.
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    pub connection_id: ConnectionId,
+    pub session_id: SessionId,
+    pub connected_at: Instant,
+    pub last_send_at: Option<Instant>,
+    pub idle_duration: Duration,
+    pub queued_messages: Option<usize>,
+    pub paused: bool,
+    /// Events waiting for [Connection::resume].
+    pub paused_messages: usize,
+    /// Topics of the session, shared by all its connections.
+    pub subscribed_topics: Vec<String>,
+    /// [SSE::epoch](super::SSE::epoch) of the process serving the connection.
+    pub epoch: u64,
+}
+
+// ------ Connection ------
+
+pub struct Connection {
+    pub(crate) remove_session_actor_on_remove: bool,
+    id: ConnectionId,
+    session_id: SessionId,
+    control_sender: Arc<dyn FrameSink>,
+    sender: Mutex<MessageSender>,
+    high_priority_sender: Mutex<MessageSender>,
+    // used only when the session doesn't have a replay buffer with its own ids
+    last_id: AtomicU64,
+    // consecutive keep-alive messages the connection hasn't accepted
+    failed_keep_alives: AtomicU32,
+    replay_buffer: Option<Arc<Mutex<ReplayBuffer>>>,
+    topics: Arc<Topics>,
+    counters: Arc<Counters>,
+    extensions: Extensions,
+    batching: bool,
+    activity: Arc<ConnectionActivity>,
+    pause: Arc<Pause>,
+    formatter: Option<Arc<dyn FrameFormatter>>,
+    frame_size_limit: Option<FrameSizeLimit>,
+    pending_acks: PendingAcks,
+    ack_timeout: Duration,
+    epoch: u64,
+    sse: Weak<SSE>,
+}
+
+impl Connection {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        id: ConnectionId,
+        session_id: Option<SessionId>,
+        remove_session_actor_on_remove: bool,
+        extensions: Extensions,
+        control_sender: Arc<dyn FrameSink>,
+        sender: MessageSender,
+        high_priority_sender: MessageSender,
+        replay_buffer: Option<Arc<Mutex<ReplayBuffer>>>,
+        topics: Arc<Topics>,
+        counters: Arc<Counters>,
+        batching: bool,
+        activity: Arc<ConnectionActivity>,
+        pause: Arc<Pause>,
+        formatter: Option<Arc<dyn FrameFormatter>>,
+        frame_size_limit: Option<FrameSizeLimit>,
+        ack_timeout: Duration,
+        epoch: u64,
+        sse: Weak<SSE>,
+    ) -> Arc<Connection> {
+        Arc::new(Self {
+            remove_session_actor_on_remove,
+            id,
+            session_id: session_id.unwrap_or_else(SessionId::new),
+            control_sender,
+            sender: Mutex::new(sender),
+            high_priority_sender: Mutex::new(high_priority_sender),
+            last_id: AtomicU64::new(0),
+            failed_keep_alives: AtomicU32::new(0),
+            replay_buffer,
+            topics,
+            counters,
+            extensions,
+            batching,
+            activity,
+            pause,
+            formatter,
+            frame_size_limit,
+            pending_acks: PendingAcks::default(),
+            ack_timeout,
+            epoch,
+            sse,
+        })
+    }
+
+    pub fn id(&self) -> ConnectionId {
+        self.id
+    }
+
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    pub(crate) fn replay_buffer(&self) -> Option<&Arc<Mutex<ReplayBuffer>>> {
+        self.replay_buffer.as_ref()
+    }
+
+    /// `true` when the client has disconnected or the connection has been closed by the server,
+    /// sending to it fails.
+    pub fn is_closed(&self) -> bool {
+        self.control_sender.is_closed() || self.sender.lock().is_closed()
+    }
+
+    /// Resolves when the client disconnects, i.e. when the `EventStream` or the sink is dropped,
+    /// so tasks producing messages for the connection can stop early.
+    pub fn closed(&self) -> impl Future<Output = ()> + '_ {
+        self.control_sender.closed()
+    }
+
+    /// Attaches the value to the connection, replaces the previous value of the same type.
+    pub fn set_extension<T: Any + Send + Sync>(&self, value: T) {
+        self.extensions.insert(value);
+    }
+
+    pub fn extension<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.extensions.get()
+    }
+
+    // Extension types already attached to this connection are kept.
+    pub(crate) fn copy_extensions_from(&self, other: &Connection) {
+        self.extensions.insert_missing(&other.extensions);
+    }
+
+    /// Subscribes the whole session, i.e. all its connections.
+    pub fn subscribe(&self, topic: &str) {
+        self.topics.subscribe(topic, self.session_id);
+    }
+
+    pub fn unsubscribe(&self, topic: &str) {
+        self.topics.unsubscribe(topic, &self.session_id);
+    }
+
+    /// Sends the event only to this connection, other connections of the session don't receive it.
+    /// Names of [ProtocolEvent](moonlight::ProtocolEvent)s and names with line breaks are rejected.
+    pub fn send(&self, event: impl AsRef<str>, data: impl AsRef<str>) -> Result<(), Error> {
+        let event = event.as_ref();
+        Error::check_event_name(event)?;
+        self.send_unchecked(event, data.as_ref())
+    }
+
+    // Allows to send protocol events.
+    pub(crate) fn send_unchecked(&self, event: &str, data: &str) -> Result<(), Error> {
+        match split_payload(self.frame_size_limit, event, data)? {
+            None => self.send_event(event, data),
+            Some(chunks) => chunks
+                .iter()
+                .try_for_each(|(event, data)| self.send_event(event, data)),
+        }
+    }
+
+    // Like `send_unchecked`, but the event overtakes the held broadcasts,
+    // used for the initial events of the connection.
+    pub(crate) fn send_initial(&self, event: &str, data: &str) -> Result<(), Error> {
+        let send_event = |event: &str, data: &str| {
+            self.write_body(&self.message_body(event, data)?, true)
+                .map(|_| ())
+                .map_err(|error| self.error(error))
+        };
+        match split_payload(self.frame_size_limit, event, data)? {
+            None => send_event(event, data),
+            Some(chunks) => chunks
+                .iter()
+                .try_for_each(|(event, data)| send_event(event, data)),
+        }
+    }
+
+    // Writes the broadcasts held while the initial events were being sent.
+    pub(crate) fn release_held_messages(&self) {
+        for _error in self.sender.lock().release() {
+            self.counters.message_dropped();
+        }
+    }
+
+    /// Like [send](Self::send), but the event is encoded only once by [PreparedEvent::new]
+    /// even when it's sent to many connections.
+    pub fn send_prepared(&self, prepared_event: &PreparedEvent) -> Result<(), Error> {
+        match split_prepared(self.frame_size_limit, prepared_event)? {
+            None => self
+                .send_body(&MessageBody::prepared(
+                    self.formatter.as_deref(),
+                    prepared_event,
+                ))
+                .map_err(|error| self.error(error)),
+            Some(chunks) => chunks
+                .iter()
+                .try_for_each(|(event, data)| self.send_event(event, data)),
+        }
+    }
+
+    /// Like [send](Self::send), but the `event:` line is copied from the [EventName].
+    pub fn send_named(&self, event: &EventName, data: impl AsRef<str>) -> Result<(), Error> {
+        let data = data.as_ref();
+        Error::check_event_name(event.as_str())?;
+        match split_payload(self.frame_size_limit, event.as_str(), data)? {
+            None => self
+                .send_body(&MessageBody::named(self.formatter.as_deref(), event, data))
+                .map_err(|error| self.error(error)),
+            Some(chunks) => chunks
+                .iter()
+                .try_for_each(|(event, data)| self.send_event(event, data)),
+        }
+    }
+
+    /// Sends the `data` encoded by base64 as the event `{event}-b64`, see [BINARY_EVENT_SUFFIX].
+    pub fn send_binary(&self, event: &str, data: &[u8]) -> Result<(), Error> {
+        Error::check_event_name(event)?;
+        let exceeds_limit = self
+            .frame_size_limit
+            .is_some_and(|limit| base64::encoded_len(data.len()) > limit.max_bytes);
+        if exceeds_limit {
+            // Rejected or chunked like other events.
+            return self.send([event, BINARY_EVENT_SUFFIX].concat(), base64::encode(data));
+        }
+        self.send_body(&MessageBody::binary(
+            self.formatter.as_deref(),
+            event,
+            data,
+        )?)
+        .map_err(|error| self.error(error))
+    }
+
+    fn send_event(&self, event: &str, data: &str) -> Result<(), Error> {
+        self.send_body(&self.message_body(event, data)?)
+            .map_err(|error| self.error(error))
+    }
+
+    /// Like [send](Self::send), but the returned handle resolves when the client acknowledges
+    /// the event by its id at `_api/message_sse/{session_id}/{connection_id}/ack/{event_id}`.
+    /// A chunked event is acknowledged by the id of its `chunk_end`.
+    ///
+    /// Not acknowledged events are stored in the replay buffer again, so they're replayed
+    /// after the next reconnect. They aren't tracked anymore.
+    pub fn send_tracked(
+        &self,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+    ) -> Result<AckHandle, Error> {
+        let (event, data) = (event.as_ref(), data.as_ref());
+        Error::check_event_name(event)?;
+        let chunks = split_payload(self.frame_size_limit, event, data)?;
+        self.pending_acks
+            .track(event, data, self.ack_timeout, || match &chunks {
+                None => self.send_event_with_id(event, data),
+                Some(chunks) => chunks
+                    .iter()
+                    .try_fold(0, |_, (event, data)| self.send_event_with_id(event, data)),
+            })
+    }
+
+    fn send_event_with_id(&self, event: &str, data: &str) -> Result<u64, Error> {
+        self.send_body_with_id(&self.message_body(event, data)?)
+            .map_err(|error| self.error(error))
+    }
+
+    pub(crate) fn ack(&self, event_id: u64) -> bool {
+        self.pending_acks.ack(event_id)
+    }
+
+    pub(crate) fn expire_acks(&self) {
+        self.requeue(self.pending_acks.expire());
+    }
+
+    // Called when the connection is removed.
+    pub(crate) fn close_acks(&self) {
+        self.requeue(self.pending_acks.close());
+    }
+
+    fn requeue(&self, messages: Vec<(String, String)>) {
+        let Some(replay_buffer) = &self.replay_buffer else {
+            return;
+        };
+        for (event, data) in messages {
+            // The event has been already sent, so its name is valid.
+            if let Ok(body) = self.message_body(&event, &data) {
+                replay_buffer.lock().requeue(&body);
+            }
+        }
+    }
+
+    /// Like [send](Self::send), see [Priority] for the differences of high priority events.
+    pub fn send_with_priority(
+        &self,
+        event: impl AsRef<str>,
+        data: impl AsRef<str>,
+        priority: Priority,
+    ) -> Result<(), Error> {
+        Error::check_event_name(event.as_ref())?;
+        match priority {
+            Priority::Normal => self.send(event, data),
+            Priority::High => self
+                .send_high_priority(
+                    self.message_body(event.as_ref(), data.as_ref())?
+                        .message(None),
+                )
+                .map_err(|error| self.error(error)),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn send_json<T: Serialize + ?Sized>(&self, event: &str, data: &T) -> Result<(), Error> {
+        let data = serde_json::to_string(data).map_err(Error::Serialization)?;
+        self.send(event, &data)
+    }
+
+    #[deprecated(note = "use `send` returning `sse::Error`")]
+    pub fn send_legacy(&self, event: &str, data: &str) -> Result<(), SendError> {
+        self.send(event, data)
+            .map_err(|error| error.into_send_error(frame(event, data).unwrap_or_default()))
+    }
+
+    #[cfg(feature = "serde")]
+    #[deprecated(note = "use `send_json` returning `sse::Error`")]
+    pub fn send_json_legacy<T: Serialize + ?Sized>(
+        &self,
+        event: &str,
+        data: &T,
+    ) -> Result<(), SendJsonError> {
+        let data = serde_json::to_string(data).map_err(SendJsonError::Serialization)?;
+        self.send(event, &data).map_err(|error| {
+            SendJsonError::Send(error.into_send_error(frame(event, &data).unwrap_or_default()))
+        })
+    }
+
+    pub(crate) fn message_body<'a>(
+        &'a self,
+        event: &'a str,
+        data: &'a str,
+    ) -> Result<MessageBody<'a>, Error> {
+        MessageBody::new(self.formatter.as_deref(), event, data)
+    }
+
+    // `body` is reused when the same event is sent to multiple connections.
+    // Use `ReplayBuffer::send` to send the body to all connections of the session.
+    pub(crate) fn send_body(&self, body: &MessageBody) -> Result<(), SendError> {
+        self.send_body_with_id(body).map(|_| ())
+    }
+
+    // Returns the id of the sent message.
+    fn send_body_with_id(&self, body: &MessageBody) -> Result<u64, SendError> {
+        self.write_body(body, false)
+    }
+
+    // `now` bypasses the held broadcasts, see `MessageSender::send_now`.
+    fn write_body(&self, body: &MessageBody, now: bool) -> Result<u64, SendError> {
+        let result = if let Some(replay_buffer) = &self.replay_buffer {
+            replay_buffer.lock().send_to(self.id, body, now)
+        } else {
+            let id = self.last_id.fetch_add(1, Ordering::Relaxed) + 1;
+            let message = body.frame(Some(id));
+            let sender = self.sender.lock();
+            if now {
+                sender.send_now(message)
+            } else {
+                sender.send(message)
+            }
+            .map(|()| id)
+        };
+        self.counters.message_sent(result.is_ok());
+        result
+    }
+
+    pub(crate) fn send_high_priority(&self, message: Bytes) -> Result<(), SendError> {
+        let result = self.high_priority_sender.lock().send(message);
+        self.counters.message_sent(result.is_ok());
+        result
+    }
+
+    // Sends the complete message as is, without an id and bypassing the replay buffer.
+    pub(crate) fn send_frame(&self, frame: Bytes) -> Result<(), SendError> {
+        let result = self.sender.lock().send(frame);
+        self.counters.message_sent(result.is_ok());
+        result
+    }
+
+    /// The id of the last event passed to the connection, `0` if there wasn't any.
+    /// Comments, keep-alives and `retry` don't consume ids.
+    pub fn last_sent_id(&self) -> u64 {
+        match &self.replay_buffer {
+            Some(replay_buffer) => replay_buffer.lock().last_id(),
+            None => self.last_id.load(Ordering::Relaxed),
+        }
+    }
+
+    /// How many events exceeded the [RateLimit](super::RateLimit) of this connection.
+    pub fn rate_limited_messages(&self) -> u64 {
+        self.activity.rate_limited_messages()
+    }
+
+    pub fn connected_at(&self) -> Instant {
+        self.activity.connected_at()
+    }
+
+    /// When the last event was passed to the client's HTTP response, `None` if there wasn't any.
+    pub fn last_send_at(&self) -> Option<Instant> {
+        self.activity.last_message_written_at()
+    }
+
+    /// Time since the connection was created or since the last client activity
+    /// reported by [touch](super::ShareableSSEMethods::touch).
+    pub fn idle_duration(&self) -> Duration {
+        self.activity.idle_duration()
+    }
+
+    pub(crate) fn touch(&self) {
+        self.activity.client_active();
+    }
+
+    /// Events are kept on the server until [resume](Self::resume), at most
+    /// [SSEConfig::pause_buffer_size](super::SSEConfig::pause_buffer_size) of them.
+    /// Keep-alives, control messages and high priority events are still written.
+    /// Connections created by a sink can't be paused.
+    pub fn pause(&self) {
+        self.pause.pause();
+    }
+
+    /// Writes the events kept while the connection has been paused, in order.
+    pub fn resume(&self) {
+        self.pause.resume();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
+    pub fn paused_messages(&self) -> usize {
+        self.pause.buffered_messages()
+    }
+
+    /// How many events wait in the queue, `None` when the connection writes to a [FrameSink].
+    pub fn queued_messages(&self) -> Option<usize> {
+        self.sender.lock().queued_messages()
+    }
+
+    /// How many messages, including high priority ones, haven't been taken by the `EventStream` yet.
+    /// Messages waiting for [resume](Self::resume) aren't included, see [paused_messages](Self::paused_messages).
+    pub fn queued_frames(&self) -> usize {
+        let queued_messages = self.queued_messages().unwrap_or_default();
+        let queued_high_priority_messages = self
+            .high_priority_sender
+            .lock()
+            .queued_messages()
+            .unwrap_or_default();
+        queued_messages + queued_high_priority_messages
+    }
+
+    pub fn info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            connection_id: self.id,
+            session_id: self.session_id,
+            connected_at: self.connected_at(),
+            last_send_at: self.last_send_at(),
+            idle_duration: self.idle_duration(),
+            queued_messages: self.queued_messages(),
+            paused: self.is_paused(),
+            paused_messages: self.paused_messages(),
+            subscribed_topics: self.topics.session_topics(&self.session_id),
+            epoch: self.epoch,
+        }
+    }
+
+    /// Sends the optional final event and ends the `EventStream`, the connection is removed
+    /// from `SSE` and [SSEConfig::on_disconnect](super::SSEConfig::on_disconnect) is called
+    /// with [DisconnectReason::Closed](super::DisconnectReason::Closed).
+    /// Returns `false` when the connection has been already removed.
+    pub fn close(self: &Arc<Self>, final_event: Option<(&str, &str)>) -> bool {
+        match self.sse.upgrade() {
+            Some(sse) => sse.close_connection(self.id, final_event),
+            None => {
+                if let Some((event, data)) = final_event {
+                    let _ = self.send(event, data);
+                }
+                self.close_senders();
+                true
+            }
+        }
+    }
+
+    // Drops the message senders so the `EventStream` ends after the queued messages.
+    pub(crate) fn close_senders(&self) {
+        *self.sender.lock() = MessageSender::closed();
+        *self.high_priority_sender.lock() = MessageSender::closed();
+        self.control_sender.close();
+        if let Some(replay_buffer) = &self.replay_buffer {
+            replay_buffer.lock().remove_sender(self.id);
+        }
+    }
+
+    /// Sends [SSE comment lines](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation)
+    /// ignored by browsers.
+    pub fn send_comment(&self, text: &str) -> Result<(), Error> {
+        self.sender
+            .lock()
+            .send(comment(text))
+            .map_err(|error| self.error(error))
+    }
+
+    /// Writes the batched messages immediately, does nothing when batching is disabled.
+    pub fn flush(&self) -> Result<(), Error> {
+        if !self.batching {
+            return Ok(());
+        }
+        // An empty control message only flushes the batch.
+        self.send_control(Bytes::new())
+            .map_err(|error| self.error(error))
+    }
+
+    /// Sets how long the client waits before reconnecting, in milliseconds.
+    pub fn set_retry(&self, milliseconds: u32) -> Result<(), Error> {
+        self.send_control(retry(milliseconds))
+            .map_err(|error| self.error(error))
+    }
+
+    pub(crate) fn error(&self, error: SendError) -> Error {
+        Error::from_send_error(self.session_id, error)
+    }
+
+    pub(crate) fn send_keep_alive(&self, keep_alive: &KeepAlive) {
+        let sent = keep_alive
+            .message(self.formatter.as_deref())
+            .is_ok_and(|message| self.send_control(message).is_ok());
+        if sent {
+            self.keep_alive_not_needed();
+        } else {
+            self.failed_keep_alives.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // The connection is alive, e.g. it has written an event recently.
+    pub(crate) fn keep_alive_not_needed(&self) {
+        self.failed_keep_alives.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn failed_keep_alives(&self) -> u32 {
+        self.failed_keep_alives.load(Ordering::Relaxed)
+    }
+
+    // Control messages are not stored in the replay buffer, don't consume event ids
+    // and don't wait in the message queue.
+    pub(crate) fn send_control(&self, message: Bytes) -> Result<(), SendError> {
+        self.control_sender
+            .try_send(message)
+            .map_err(SendError::from)
+    }
+}
@@ -0,0 +1,83 @@
+use super::extensions::Extensions;
+use super::{Connection, ConnectionKind, Error, EventStream, ShareableSSE};
+use moonlight::SessionId;
+use std::any::Any;
+use std::sync::Arc;
+
+// Produces the initial events of the connection, see
+// [new_connection_with_init](super::ShareableSSEMethods::new_connection_with_init).
+pub(super) type Init<'a> = Box<dyn FnOnce(&Connection) -> Vec<(String, String)> + 'a>;
+
+// ------ ConnectionBuilder ------
+
+/// Created by [connection_builder](super::ShareableSSEMethods::connection_builder),
+/// the connection is created by [connect](Self::connect).
+pub struct ConnectionBuilder<'a> {
+    pub(super) sse: &'a ShareableSSE,
+    pub(super) session_id: Option<SessionId>,
+    pub(super) last_event_id: Option<u64>,
+    pub(super) last_known_epoch: Option<u64>,
+    pub(super) connection_kind: ConnectionKind,
+    pub(super) keep_session_actor: bool,
+    pub(super) extensions: Extensions,
+    pub(super) init: Option<Init<'a>>,
+}
+
+impl<'a> ConnectionBuilder<'a> {
+    pub(super) fn new(sse: &'a ShareableSSE) -> Self {
+        Self {
+            sse,
+            session_id: None,
+            last_event_id: None,
+            last_known_epoch: None,
+            connection_kind: sse.config.connection_kind,
+            keep_session_actor: false,
+            extensions: Extensions::default(),
+            init: None,
+        }
+    }
+
+    /// Resumes the session, a new session id is generated when it isn't set.
+    pub fn session_id(mut self, session_id: SessionId) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// The id of the last event received by the client, missed events are replayed.
+    pub fn last_event_id(mut self, last_event_id: u64) -> Self {
+        self.last_event_id = Some(last_event_id);
+        self
+    }
+
+    /// The [SSE::epoch](super::SSE::epoch) received by the client before it has reconnected.
+    /// The connection starts with the `state_reset` event when the epoch is different,
+    /// i.e. when the server has been restarted and the session state is lost.
+    pub fn last_known_epoch(mut self, epoch: u64) -> Self {
+        self.last_known_epoch = Some(epoch);
+        self
+    }
+
+    pub fn connection_kind(mut self, connection_kind: ConnectionKind) -> Self {
+        self.connection_kind = connection_kind;
+        self
+    }
+
+    /// The session actor isn't removed when the last connection of the session is removed.
+    /// By default, it's removed for connections with a [session_id](Self::session_id).
+    pub fn keep_session_actor_on_disconnect(mut self) -> Self {
+        self.keep_session_actor = true;
+        self
+    }
+
+    /// Attaches the value to the connection before [SSEConfig::on_connect](super::SSEConfig::on_connect)
+    /// is called, it's available through [Connection::extension].
+    pub fn metadata<T: Any + Send + Sync>(self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+
+    pub fn connect(self) -> Result<(Arc<Connection>, EventStream), Error> {
+        let (_, connection, event_stream) = self.sse.create_connection(self)?;
+        Ok((connection, event_stream))
+    }
+}
@@ -0,0 +1,160 @@
+use super::message::Frame;
+use super::stats::Counters;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+// ------ drop_oldest_channel ------
+
+// Tokio channels can't evict queued messages, so `ConnectionKind::DropOldest` has its own queue.
+pub(crate) fn drop_oldest_channel(
+    keep: usize,
+    counters: Arc<Counters>,
+) -> (DropOldestSender, DropOldestReceiver) {
+    let queue = Arc::new(Queue {
+        keep: keep.max(1),
+        counters,
+        state: Mutex::new(State {
+            messages: VecDeque::new(),
+            senders: 1,
+            receiver_alive: true,
+            waker: None,
+        }),
+    });
+    (
+        DropOldestSender(Arc::clone(&queue)),
+        DropOldestReceiver(queue),
+    )
+}
+
+struct Queue {
+    keep: usize,
+    counters: Arc<Counters>,
+    state: Mutex<State>,
+}
+
+struct State {
+    messages: VecDeque<Frame>,
+    senders: usize,
+    receiver_alive: bool,
+    waker: Option<Waker>,
+}
+
+// ------ DropOldestSender ------
+
+pub(crate) struct DropOldestSender(Arc<Queue>);
+
+impl DropOldestSender {
+    // Returns the message back when the receiver has been dropped.
+    pub(crate) fn send(&self, message: Frame) -> Result<(), Frame> {
+        let mut state = self.0.state.lock();
+        if !state.receiver_alive {
+            return Err(message);
+        }
+        if state.messages.len() >= self.0.keep {
+            state.messages.pop_front();
+            self.0.counters.message_dropped();
+        }
+        state.messages.push_back(message);
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        !self.0.state.lock().receiver_alive
+    }
+
+    pub(crate) fn queued_messages(&self) -> usize {
+        self.0.state.lock().messages.len()
+    }
+}
+
+impl Clone for DropOldestSender {
+    fn clone(&self) -> Self {
+        self.0.state.lock().senders += 1;
+        Self(Arc::clone(&self.0))
+    }
+}
+
+// The receiver ends after the queued messages when the last sender is dropped.
+impl Drop for DropOldestSender {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock();
+        state.senders -= 1;
+        let waker = if state.senders == 0 {
+            state.waker.take()
+        } else {
+            None
+        };
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+// ------ DropOldestReceiver ------
+
+pub(crate) struct DropOldestReceiver(Arc<Queue>);
+
+impl DropOldestReceiver {
+    pub(crate) fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<Frame>> {
+        let mut state = self.0.state.lock();
+        if let Some(message) = state.messages.pop_front() {
+            return Poll::Ready(Some(message));
+        }
+        if state.senders == 0 {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for DropOldestReceiver {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock();
+        state.receiver_alive = false;
+        state.messages.clear();
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::web::Bytes;
+    use futures::task::noop_waker_ref;
+
+    #[test]
+    fn test_evict_oldest_message() {
+        // ------ ARRANGE ------
+        let counters = Arc::<Counters>::default();
+        let (sender, mut receiver) = drop_oldest_channel(2, Arc::clone(&counters));
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        // ------ ACT ------
+        for message in ["1", "2", "3"] {
+            sender.send(Frame::from(Bytes::from(message))).unwrap();
+        }
+        drop(sender);
+
+        // ------ ASSERT ------
+        assert_eq!(
+            receiver.poll_recv(&mut cx),
+            Poll::Ready(Some(Frame::from(Bytes::from("2"))))
+        );
+        assert_eq!(
+            receiver.poll_recv(&mut cx),
+            Poll::Ready(Some(Frame::from(Bytes::from("3"))))
+        );
+        assert_eq!(receiver.poll_recv(&mut cx), Poll::Ready(None));
+        assert_eq!(counters.stats(0, Default::default(), 0).dropped_messages, 1);
+    }
+}
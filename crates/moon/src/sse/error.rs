@@ -0,0 +1,181 @@
+use super::{AuthError, ConnectError, SendError};
+use actix_web::web::Bytes;
+#[cfg(feature = "serde")]
+use moonlight::serde_json;
+use moonlight::{ProtocolEvent, SessionId};
+use std::error;
+use std::fmt;
+use std::time::Duration;
+
+// ------ Error ------
+
+#[derive(Debug)]
+pub enum Error {
+    ConnectionClosed {
+        session_id: SessionId,
+    },
+    SessionNotFound {
+        session_id: SessionId,
+    },
+    QueueFull {
+        session_id: SessionId,
+    },
+    /// The session is already connected and [ReconnectPolicy::RejectNew](super::ReconnectPolicy::RejectNew) is set.
+    ConnectionRejected {
+        session_id: SessionId,
+    },
+    /// [SSEConfig::max_connections](super::SSEConfig::max_connections) has been reached.
+    ConnectionLimitReached {
+        max_connections: usize,
+        /// [SSEConfig::connection_limit_retry_after](super::SSEConfig::connection_limit_retry_after).
+        retry_after: Duration,
+    },
+    /// The connection has been requested after [close_all](super::ShareableSSEMethods::close_all).
+    ShuttingDown,
+    /// The event data exceeds [SSEConfig::max_frame_size](super::SSEConfig::max_frame_size).
+    PayloadTooLarge {
+        size: usize,
+        max_frame_size: usize,
+    },
+    /// [SSEConfig::auth](super::SSEConfig::auth) has rejected the request.
+    Unauthorized(AuthError),
+    /// The event name is reserved for events sent by Moon itself.
+    ReservedEventName(ProtocolEvent),
+    /// The event name contains a line break, so it would end the `event:` line.
+    InvalidEventName(String),
+    #[cfg(feature = "serde")]
+    Serialization(serde_json::Error),
+}
+
+impl Error {
+    pub(crate) fn from_send_error(session_id: SessionId, error: SendError) -> Self {
+        match error {
+            SendError::ConnectionClosed(_) => Self::ConnectionClosed { session_id },
+            SendError::QueueFull(_) => Self::QueueFull { session_id },
+        }
+    }
+
+    // For the deprecated `*_legacy` methods, `message` is the refused message,
+    // it's empty when the event name has been rejected.
+    pub(crate) fn into_send_error(self, message: Bytes) -> SendError {
+        match self {
+            Self::QueueFull { .. } => SendError::QueueFull(message),
+            _ => SendError::ConnectionClosed(message),
+        }
+    }
+
+    // User events can't inject other fields or events into the stream
+    // and they can't be mistaken for protocol events by Zoon.
+    pub(crate) fn check_event_name(event: &str) -> Result<(), Self> {
+        Self::check_line_breaks(event)?;
+        match event.parse() {
+            Ok(protocol_event) => Err(Self::ReservedEventName(protocol_event)),
+            Err(_) => Ok(()),
+        }
+    }
+
+    // A line break in the event name would allow to inject another field or event.
+    pub(crate) fn check_line_breaks(event: &str) -> Result<(), Self> {
+        if event.contains(['\r', '\n']) {
+            return Err(Self::InvalidEventName(event.to_owned()));
+        }
+        Ok(())
+    }
+
+    /// Returns `None` when the error isn't caused by rejecting the new connection.
+    /// Connect handlers respond with [ConnectError::to_response].
+    pub fn connect_error(&self) -> Option<ConnectError> {
+        match self {
+            Self::ConnectionLimitReached { retry_after, .. } => {
+                Some(ConnectError::TooManyConnections {
+                    retry_after: *retry_after,
+                })
+            }
+            Self::Unauthorized(_) => Some(ConnectError::Unauthorized),
+            Self::ConnectionRejected { .. } => Some(ConnectError::SessionReplacedRejected),
+            Self::ShuttingDown => Some(ConnectError::ShuttingDown),
+            _ => None,
+        }
+    }
+
+    pub fn session_id(&self) -> Option<SessionId> {
+        match self {
+            Self::ConnectionClosed { session_id }
+            | Self::SessionNotFound { session_id }
+            | Self::QueueFull { session_id }
+            | Self::ConnectionRejected { session_id } => Some(*session_id),
+            Self::ConnectionLimitReached { .. }
+            | Self::ShuttingDown
+            | Self::PayloadTooLarge { .. }
+            | Self::Unauthorized(_)
+            | Self::ReservedEventName(_)
+            | Self::InvalidEventName(_) => None,
+            #[cfg(feature = "serde")]
+            Self::Serialization(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConnectionClosed { session_id } => {
+                write!(f, "SSE connection of session '{session_id}' is closed")
+            }
+            Self::SessionNotFound { session_id } => {
+                write!(f, "session '{session_id}' doesn't have an SSE connection")
+            }
+            Self::QueueFull { session_id } => {
+                write!(f, "SSE connection queue of session '{session_id}' is full")
+            }
+            Self::ConnectionRejected { session_id } => {
+                write!(f, "session '{session_id}' already has an SSE connection")
+            }
+            Self::ConnectionLimitReached {
+                max_connections, ..
+            } => {
+                write!(
+                    f,
+                    "the limit of {max_connections} SSE connections has been reached"
+                )
+            }
+            Self::ShuttingDown => write!(f, "SSE is shutting down"),
+            Self::PayloadTooLarge {
+                size,
+                max_frame_size,
+            } => {
+                write!(
+                    f,
+                    "SSE event data has {size} bytes, the limit is {max_frame_size} bytes"
+                )
+            }
+            Self::Unauthorized(_) => write!(f, "SSE request is unauthorized"),
+            Self::ReservedEventName(event) => {
+                write!(f, "SSE event name '{event}' is reserved for Moon")
+            }
+            Self::InvalidEventName(event) => {
+                write!(f, "SSE event name {event:?} contains a line break")
+            }
+            #[cfg(feature = "serde")]
+            Self::Serialization(error) => write!(f, "failed to serialize SSE data: {error}"),
+        }
+    }
+}
+
+// Broadcast methods return all errors, `?` rejects the whole broadcast with one error.
+impl From<Error> for Vec<Error> {
+    fn from(error: Error) -> Self {
+        vec![error]
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Unauthorized(error) => Some(error),
+            #[cfg(feature = "serde")]
+            Self::Serialization(error) => Some(error),
+            _ => None,
+        }
+    }
+}
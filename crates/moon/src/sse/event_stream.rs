@@ -0,0 +1,187 @@
+use super::channel::MessageReceiver;
+use super::config::Batching;
+use super::message::Frame;
+use super::pause::Pause;
+use super::rate_limit::RateLimiter;
+use super::stats::ConnectionActivity;
+use super::{ConnectionId, SSE};
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::Error;
+use futures::{Future, Stream};
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::{sleep, Sleep};
+
+// ------ EventStream ------
+
+pub struct EventStream {
+    // keep-alive messages don't wait behind messages in a possibly full queue
+    control: UnboundedReceiver<Bytes>,
+    high_priority_messages: MessageReceiver,
+    messages: MessageReceiver,
+    rate_limiter: Option<RateLimiter>,
+    activity: Arc<ConnectionActivity>,
+    pause: Arc<Pause>,
+    sse: Weak<SSE>,
+    connection_id: ConnectionId,
+    batching: Option<Batching>,
+    // messages waiting to be written as one chunk
+    batch: BytesMut,
+    batch_deadline: Option<Pin<Box<Sleep>>>,
+    // the body of the message whose `id:` line has been just written
+    pending_body: Option<Bytes>,
+}
+
+impl EventStream {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        control: UnboundedReceiver<Bytes>,
+        high_priority_messages: MessageReceiver,
+        messages: MessageReceiver,
+        rate_limiter: Option<RateLimiter>,
+        activity: Arc<ConnectionActivity>,
+        pause: Arc<Pause>,
+        sse: Weak<SSE>,
+        connection_id: ConnectionId,
+        batching: Option<Batching>,
+    ) -> Self {
+        Self {
+            control,
+            high_priority_messages,
+            messages,
+            rate_limiter,
+            activity,
+            pause,
+            sse,
+            connection_id,
+            batching,
+            batch: BytesMut::new(),
+            batch_deadline: None,
+            pending_body: None,
+        }
+    }
+
+    // The stream ends with the normal queue, `Connection::close` closes both queues.
+    fn poll_message(&mut self, cx: &mut Context<'_>) -> Poll<Option<Frame>> {
+        let message = match self.high_priority_messages.poll_recv(cx) {
+            Poll::Ready(Some(message)) => Poll::Ready(Some(message)),
+            _ => self.poll_normal_message(cx),
+        };
+        if let Poll::Ready(Some(_)) = message {
+            self.activity.message_written();
+        }
+        message
+    }
+
+    // Messages buffered while the connection has been paused are written first.
+    fn poll_normal_message(&mut self, cx: &mut Context<'_>) -> Poll<Option<Frame>> {
+        if self.pause.buffer(&mut self.messages, cx) {
+            return Poll::Pending;
+        }
+        if let Some(message) = self.pause.pop_buffered() {
+            return Poll::Ready(Some(message));
+        }
+        match &mut self.rate_limiter {
+            Some(rate_limiter) => rate_limiter.poll_recv(&mut self.messages, cx),
+            None => self.messages.poll_recv(cx),
+        }
+    }
+
+    fn take_batch(&mut self) -> Bytes {
+        self.batch_deadline = None;
+        self.batch.split().freeze()
+    }
+
+    fn drain_messages_to_batch(&mut self, cx: &mut Context<'_>) {
+        while let Poll::Ready(Some(frame)) = self.poll_message(cx) {
+            self.push_to_batch(frame);
+        }
+    }
+
+    fn push_to_batch(&mut self, frame: Frame) {
+        let (id_line, body) = frame.into_parts();
+        if let Some(id_line) = id_line {
+            self.batch.extend_from_slice(&id_line);
+        }
+        self.batch.extend_from_slice(&body);
+    }
+
+    fn poll_next_batch(&mut self, batching: Batching, cx: &mut Context<'_>) -> Poll<Option<Bytes>> {
+        // A control message, e.g. keep-alive or `Connection::flush`, flushes the batch.
+        if let Poll::Ready(Some(bytes)) = self.control.poll_recv(cx) {
+            self.drain_messages_to_batch(cx);
+            self.batch.extend_from_slice(&bytes);
+            if self.batch.is_empty() {
+                // An empty flush message and nothing to flush.
+                return self.poll_next_batch(batching, cx);
+            }
+            return Poll::Ready(Some(self.take_batch()));
+        }
+        loop {
+            match self.poll_message(cx) {
+                Poll::Ready(Some(frame)) => {
+                    self.push_to_batch(frame);
+                    if self.batch.len() >= batching.max_bytes {
+                        return Poll::Ready(Some(self.take_batch()));
+                    }
+                }
+                Poll::Ready(None) if self.batch.is_empty() => return Poll::Ready(None),
+                Poll::Ready(None) => return Poll::Ready(Some(self.take_batch())),
+                Poll::Pending => break,
+            }
+        }
+        if self.batch.is_empty() {
+            return Poll::Pending;
+        }
+        let batch_deadline = self
+            .batch_deadline
+            .get_or_insert_with(|| Box::pin(sleep(batching.window)));
+        match batch_deadline.as_mut().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Some(self.take_batch())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+// Actix drops the stream when the client disconnects, so we don't have to wait
+// for the next failed keep-alive to remove the connection.
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        if let Some(sse) = self.sse.upgrade() {
+            sse.remove_dropped_connection(self.connection_id);
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(batching) = self.batching {
+            return self
+                .poll_next_batch(batching, cx)
+                .map(|bytes| bytes.map(Ok));
+        }
+        // Nothing may be written between the `id:` line and the rest of the message.
+        if let Some(body) = self.pending_body.take() {
+            return Poll::Ready(Some(Ok(body)));
+        }
+        if let Poll::Ready(Some(bytes)) = self.control.poll_recv(cx) {
+            return Poll::Ready(Some(Ok(bytes)));
+        }
+        // The shared body is written as its own chunk instead of being copied behind the id.
+        match self.poll_message(cx) {
+            Poll::Ready(Some(frame)) => match frame.into_parts() {
+                (Some(id_line), body) => {
+                    self.pending_body = Some(body);
+                    Poll::Ready(Some(Ok(id_line)))
+                }
+                (None, body) => Poll::Ready(Some(Ok(body))),
+            },
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use parking_lot::RwLock;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// ------ Extensions ------
+
+// Typed values attached to a connection, dropped together with it.
+#[derive(Default)]
+pub(crate) struct Extensions(RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>);
+
+impl Extensions {
+    pub(crate) fn insert<T: Any + Send + Sync>(&self, value: T) {
+        self.0.write().insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    // Values of types already in `self` aren't replaced.
+    pub(crate) fn insert_missing(&self, other: &Extensions) {
+        let other = other.0.read();
+        let mut extensions = self.0.write();
+        for (type_id, value) in other.iter() {
+            extensions
+                .entry(*type_id)
+                .or_insert_with(|| Arc::clone(value));
+        }
+    }
+
+    pub(crate) fn get<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        let value = Arc::clone(self.0.read().get(&TypeId::of::<T>())?);
+        value.downcast().ok()
+    }
+}
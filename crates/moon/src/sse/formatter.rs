@@ -0,0 +1,60 @@
+use super::message::{message_body, Frame};
+use actix_web::web::Bytes;
+
+// ------ FrameFormatter ------
+
+/// Encodes events for the client, set it in [SSEConfig::formatter](super::SSEConfig::formatter)
+/// to send events in another envelope than the standard `id:`, `event:` and `data:` fields.
+/// The returned bytes must end with a blank line to be dispatched by `EventSource`.
+///
+/// Coalescing by [RateLimit](super::RateLimit) recognizes events by their `event:` line,
+/// events without it are coalesced together.
+pub trait FrameFormatter: Send + Sync {
+    /// `id` is `None` for messages without an event id, e.g. keep-alive events.
+    fn frame(&self, event: &str, data: &str, id: Option<u64>) -> Bytes;
+}
+
+// ------ DefaultFrameFormatter ------
+
+/// The standard SSE encoding used when no formatter is set,
+/// multi-line `data` is split into multiple `data:` lines.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultFrameFormatter;
+
+impl FrameFormatter for DefaultFrameFormatter {
+    // The send methods reject event names with line breaks before they're formatted,
+    // such a name is encoded as an empty frame.
+    fn frame(&self, event: &str, data: &str, id: Option<u64>) -> Bytes {
+        message_body(event, data)
+            .map_or_else(|_| Bytes::new(), |body| Frame::new(id, body).into_bytes())
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sse::KeepAlive;
+    use std::borrow::Cow;
+
+    struct EnvelopeFormatter;
+
+    impl FrameFormatter for EnvelopeFormatter {
+        fn frame(&self, event: &str, data: &str, _: Option<u64>) -> Bytes {
+            Bytes::from(format!("data: {{\"t\":{event:?},\"p\":{data:?}}}\n\n"))
+        }
+    }
+
+    #[test]
+    fn test_keep_alive_event_uses_formatter() {
+        // ------ ARRANGE ------
+        let keep_alive = KeepAlive::Event(Cow::Borrowed("ping"));
+
+        // ------ ACT ------
+        let message = keep_alive.message(Some(&EnvelopeFormatter)).unwrap();
+
+        // ------ ASSERT ------
+        assert_eq!(message, "data: {\"t\":\"ping\",\"p\":\"\"}\n\n");
+    }
+}
@@ -0,0 +1,109 @@
+use super::{Error, ShareableSSEMethods, SSE};
+use moonlight::SessionId;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::sync::{Arc, Weak};
+
+type Members = Mutex<HashSet<SessionId>>;
+
+// ------ ConnectionGroup ------
+
+/// Sessions chosen by the server, unlike topics subscribed by clients.
+/// Created by [group](super::ShareableSSEMethods::group).
+///
+/// A session leaves all groups when it's removed from `SSE`,
+/// i.e. when it hasn't reconnected in time. Dropping the group releases its members.
+pub struct ConnectionGroup {
+    sse: Weak<SSE>,
+    members: Arc<Members>,
+}
+
+impl ConnectionGroup {
+    pub(crate) fn new(sse: &Arc<SSE>) -> Self {
+        let members = Arc::<Members>::default();
+        sse.groups.register(&members);
+        Self {
+            sse: Arc::downgrade(sse),
+            members,
+        }
+    }
+
+    /// Returns `false` when the session isn't connected or it's already in the group.
+    pub fn add(&self, session_id: &SessionId) -> bool {
+        let Some(sse) = self.sse.upgrade() else {
+            return false;
+        };
+        // The session is kept while its client is reconnecting.
+        let known_session = sse.sessions.contains_key(session_id)
+            || sse.disconnected_sessions.contains_key(session_id);
+        known_session && self.members.lock().insert(*session_id)
+    }
+
+    /// Returns `false` when the session isn't in the group.
+    pub fn remove(&self, session_id: &SessionId) -> bool {
+        self.members.lock().remove(session_id)
+    }
+
+    pub fn contains(&self, session_id: &SessionId) -> bool {
+        self.members.lock().contains(session_id)
+    }
+
+    /// Sends the event like [broadcast_to](super::ShareableSSEMethods::broadcast_to)
+    /// to all sessions in the group.
+    pub fn send(&self, event: impl AsRef<str>, data: impl AsRef<str>) -> Result<(), Vec<Error>> {
+        Error::check_event_name(event.as_ref())?;
+        let Some(sse) = self.sse.upgrade() else {
+            return Ok(());
+        };
+        // The group isn't locked while the event is sent.
+        let session_ids = self.session_ids();
+        sse.broadcast_to(&session_ids, event, data)
+    }
+
+    pub fn session_ids(&self) -> Vec<SessionId> {
+        self.members.lock().iter().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.lock().is_empty()
+    }
+}
+
+// ------ Groups ------
+
+// Members of all living groups, so removed sessions can leave them.
+#[derive(Default)]
+pub(crate) struct Groups(Mutex<Vec<Weak<Members>>>);
+
+impl Groups {
+    fn register(&self, members: &Arc<Members>) {
+        let mut groups = self.0.lock();
+        groups.retain(|group| group.strong_count() > 0);
+        groups.push(Arc::downgrade(members));
+    }
+
+    pub(crate) fn replace_session(&self, old_session_id: &SessionId, new_session_id: SessionId) {
+        for group in self.0.lock().iter() {
+            if let Some(members) = group.upgrade() {
+                let mut members = members.lock();
+                if members.remove(old_session_id) {
+                    members.insert(new_session_id);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn remove_session(&self, session_id: &SessionId) {
+        self.0.lock().retain(|group| {
+            let Some(members) = group.upgrade() else {
+                return false;
+            };
+            members.lock().remove(session_id);
+            true
+        });
+    }
+}
@@ -0,0 +1,147 @@
+use super::{Error, SSE};
+use moonlight::SessionId;
+use parking_lot::Mutex;
+
+// ------ SSEHandle ------
+
+/// The send surface of [SSE]. Code accepting `&impl SSEHandle` or `&dyn SSEHandle`
+/// can be tested with [TestSSE]; pass `&*sse` for a [ShareableSSE](super::ShareableSSE).
+pub trait SSEHandle: Send + Sync {
+    fn send(&self, session_id: &SessionId, event: &str, data: &str) -> Result<(), Error>;
+
+    fn broadcast(&self, event: &str, data: &str) -> Result<(), Vec<Error>>;
+
+    fn is_connected(&self, session_id: &SessionId) -> bool;
+}
+
+impl SSEHandle for SSE {
+    fn send(&self, session_id: &SessionId, event: &str, data: &str) -> Result<(), Error> {
+        Error::check_event_name(event)?;
+        self.send_message(session_id, event, data).map(drop)
+    }
+
+    fn broadcast(&self, event: &str, data: &str) -> Result<(), Vec<Error>> {
+        Error::check_event_name(event)?;
+        self.broadcast_message(&[], event, data)
+    }
+
+    fn is_connected(&self, session_id: &SessionId) -> bool {
+        SSE::is_connected(self, session_id)
+    }
+}
+
+impl<T: SSEHandle + ?Sized> SSEHandle for &T {
+    fn send(&self, session_id: &SessionId, event: &str, data: &str) -> Result<(), Error> {
+        (**self).send(session_id, event, data)
+    }
+
+    fn broadcast(&self, event: &str, data: &str) -> Result<(), Vec<Error>> {
+        (**self).broadcast(event, data)
+    }
+
+    fn is_connected(&self, session_id: &SessionId) -> bool {
+        (**self).is_connected(session_id)
+    }
+}
+
+// ------ TestSSE ------
+
+/// [SSEHandle] recording the events instead of sending them, it doesn't need any runtime.
+/// Only sessions connected by [connect](Self::connect) receive events.
+#[derive(Default)]
+pub struct TestSSE {
+    connected_sessions: Mutex<Vec<SessionId>>,
+    events: Mutex<Vec<(SessionId, String, String)>>,
+}
+
+impl TestSSE {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect(&self, session_id: SessionId) {
+        let mut connected_sessions = self.connected_sessions.lock();
+        if !connected_sessions.contains(&session_id) {
+            connected_sessions.push(session_id);
+        }
+    }
+
+    /// The session stops receiving events as if its connection has been lost.
+    pub fn simulate_disconnect(&self, session_id: &SessionId) {
+        self.connected_sessions
+            .lock()
+            .retain(|connected_session_id| connected_session_id != session_id);
+    }
+
+    /// Returns the recorded `(session_id, event, data)`, the oldest first, and forgets them.
+    pub fn take_events(&self) -> Vec<(SessionId, String, String)> {
+        std::mem::take(&mut self.events.lock())
+    }
+}
+
+impl SSEHandle for TestSSE {
+    fn send(&self, session_id: &SessionId, event: &str, data: &str) -> Result<(), Error> {
+        Error::check_event_name(event)?;
+        if !self.is_connected(session_id) {
+            return Err(Error::SessionNotFound {
+                session_id: *session_id,
+            });
+        }
+        self.events
+            .lock()
+            .push((*session_id, event.to_owned(), data.to_owned()));
+        Ok(())
+    }
+
+    fn broadcast(&self, event: &str, data: &str) -> Result<(), Vec<Error>> {
+        Error::check_event_name(event)?;
+        let connected_sessions = self.connected_sessions.lock();
+        let mut events = self.events.lock();
+        for session_id in connected_sessions.iter() {
+            events.push((*session_id, event.to_owned(), data.to_owned()));
+        }
+        Ok(())
+    }
+
+    fn is_connected(&self, session_id: &SessionId) -> bool {
+        self.connected_sessions.lock().contains(session_id)
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notify(sse: &impl SSEHandle, session_id: &SessionId) -> Result<(), Error> {
+        sse.send(session_id, "notification", "hello")
+    }
+
+    #[test]
+    fn test_sse_records_events_of_connected_sessions() {
+        // ------ ARRANGE ------
+        let sse = TestSSE::new();
+        let session_id = SessionId::new();
+        let other_session_id = SessionId::new();
+        sse.connect(session_id);
+        sse.connect(other_session_id);
+
+        // ------ ACT ------
+        notify(&sse, &session_id).unwrap();
+        sse.simulate_disconnect(&other_session_id);
+        sse.broadcast("refresh", "").unwrap();
+        let error = notify(&sse, &other_session_id).unwrap_err();
+
+        // ------ ASSERT ------
+        assert_eq!(
+            sse.take_events(),
+            [
+                (session_id, "notification".to_owned(), "hello".to_owned()),
+                (session_id, "refresh".to_owned(), String::new()),
+            ]
+        );
+        assert!(matches!(error, Error::SessionNotFound { .. }));
+        assert!(sse.take_events().is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+use super::Connection;
+use moonlight::SessionId;
+use std::sync::Arc;
+
+// ------ DisconnectReason ------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// The keep-alive message couldn't be sent to the client.
+    PingFailed,
+    /// The client has closed the connection and actix has dropped the `EventStream`.
+    StreamDropped,
+    /// The session has opened a new connection with `ReconnectPolicy::ReplaceOld`.
+    Superseded,
+    /// The connection has been removed by `remove_connection`.
+    ExplicitRemove,
+    /// The connection has been closed by `close_all`.
+    ServerShutdown,
+    /// The connection has been closed by `kick`.
+    Kicked,
+    /// The client has reported the closed connection, see `remove_closed_connection`.
+    ClientClosed,
+    /// There wasn't any client activity for `SSEConfig::max_idle`.
+    IdleTimeout,
+    /// The session has been replaced by another one, see `replace_session`.
+    Replaced,
+    /// The connection has been closed by `Connection::close` or `close`.
+    Closed,
+}
+
+// ------ OnDisconnect ------
+
+/// Called after the connection has been removed, without any SSE lock held,
+/// so it's safe to send messages from it.
+pub type OnDisconnect = Arc<dyn Fn(SessionId, DisconnectReason) + Send + Sync>;
+
+// ------ OnConnect ------
+
+/// Called after the connection has been registered, so messages sent from it
+/// by `session_id` reach the client. The flag is `true` when the session reconnects.
+pub type OnConnect = Arc<dyn Fn(SessionId, Arc<Connection>, bool) + Send + Sync>;
@@ -0,0 +1,25 @@
+use super::SendError;
+use moonlight::serde_json;
+use std::error::Error;
+use std::fmt;
+
+// ------ SendJsonError ------
+
+/// Returned by the deprecated `send_json_legacy` methods,
+/// [send_json](super::ShareableSSEMethods::send_json) returns [Error::Serialization](super::Error::Serialization).
+#[derive(Debug)]
+pub enum SendJsonError<E = SendError> {
+    Serialization(serde_json::Error),
+    Send(E),
+}
+
+impl<E: fmt::Debug> fmt::Display for SendJsonError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serialization(error) => write!(f, "failed to serialize SSE data: {error}"),
+            Self::Send(error) => write!(f, "failed to send SSE data: {error:?}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> Error for SendJsonError<E> {}
@@ -0,0 +1,69 @@
+use super::DisconnectReason;
+use futures::stream::{self, Stream};
+use moonlight::SessionId;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError, Sender};
+
+// Events not yet received by a subscriber, the oldest ones are replaced by new events.
+const CAPACITY: usize = 1024;
+
+// ------ SSELifecycleEvent ------
+
+/// Received from [SSE::events](super::SSE::events), e.g. by a metrics exporter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SSELifecycleEvent {
+    Connected {
+        session_id: SessionId,
+    },
+    Disconnected {
+        session_id: SessionId,
+        reason: DisconnectReason,
+    },
+    /// `delivered` connections have accepted the event, `failed` connections have refused it.
+    BroadcastCompleted {
+        delivered: usize,
+        failed: usize,
+    },
+    /// The connection remover has checked all connections,
+    /// `removed` connections were idle or didn't accept the keep-alive message.
+    SweepCompleted {
+        removed: usize,
+        duration: Duration,
+    },
+    /// The subscriber has been too slow, `missed` events have been dropped.
+    Lagged {
+        missed: u64,
+    },
+}
+
+// ------ Lifecycle ------
+
+pub(crate) struct Lifecycle(Sender<SSELifecycleEvent>);
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self(broadcast::channel(CAPACITY).0)
+    }
+}
+
+impl Lifecycle {
+    // The event isn't even created when nobody is subscribed.
+    pub(crate) fn publish(&self, event: impl FnOnce() -> SSELifecycleEvent) {
+        if self.0.receiver_count() > 0 {
+            let _ = self.0.send(event());
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> Pin<Box<dyn Stream<Item = SSELifecycleEvent> + Send>> {
+        let receiver = self.0.subscribe();
+        Box::pin(stream::unfold(receiver, |mut receiver| async move {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => SSELifecycleEvent::Lagged { missed },
+                Err(RecvError::Closed) => return None,
+            };
+            Some((event, receiver))
+        }))
+    }
+}
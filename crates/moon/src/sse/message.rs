@@ -0,0 +1,607 @@
+use super::base64;
+use super::{Error, FrameFormatter};
+use actix_web::web::{Bytes, BytesMut};
+use moonlight::ProtocolEvent;
+use std::borrow::Cow;
+use std::iter;
+use std::sync::OnceLock;
+
+/// Encodes the event for [broadcast_bytes](super::ShareableSSEMethods::broadcast_bytes),
+/// so the same message can be sent many times without encoding it again.
+/// Names of [ProtocolEvent]s and names with line breaks are rejected.
+pub fn frame(event: &str, data: &str) -> Result<Bytes, Error> {
+    Error::check_event_name(event)?;
+    message_body(event, data)
+}
+
+/// Appended to the event name of binary events sent by
+/// [Connection::send_binary](super::Connection::send_binary), their data is
+/// the payload encoded by the standard base64 alphabet with padding.
+/// The client decodes an event `delta-b64` e.g. with `atob` in JS.
+pub const BINARY_EVENT_SUFFIX: &str = "-b64";
+
+// ------ PreparedEvent ------
+
+/// The event encoded once for [broadcast_prepared](super::ShareableSSEMethods::broadcast_prepared)
+/// and [Connection::send_prepared](super::Connection::send_prepared).
+/// The encoded body is shared by all messages, the event id line is written in front of it.
+#[derive(Debug, Clone)]
+pub struct PreparedEvent {
+    body: Bytes,
+}
+
+impl PreparedEvent {
+    /// Names of [ProtocolEvent]s and names with line breaks are rejected
+    /// like by [Connection::send](super::Connection::send).
+    pub fn new(event: &str, data: &str) -> Result<Self, Error> {
+        Error::check_event_name(event)?;
+        Ok(Self {
+            body: message_body(event, data)?,
+        })
+    }
+
+    pub(crate) fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    // The event and the data for the broker, custom formatters and chunking.
+    // Line breaks in the data are decoded as `\n`, the same way as by `EventSource`.
+    pub(crate) fn decode(&self) -> (String, String) {
+        let body = String::from_utf8_lossy(&self.body);
+        let mut event = "";
+        let mut data_lines = Vec::new();
+        for line in body.split('\n') {
+            if let Some(data_line) = line.strip_prefix("data: ") {
+                data_lines.push(data_line);
+            } else if let Some(name) = line.strip_prefix("event: ") {
+                event = name;
+            }
+        }
+        (event.to_owned(), data_lines.join("\n"))
+    }
+}
+
+// ------ Frame ------
+
+// The message queued for a connection. The `id:` line is written as its own chunk
+// in front of the body, so the body encoded once is shared by the frames of all connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Frame {
+    id_line: Option<Bytes>,
+    body: Bytes,
+}
+
+impl Frame {
+    pub(crate) fn new(id: Option<u64>, body: Bytes) -> Self {
+        Self {
+            id_line: id.map(|id| Bytes::from(format!("id: {id}\n"))),
+            body,
+        }
+    }
+
+    pub(crate) fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    pub(crate) fn into_parts(self) -> (Option<Bytes>, Bytes) {
+        (self.id_line, self.body)
+    }
+
+    // The body is copied only when the frame has an id.
+    pub(crate) fn into_bytes(self) -> Bytes {
+        let Some(id_line) = self.id_line else {
+            return self.body;
+        };
+        let mut message = BytesMut::with_capacity(id_line.len() + self.body.len());
+        message.extend_from_slice(&id_line);
+        message.extend_from_slice(&self.body);
+        message.freeze()
+    }
+}
+
+impl From<Bytes> for Frame {
+    fn from(body: Bytes) -> Self {
+        Self {
+            id_line: None,
+            body,
+        }
+    }
+}
+
+// ------ EventName ------
+
+/// The event name with its `event:` line encoded only once, for events sent very often by
+/// [Connection::send_named](super::Connection::send_named) and
+/// [broadcast_named](super::ShareableSSEMethods::broadcast_named).
+/// [from_static](Self::from_static) allows to keep the names in `static`s.
+/// Names of [ProtocolEvent]s are rejected when the event is sent.
+#[derive(Debug, Clone)]
+pub struct EventName {
+    name: Cow<'static, str>,
+    line: OnceLock<String>,
+}
+
+impl EventName {
+    /// The line is encoded by the first send.
+    /// A name with a line break fails the compilation of the `static`.
+    pub const fn from_static(name: &'static str) -> Self {
+        let bytes = name.as_bytes();
+        let mut index = 0;
+        while index < bytes.len() {
+            if matches!(bytes[index], b'\r' | b'\n') {
+                panic!("SSE event name contains a line break");
+            }
+            index += 1;
+        }
+        Self {
+            name: Cow::Borrowed(name),
+            line: OnceLock::new(),
+        }
+    }
+
+    /// Names of [ProtocolEvent]s and names with line breaks are rejected
+    /// like by [Connection::send](super::Connection::send).
+    pub fn new(name: impl Into<String>) -> Result<Self, Error> {
+        let name = name.into();
+        Error::check_event_name(&name)?;
+        let event_name = Self {
+            name: Cow::Owned(name),
+            line: OnceLock::new(),
+        };
+        event_name.line();
+        Ok(event_name)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    // The name has been checked for line breaks by the constructors.
+    fn line(&self) -> &str {
+        self.line
+            .get_or_init(|| ["event: ", &self.name, "\n"].concat())
+    }
+}
+
+// Like `frame`, but for events sent by Moon itself.
+pub(crate) fn protocol_frame(event: ProtocolEvent, data: &str) -> Bytes {
+    protocol_message_body(event, data)
+}
+
+#[cfg(test)]
+pub(crate) fn message(id: Option<u64>, event: &str, data: &str) -> Bytes {
+    Frame::new(id, message_body(event, data).unwrap()).into_bytes()
+}
+
+// The event name and data lines with the final blank line, but without the id,
+// so the same body can be written after a different `id:` line for each connection.
+pub(crate) fn message_body(event: &str, data: &str) -> Result<Bytes, Error> {
+    let mut body = String::with_capacity("event: \n".len() + event.len() + data_capacity(data));
+    push_event_line(&mut body, event, "")?;
+    push_data_lines(&mut body, data);
+    body.push('\n');
+    Ok(Bytes::from(body))
+}
+
+// Like `message_body`, but the event line is copied from `EventName`.
+pub(crate) fn named_message_body(event: &EventName, data: &str) -> Bytes {
+    body_with_event_line(event.line(), data)
+}
+
+// Protocol event names don't contain line breaks, so they aren't checked.
+fn protocol_message_body(event: ProtocolEvent, data: &str) -> Bytes {
+    body_with_event_line(&["event: ", event.as_str(), "\n"].concat(), data)
+}
+
+fn body_with_event_line(event_line: &str, data: &str) -> Bytes {
+    let mut body = String::with_capacity(event_line.len() + data_capacity(data));
+    body.push_str(event_line);
+    push_data_lines(&mut body, data);
+    body.push('\n');
+    Bytes::from(body)
+}
+
+// Includes the final blank line.
+fn data_capacity(data: &str) -> usize {
+    let line_breaks = data
+        .bytes()
+        .filter(|byte| matches!(byte, b'\r' | b'\n'))
+        .count();
+    data.len() + (line_breaks + 1) * "data: \n".len() + 1
+}
+
+fn push_data_lines(body: &mut String, data: &str) {
+    for line in lines(data) {
+        body.push_str("data: ");
+        body.push_str(line);
+        body.push('\n');
+    }
+}
+
+// The data is encoded directly into the body, base64 doesn't contain line breaks.
+pub(crate) fn binary_message_body(event: &str, data: &[u8]) -> Result<Bytes, Error> {
+    let capacity = "event: \ndata: \n\n".len()
+        + event.len()
+        + BINARY_EVENT_SUFFIX.len()
+        + base64::encoded_len(data.len());
+    let mut body = String::with_capacity(capacity);
+    push_event_line(&mut body, event, BINARY_EVENT_SUFFIX)?;
+    body.push_str("data: ");
+    base64::encode_into(&mut body, data);
+    body.push_str("\n\n");
+    Ok(Bytes::from(body))
+}
+
+fn push_event_line(body: &mut String, event: &str, suffix: &str) -> Result<(), Error> {
+    Error::check_line_breaks(event)?;
+    body.push_str("event: ");
+    body.push_str(event);
+    body.push_str(suffix);
+    body.push('\n');
+    Ok(())
+}
+
+// ------ MessageBody ------
+
+// The message without the id, so it can be stamped with a different id for each connection.
+pub(crate) enum MessageBody<'a> {
+    // created by `message_body`, shared by the frames of all connections
+    Default(Bytes),
+    // custom formatters get the id together with the event
+    Custom {
+        formatter: &'a dyn FrameFormatter,
+        event: Cow<'a, str>,
+        data: Cow<'a, str>,
+    },
+}
+
+impl<'a> MessageBody<'a> {
+    // Only event names with line breaks are rejected here,
+    // the public send methods reject protocol event names before.
+    pub(crate) fn new(
+        formatter: Option<&'a dyn FrameFormatter>,
+        event: &'a str,
+        data: &'a str,
+    ) -> Result<Self, Error> {
+        match formatter {
+            Some(formatter) => {
+                Error::check_line_breaks(event)?;
+                Ok(Self::Custom {
+                    formatter,
+                    event: Cow::Borrowed(event),
+                    data: Cow::Borrowed(data),
+                })
+            }
+            None => message_body(event, data).map(Self::Default),
+        }
+    }
+
+    // For events sent by Moon itself.
+    pub(crate) fn protocol(
+        formatter: Option<&'a dyn FrameFormatter>,
+        event: ProtocolEvent,
+        data: &'a str,
+    ) -> Self {
+        match formatter {
+            Some(formatter) => Self::Custom {
+                formatter,
+                event: Cow::Borrowed(event.as_str()),
+                data: Cow::Borrowed(data),
+            },
+            None => Self::Default(protocol_message_body(event, data)),
+        }
+    }
+
+    pub(crate) fn named(
+        formatter: Option<&'a dyn FrameFormatter>,
+        event: &'a EventName,
+        data: &'a str,
+    ) -> Self {
+        match formatter {
+            Some(formatter) => Self::Custom {
+                formatter,
+                event: Cow::Borrowed(event.as_str()),
+                data: Cow::Borrowed(data),
+            },
+            None => Self::Default(named_message_body(event, data)),
+        }
+    }
+
+    // Custom formatters encode the prepared event again, they may not use `message_body`.
+    pub(crate) fn prepared(
+        formatter: Option<&'a dyn FrameFormatter>,
+        prepared_event: &PreparedEvent,
+    ) -> Self {
+        match formatter {
+            Some(formatter) => {
+                let (event, data) = prepared_event.decode();
+                Self::Custom {
+                    formatter,
+                    event: Cow::Owned(event),
+                    data: Cow::Owned(data),
+                }
+            }
+            None => Self::Default(prepared_event.body.clone()),
+        }
+    }
+
+    // See `BINARY_EVENT_SUFFIX`.
+    pub(crate) fn binary(
+        formatter: Option<&'a dyn FrameFormatter>,
+        event: &'a str,
+        data: &[u8],
+    ) -> Result<Self, Error> {
+        match formatter {
+            Some(formatter) => {
+                Error::check_line_breaks(event)?;
+                Ok(Self::Custom {
+                    formatter,
+                    event: Cow::Owned([event, BINARY_EVENT_SUFFIX].concat()),
+                    data: Cow::Owned(base64::encode(data)),
+                })
+            }
+            None => binary_message_body(event, data).map(Self::Default),
+        }
+    }
+
+    pub(crate) fn frame(&self, id: Option<u64>) -> Frame {
+        match self {
+            Self::Default(body) => Frame::new(id, body.clone()),
+            Self::Custom {
+                formatter,
+                event,
+                data,
+            } => Frame::from(formatter.frame(event, data, id)),
+        }
+    }
+
+    pub(crate) fn message(&self, id: Option<u64>) -> Bytes {
+        self.frame(id).into_bytes()
+    }
+}
+
+pub(crate) fn comment(text: &str) -> Bytes {
+    let mut comment = String::with_capacity(text.len() + 4);
+    for line in lines(text) {
+        comment.push_str(": ");
+        comment.push_str(line);
+        comment.push('\n');
+    }
+    comment.push('\n');
+    Bytes::from(comment)
+}
+
+pub(crate) fn retry(milliseconds: u32) -> Bytes {
+    Bytes::from(["retry: ", &milliseconds.to_string(), "\n\n"].concat())
+}
+
+pub(crate) fn gap_message(formatter: Option<&dyn FrameFormatter>, last_event_id: u64) -> Bytes {
+    MessageBody::protocol(formatter, ProtocolEvent::Gap, &last_event_id.to_string()).message(None)
+}
+
+// The event name of a message created by `message_body`, used to coalesce messages.
+pub(crate) fn event_name(message: &Bytes) -> Bytes {
+    const PREFIX: &[u8] = b"event: ";
+    let mut line_start = 0;
+    for line in message.split(|byte| *byte == b'\n') {
+        let line_end = line_start + line.len();
+        if line.starts_with(PREFIX) {
+            return message.slice(line_start + PREFIX.len()..line_end);
+        }
+        line_start = line_end + 1;
+    }
+    Bytes::new()
+}
+
+// Splits the text on `\r\n`, `\n` and `\r` the same way as the SSE parser in browsers.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(text);
+    iter::from_fn(move || {
+        let current = rest?;
+        let Some(index) = current.find(['\r', '\n']) else {
+            rest = None;
+            return Some(current);
+        };
+        let line_break_length = if current[index..].starts_with("\r\n") {
+            2
+        } else {
+            1
+        };
+        rest = Some(&current[index + line_break_length..]);
+        Some(&current[..index])
+    })
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sse::DefaultFrameFormatter;
+
+    // Simplified `EventSource` parser - returns the `data` of the only event in the message.
+    fn parse_data(message: &Bytes) -> String {
+        let message = std::str::from_utf8(message).unwrap();
+        assert!(message.ends_with("\n\n"));
+        let data_lines = message
+            .trim_end_matches('\n')
+            .split('\n')
+            .filter_map(|line| line.strip_prefix("data: "))
+            .collect::<Vec<_>>();
+        data_lines.join("\n")
+    }
+
+    #[test]
+    fn test_named_message() {
+        // ------ ARRANGE ------
+        static EVENT: EventName = EventName::from_static("price_changed");
+
+        // ------ ACT ------
+        let bodies = [
+            named_message_body(&EVENT, "1\n2"),
+            named_message_body(&EventName::new("price_changed").unwrap(), "1\n2"),
+        ];
+
+        // ------ ASSERT ------
+        assert_eq!(
+            bodies,
+            [
+                message_body("price_changed", "1\n2").unwrap(),
+                bodies[0].clone()
+            ]
+        );
+        assert_eq!(bodies[0], "event: price_changed\ndata: 1\ndata: 2\n\n");
+        assert!(matches!(
+            EventName::new("price\nchanged"),
+            Err(Error::InvalidEventName(_))
+        ));
+        assert!(matches!(
+            EventName::new("epoch"),
+            Err(Error::ReservedEventName(ProtocolEvent::Epoch))
+        ));
+    }
+
+    #[test]
+    fn test_multi_line_data() {
+        // ------ ARRANGE ------
+        let data = "first\nsecond\n\nfourth";
+
+        // ------ ACT ------
+        let message = message(None, "event", data);
+
+        // ------ ASSERT ------
+        assert_eq!(
+            message,
+            "event: event\ndata: first\ndata: second\ndata: \ndata: fourth\n\n"
+        );
+        assert_eq!(parse_data(&message), data);
+    }
+
+    #[test]
+    fn test_crlf_data() {
+        // ------ ARRANGE ------
+        let data = "first\r\nsecond\rthird";
+
+        // ------ ACT ------
+        let message = message(Some(7), "event", data);
+
+        // ------ ASSERT ------
+        assert_eq!(
+            message,
+            "id: 7\nevent: event\ndata: first\ndata: second\ndata: third\n\n"
+        );
+        assert_eq!(parse_data(&message), "first\nsecond\nthird");
+    }
+
+    #[test]
+    fn test_trailing_new_line_data() {
+        // ------ ARRANGE ------
+        let data = "first\n";
+
+        // ------ ACT ------
+        let message = message(None, "event", data);
+
+        // ------ ASSERT ------
+        assert_eq!(message, "event: event\ndata: first\ndata: \n\n");
+        assert_eq!(parse_data(&message), data);
+    }
+
+    #[test]
+    fn test_event_name_with_new_line() {
+        // ------ ARRANGE ------
+        let event = "event\n\nevent: injected";
+
+        // ------ ACT ------
+        let results = [
+            message_body(event, "data").map(drop),
+            binary_message_body(event, b"data").map(drop),
+            MessageBody::new(Some(&DefaultFrameFormatter), event, "data").map(drop),
+            frame(event, "data").map(drop),
+        ];
+
+        // ------ ASSERT ------
+        for result in results {
+            assert!(
+                matches!(&result, Err(Error::InvalidEventName(name)) if name == event),
+                "{result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_event_name() {
+        // ------ ARRANGE ------
+        let message = message(Some(3), "position", "event: fake");
+
+        // ------ ACT ------
+        let event_name = event_name(&message);
+
+        // ------ ASSERT ------
+        assert_eq!(event_name, "position");
+    }
+
+    #[test]
+    fn test_prepared_event_gets_id() {
+        // ------ ARRANGE ------
+        let prepared_event = PreparedEvent::new("notification", "first\nsecond").unwrap();
+        let body = MessageBody::prepared(None, &prepared_event);
+
+        // ------ ACT ------
+        let frames = [body.frame(Some(1)), body.frame(Some(2))];
+
+        // ------ ASSERT ------
+        for frame in &frames {
+            // The body isn't copied for each connection.
+            assert_eq!(frame.body().as_ptr(), prepared_event.body().as_ptr());
+        }
+        assert_eq!(
+            frames.map(Frame::into_bytes),
+            [
+                message(Some(1), "notification", "first\nsecond"),
+                message(Some(2), "notification", "first\nsecond"),
+            ]
+        );
+        assert_eq!(
+            prepared_event.decode(),
+            ("notification".to_owned(), "first\nsecond".to_owned())
+        );
+        assert!(PreparedEvent::new("chunk", "").is_err());
+    }
+
+    #[test]
+    fn test_binary_message() {
+        // ------ ARRANGE ------
+        let body = MessageBody::binary(None, "delta", &[0xfb, 0xff, 0x00, 0x10]).unwrap();
+
+        // ------ ACT ------
+        let message = body.message(Some(2));
+
+        // ------ ASSERT ------
+        assert_eq!(message, "id: 2\nevent: delta-b64\ndata: +/8AEA==\n\n");
+    }
+
+    #[test]
+    fn test_hostile_data_stays_in_one_event() {
+        // ------ ARRANGE ------
+        let hostile_data = [
+            "ok\n\nevent: admin_grant\ndata: evil",
+            "ok\r\n\r\nid: 999\r\nretry: 1",
+            "ok\r\revent: admin_grant\n",
+            "\n\n\n",
+        ];
+
+        for data in hostile_data {
+            // ------ ACT ------
+            let message = message(Some(1), "chat", data);
+
+            // ------ ASSERT ------
+            let message = std::str::from_utf8(&message).unwrap();
+            let (fields, rest) = message.split_once("\n\n").unwrap();
+            assert_eq!(rest, "", "{data:?} has injected another event");
+            let mut lines = fields.split('\n');
+            assert_eq!(lines.next(), Some("id: 1"));
+            assert_eq!(lines.next(), Some("event: chat"));
+            assert!(lines.all(|line| line.starts_with("data: ")), "{message:?}");
+        }
+    }
+}
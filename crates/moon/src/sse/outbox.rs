@@ -0,0 +1,102 @@
+use super::shard_map::ShardMap;
+use super::Connection;
+use moonlight::SessionId;
+use std::collections::VecDeque;
+
+// ------ SessionOutbox ------
+
+/// Keeps messages sent to sessions without a connection until the session connects again.
+/// Implement it for a database-backed storage to keep the messages across server restarts.
+pub trait SessionOutbox: Send + Sync {
+    fn push(&self, session_id: SessionId, event: &str, data: &str);
+
+    /// Removes and returns the session messages, the oldest first.
+    fn drain(&self, session_id: &SessionId) -> Vec<(String, String)>;
+}
+
+// ------ MemoryOutbox ------
+
+/// In-memory [SessionOutbox], the oldest messages are dropped when the session has too many.
+pub struct MemoryOutbox {
+    max_messages_per_session: usize,
+    messages: ShardMap<SessionId, VecDeque<(String, String)>>,
+}
+
+impl MemoryOutbox {
+    pub fn new(max_messages_per_session: usize) -> Self {
+        Self {
+            max_messages_per_session,
+            messages: ShardMap::new(),
+        }
+    }
+}
+
+impl SessionOutbox for MemoryOutbox {
+    fn push(&self, session_id: SessionId, event: &str, data: &str) {
+        if self.max_messages_per_session == 0 {
+            return;
+        }
+        self.messages.alter(session_id, |messages| {
+            let mut messages = messages.unwrap_or_default();
+            if messages.len() >= self.max_messages_per_session {
+                messages.pop_front();
+            }
+            messages.push_back((event.to_owned(), data.to_owned()));
+            Some(messages)
+        });
+    }
+
+    fn drain(&self, session_id: &SessionId) -> Vec<(String, String)> {
+        self.messages
+            .remove(session_id)
+            .map(Vec::from)
+            .unwrap_or_default()
+    }
+}
+
+// ------ flush ------
+
+// Messages that can't be sent are returned to the outbox.
+pub(crate) fn flush(outbox: &dyn SessionOutbox, session_id: SessionId, connection: &Connection) {
+    let mut messages = outbox.drain(&session_id).into_iter();
+    while let Some((event, data)) = messages.next() {
+        // The outbox contains also protocol events, e.g. `down_msg`.
+        if connection.send_initial(&event, &data).is_err() {
+            outbox.push(session_id, &event, &data);
+            for (event, data) in messages {
+                outbox.push(session_id, &event, &data);
+            }
+            return;
+        }
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_outbox_drops_oldest_messages() {
+        // ------ ARRANGE ------
+        let outbox = MemoryOutbox::new(2);
+        let session_id = SessionId::new();
+
+        // ------ ACT ------
+        outbox.push(session_id, "notification", "1");
+        outbox.push(session_id, "notification", "2");
+        outbox.push(session_id, "notification", "3");
+
+        // ------ ASSERT ------
+        let messages = outbox.drain(&session_id);
+        assert_eq!(
+            messages,
+            [
+                ("notification".to_owned(), "2".to_owned()),
+                ("notification".to_owned(), "3".to_owned()),
+            ]
+        );
+        assert!(outbox.drain(&session_id).is_empty());
+    }
+}
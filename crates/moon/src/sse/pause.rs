@@ -0,0 +1,124 @@
+use super::channel::MessageReceiver;
+use super::message::Frame;
+use super::stats::Counters;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+// ------ Pause ------
+
+// Shared by the `Connection` and its `EventStream`,
+// the stream moves messages sent while the connection is paused to `messages`.
+pub(crate) struct Pause {
+    capacity: usize,
+    counters: Arc<Counters>,
+    state: Mutex<State>,
+}
+
+struct State {
+    paused: bool,
+    messages: VecDeque<Frame>,
+    waker: Option<Waker>,
+}
+
+impl Pause {
+    pub(crate) fn new(capacity: usize, counters: Arc<Counters>) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            counters,
+            state: Mutex::new(State {
+                paused: false,
+                messages: VecDeque::new(),
+                waker: None,
+            }),
+        }
+    }
+
+    pub(crate) fn pause(&self) {
+        self.state.lock().paused = true;
+    }
+
+    pub(crate) fn resume(&self) {
+        let mut state = self.state.lock();
+        state.paused = false;
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.state.lock().paused
+    }
+
+    pub(crate) fn buffered_messages(&self) -> usize {
+        self.state.lock().messages.len()
+    }
+
+    // Returns `true` when the connection is paused and the stream has to wait for `resume`.
+    // The buffered messages are flushed when the connection is closed.
+    pub(crate) fn buffer(&self, messages: &mut MessageReceiver, cx: &mut Context<'_>) -> bool {
+        let mut state = self.state.lock();
+        if !state.paused {
+            return false;
+        }
+        loop {
+            match messages.poll_recv(cx) {
+                Poll::Ready(Some(message)) => {
+                    if state.messages.len() >= self.capacity {
+                        state.messages.pop_front();
+                        self.counters.message_dropped();
+                    }
+                    state.messages.push_back(message);
+                }
+                Poll::Ready(None) => return false,
+                Poll::Pending => {
+                    state.waker = Some(cx.waker().clone());
+                    return true;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn pop_buffered(&self) -> Option<Frame> {
+        self.state.lock().messages.pop_front()
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sse::channel::channel;
+    use crate::sse::ConnectionKind;
+    use actix_web::web::Bytes;
+    use futures::task::noop_waker_ref;
+
+    #[test]
+    fn test_buffer_while_paused() {
+        // ------ ARRANGE ------
+        let counters = Arc::<Counters>::default();
+        let (sender, mut receiver) = channel(ConnectionKind::DropOldest { keep: 10 }, &counters);
+        let pause = Pause::new(2, Arc::clone(&counters));
+        let mut cx = Context::from_waker(noop_waker_ref());
+        pause.pause();
+
+        // ------ ACT ------
+        for message in ["1", "2", "3"] {
+            sender.send(Bytes::from(message)).unwrap();
+        }
+        let waits = pause.buffer(&mut receiver, &mut cx);
+        pause.resume();
+
+        // ------ ASSERT ------
+        assert!(waits);
+        assert!(!pause.buffer(&mut receiver, &mut cx));
+        assert_eq!(pause.pop_buffered(), Some(Frame::from(Bytes::from("2"))));
+        assert_eq!(pause.pop_buffered(), Some(Frame::from(Bytes::from("3"))));
+        assert_eq!(pause.pop_buffered(), None);
+        assert_eq!(counters.stats(0, Default::default(), 0).dropped_messages, 1);
+    }
+}
@@ -0,0 +1,163 @@
+use super::channel::MessageReceiver;
+use super::config::{RateLimit, RateLimitExceeded};
+use super::message::{event_name, Frame};
+use super::stats::{ConnectionActivity, Counters};
+use actix_web::web::Bytes;
+use futures::Future;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::time::{sleep_until, Duration, Instant, Sleep};
+
+// ------ RateLimiter ------
+
+// Token bucket between the message queue and the `EventStream`,
+// control messages like keep-alive don't pass through it.
+pub(crate) struct RateLimiter {
+    rate_limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+    // coalesced messages waiting for tokens, at most one per event name
+    pending: VecDeque<(Bytes, Frame)>,
+    next_token: Option<Pin<Box<Sleep>>>,
+    receiver_closed: bool,
+    activity: Arc<ConnectionActivity>,
+    counters: Arc<Counters>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(
+        rate_limit: RateLimit,
+        activity: Arc<ConnectionActivity>,
+        counters: Arc<Counters>,
+    ) -> Self {
+        Self {
+            rate_limit,
+            tokens: f64::from(rate_limit.max_events_per_second),
+            refilled_at: Instant::now(),
+            pending: VecDeque::new(),
+            next_token: None,
+            receiver_closed: false,
+            activity,
+            counters,
+        }
+    }
+
+    pub(crate) fn poll_recv(
+        &mut self,
+        receiver: &mut MessageReceiver,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Frame>> {
+        loop {
+            if self.has_token(Instant::now()) {
+                let message = match self.pending.pop_front() {
+                    Some((_, message)) => message,
+                    None if self.receiver_closed => return Poll::Ready(None),
+                    None => match receiver.poll_recv(cx) {
+                        Poll::Ready(Some(message)) => message,
+                        Poll::Ready(None) => return Poll::Ready(None),
+                        Poll::Pending => return Poll::Pending,
+                    },
+                };
+                self.tokens -= 1.;
+                return Poll::Ready(Some(message));
+            }
+            // The queue is emptied even without tokens so it can't grow because of the limit.
+            while !self.receiver_closed {
+                match receiver.poll_recv(cx) {
+                    Poll::Ready(Some(message)) => self.limit(message),
+                    Poll::Ready(None) => self.receiver_closed = true,
+                    Poll::Pending => break,
+                }
+            }
+            if self.pending.is_empty() {
+                if self.receiver_closed {
+                    return Poll::Ready(None);
+                }
+                return Poll::Pending;
+            }
+            let wait = (1. - self.tokens) / f64::from(self.rate_limit.max_events_per_second);
+            let next_token = self.next_token.get_or_insert_with(|| {
+                Box::pin(sleep_until(
+                    self.refilled_at + Duration::from_secs_f64(wait),
+                ))
+            });
+            if next_token.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            self.next_token = None;
+        }
+    }
+
+    fn has_token(&mut self, now: Instant) -> bool {
+        let max_tokens = f64::from(self.rate_limit.max_events_per_second);
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * max_tokens).min(max_tokens);
+        self.refilled_at = now;
+        self.tokens >= 1.
+    }
+
+    fn limit(&mut self, message: Frame) {
+        self.activity.message_rate_limited();
+        self.counters.message_rate_limited();
+        match self.rate_limit.on_exceeded {
+            RateLimitExceeded::Drop => (),
+            RateLimitExceeded::Coalesce => {
+                let event = event_name(message.body());
+                // The replaced message is moved to the back to keep event ids in order.
+                if let Some(index) = self.pending.iter().position(|(name, _)| *name == event) {
+                    self.pending.remove(index);
+                }
+                self.pending.push_back((event, message));
+            }
+        }
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sse::message::message;
+
+    fn frame(id: u64, event: &str, data: &str) -> Frame {
+        Frame::new(Some(id), message(None, event, data))
+    }
+
+    #[test]
+    fn test_coalesce_by_event_name() {
+        // ------ ARRANGE ------
+        let rate_limit = RateLimit {
+            max_events_per_second: 1,
+            on_exceeded: RateLimitExceeded::Coalesce,
+        };
+        let activity = Arc::<ConnectionActivity>::default();
+        let mut rate_limiter = RateLimiter::new(rate_limit, Arc::clone(&activity), Arc::default());
+        rate_limiter.tokens = 0.;
+
+        // ------ ACT ------
+        rate_limiter.limit(frame(1, "position", "1"));
+        rate_limiter.limit(frame(2, "chat", "hello"));
+        rate_limiter.limit(frame(3, "position", "2"));
+
+        // ------ ASSERT ------
+        let pending = rate_limiter
+            .pending
+            .iter()
+            .map(|(_, message)| message.clone().into_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pending,
+            [
+                message(Some(2), "chat", "hello"),
+                message(Some(3), "position", "2"),
+            ]
+        );
+        assert_eq!(activity.rate_limited_messages(), 3);
+        assert!(!rate_limiter.has_token(rate_limiter.refilled_at));
+    }
+}
@@ -0,0 +1,34 @@
+use super::{Connection, EventStream};
+use moonlight::ProtocolEvent;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+// ------ SessionReplacement ------
+
+/// Options of [replace_session](super::ShareableSSEMethods::replace_session).
+#[derive(Debug, Clone)]
+pub struct SessionReplacement {
+    /// The final event sent to the old connections.
+    pub event: Cow<'static, str>,
+    /// Topic subscriptions, groups and connection extensions of the old session
+    /// are moved to the new connection. The extensions of the new connection are kept.
+    pub transfer_state: bool,
+}
+
+impl Default for SessionReplacement {
+    fn default() -> Self {
+        Self {
+            event: Cow::Borrowed(ProtocolEvent::SessionReplaced.as_str()),
+            transfer_state: false,
+        }
+    }
+}
+
+// ------ ReplaceOutcome ------
+
+pub struct ReplaceOutcome {
+    /// `false` when the old session didn't have any connection.
+    pub replaced: bool,
+    pub connection: Arc<Connection>,
+    pub event_stream: EventStream,
+}
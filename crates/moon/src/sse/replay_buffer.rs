@@ -0,0 +1,210 @@
+use super::channel::{MessageSender, SendError};
+use super::message::{gap_message, Frame, MessageBody};
+use super::{ConnectionId, FrameFormatter};
+use smallvec::SmallVec;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+// ------ ReplayBufferSize ------
+
+/// How many last messages are kept per session to be replayed after a reconnect.
+/// `ReplayBufferSize(0)` disables replaying.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayBufferSize(pub usize);
+
+// ------ ReplayBuffer ------
+
+pub(crate) struct ReplayBuffer {
+    capacity: usize,
+    last_id: u64,
+    // messages sent only to one connection of the session aren't replayed
+    messages: VecDeque<(u64, Option<ConnectionId>, Frame)>,
+    // the senders of all connections of the session
+    senders: SmallVec<[(ConnectionId, MessageSender); 2]>,
+    formatter: Option<Arc<dyn FrameFormatter>>,
+}
+
+impl ReplayBuffer {
+    pub(crate) fn new(capacity: usize, formatter: Option<Arc<dyn FrameFormatter>>) -> Self {
+        Self {
+            capacity,
+            last_id: 0,
+            messages: VecDeque::with_capacity(capacity),
+            senders: SmallVec::new(),
+            formatter,
+        }
+    }
+
+    pub(crate) fn last_id(&self) -> u64 {
+        self.last_id
+    }
+
+    // Sends the message to all connections of the session.
+    pub(crate) fn send(&mut self, body: &MessageBody) -> Vec<Result<(), SendError>> {
+        let message = self.push(None, body);
+        self.senders
+            .iter()
+            .map(|(_, sender)| sender.send(message.clone()))
+            .collect()
+    }
+
+    // Returns the id of the sent message.
+    // `now` bypasses the held messages of a connection that is being initialized.
+    pub(crate) fn send_to(
+        &mut self,
+        connection_id: ConnectionId,
+        body: &MessageBody,
+        now: bool,
+    ) -> Result<u64, SendError> {
+        let message = self.push(Some(connection_id), body);
+        let Some((_, sender)) = self.senders.iter().find(|(id, _)| *id == connection_id) else {
+            return Err(SendError::ConnectionClosed(message.into_bytes()));
+        };
+        let result = if now {
+            sender.send_now(message)
+        } else {
+            sender.send(message)
+        };
+        result.map(|()| self.last_id)
+    }
+
+    // Stores the message for the next reconnect without sending it to the current connections.
+    pub(crate) fn requeue(&mut self, body: &MessageBody) {
+        self.push(None, body);
+    }
+
+    pub(crate) fn remove_sender(&mut self, connection_id: ConnectionId) {
+        self.senders.retain(|(id, _)| *id != connection_id);
+    }
+
+    pub(crate) fn replay(
+        &mut self,
+        last_event_id: Option<u64>,
+        connection_id: ConnectionId,
+        sender: MessageSender,
+    ) {
+        self.senders.push((connection_id, sender.clone()));
+
+        let Some(last_event_id) = last_event_id else {
+            return;
+        };
+        if last_event_id > self.last_id {
+            // The id comes from a previous server run - we don't know what the client missed.
+            // Continue numbering from the client's id so it keeps growing on the client side.
+            self.last_id = last_event_id;
+            self.messages.clear();
+            let _ = sender.send_now(gap_message(self.formatter.as_deref(), last_event_id));
+            return;
+        }
+        let first_buffered_id = self
+            .messages
+            .front()
+            .map(|(id, _, _)| *id)
+            .unwrap_or(self.last_id + 1);
+        if last_event_id + 1 < first_buffered_id {
+            // Some missed messages have been already pushed out of the buffer.
+            let _ = sender.send_now(gap_message(self.formatter.as_deref(), last_event_id));
+        }
+        let missed_messages = self
+            .messages
+            .iter()
+            .filter(|(id, target, _)| *id > last_event_id && target.is_none());
+        for (_, _, message) in missed_messages {
+            let _ = sender.send_now(message.clone());
+        }
+    }
+
+    fn push(&mut self, target: Option<ConnectionId>, body: &MessageBody) -> Frame {
+        self.last_id += 1;
+        let message = body.frame(Some(self.last_id));
+        if self.messages.len() == self.capacity {
+            self.messages.pop_front();
+        }
+        self.messages
+            .push_back((self.last_id, target, message.clone()));
+        message
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sse::channel::{channel, ConnectionKind, MessageReceiver};
+    use crate::sse::message::message;
+    use actix_web::web::Bytes;
+    use futures::task::noop_waker_ref;
+    use moonlight::ProtocolEvent;
+    use std::task::{Context, Poll};
+
+    fn buffer_with_messages(capacity: usize, data: &[&str]) -> ReplayBuffer {
+        let mut replay_buffer = ReplayBuffer::new(capacity, None);
+        for data in data {
+            replay_buffer.requeue(&MessageBody::new(None, "news", data).unwrap());
+        }
+        replay_buffer
+    }
+
+    fn replay(replay_buffer: &mut ReplayBuffer, last_event_id: u64) -> MessageReceiver {
+        let (sender, receiver) = channel(ConnectionKind::Unbounded, &Arc::default());
+        replay_buffer.replay(Some(last_event_id), ConnectionId::new(), sender);
+        receiver
+    }
+
+    fn received_messages(receiver: &mut MessageReceiver) -> Vec<Bytes> {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut messages = Vec::new();
+        while let Poll::Ready(Some(frame)) = receiver.poll_recv(&mut cx) {
+            messages.push(frame.into_bytes());
+        }
+        messages
+    }
+
+    fn gap(last_event_id: &str) -> Bytes {
+        message(None, ProtocolEvent::Gap.as_str(), last_event_id)
+    }
+
+    #[test]
+    fn test_overflow_emits_gap() {
+        // ------ ARRANGE ------
+        let mut replay_buffer = buffer_with_messages(2, &["1", "2", "3"]);
+
+        // ------ ACT ------
+        let mut missed_evicted = replay(&mut replay_buffer, 0);
+        let mut missed_buffered = replay(&mut replay_buffer, 1);
+
+        // ------ ASSERT ------
+        assert_eq!(
+            received_messages(&mut missed_evicted),
+            [
+                gap("0"),
+                message(Some(2), "news", "2"),
+                message(Some(3), "news", "3")
+            ]
+        );
+        assert_eq!(
+            received_messages(&mut missed_buffered),
+            [message(Some(2), "news", "2"), message(Some(3), "news", "3")]
+        );
+    }
+
+    #[test]
+    fn test_stale_last_event_id_resets_numbering() {
+        // ------ ARRANGE ------
+        let mut replay_buffer = buffer_with_messages(10, &["1"]);
+
+        // ------ ACT ------
+        let mut receiver = replay(&mut replay_buffer, 5);
+        let results = replay_buffer.send(&MessageBody::new(None, "news", "2").unwrap());
+
+        // ------ ASSERT ------
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_ok());
+        assert_eq!(replay_buffer.last_id(), 6);
+        assert_eq!(
+            received_messages(&mut receiver),
+            [gap("5"), message(Some(6), "news", "2")]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use super::EventStream;
+use actix_web::http::header::{CacheControl, CacheDirective, ContentType, CONTENT_ENCODING};
+use actix_web::HttpResponse;
+
+// ------ into_response ------
+
+/// The streaming response with the headers required to deliver events without buffering.
+/// `X-Accel-Buffering: no` disables buffering in nginx and `Content-Encoding: identity`
+/// stops the `Compress` middleware from compressing the stream.
+/// The first `retry` is sent by the connection, see [SSEConfig::initial_retry](super::SSEConfig::initial_retry).
+pub fn into_response(event_stream: EventStream) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(ContentType(mime::TEXT_EVENT_STREAM))
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .insert_header((CONTENT_ENCODING, "identity"))
+        .streaming(event_stream)
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sse::message::message;
+    use crate::sse::{ShareableSSEMethods, SSE};
+    use actix_web::body::MessageBody;
+    use actix_web::http::header::ACCEPT_ENCODING;
+    use actix_web::middleware::Compress;
+    use actix_web::rt as actix_rt;
+    use actix_web::web::Bytes;
+    use actix_web::{test, web, App};
+    use std::future::poll_fn;
+    use std::pin::pin;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[actix_rt::test]
+    async fn test_response_disables_buffering() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let (_, _, event_stream) = sse.new_connection(None, None).unwrap();
+
+        // ------ ACT ------
+        let response = into_response(event_stream);
+
+        // ------ ASSERT ------
+        let headers = response.headers();
+        assert_eq!(headers.get("x-accel-buffering").unwrap(), "no");
+        assert_eq!(headers.get("content-encoding").unwrap(), "identity");
+    }
+
+    #[actix_rt::test]
+    async fn test_compress_middleware_does_not_buffer_events() {
+        // ------ ARRANGE ------
+        let sse = SSE::start();
+        let app = test::init_service(App::new().wrap(Compress::default()).route("/sse", {
+            let sse = sse.clone();
+            web::get().to(move || {
+                let (_, _, event_stream) = sse.new_connection(None, None).unwrap();
+                async move { into_response(event_stream) }
+            })
+        }))
+        .await;
+        let request = test::TestRequest::get()
+            .uri("/sse")
+            .insert_header((ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+
+        // ------ ACT ------
+        sse.broadcast("news", "1").unwrap();
+
+        // ------ ASSERT ------
+        assert_eq!(
+            response.headers().get("content-encoding").unwrap(),
+            "identity"
+        );
+        // A compressing encoder would wait for more data before emitting the frames.
+        // The `id` line of a tracked event is written as its own frame.
+        let mut body = pin!(response.into_body());
+        let mut chunks = Vec::new();
+        for _ in 0..3 {
+            let chunk = timeout(
+                Duration::from_secs(1),
+                poll_fn(|cx| body.as_mut().poll_next(cx)),
+            )
+            .await
+            .expect("the event has been buffered")
+            .unwrap();
+            chunks.push(chunk.ok());
+        }
+        assert_eq!(
+            chunks,
+            [
+                Some(message(None, "epoch", &sse.epoch().to_string())),
+                Some(Bytes::from_static(b"id: 1\n")),
+                Some(message(None, "news", "1"))
+            ]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use moonlight::SessionId;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Weak};
+use tokio::time::{Duration, Instant};
+
+// ------ ScheduledSend ------
+
+/// Returned by [send_after](super::ShareableSSEMethods::send_after).
+/// Dropping the handle doesn't cancel the send.
+pub struct ScheduledSend {
+    id: u64,
+    schedule: Weak<Schedule>,
+}
+
+impl ScheduledSend {
+    // The send has been dropped already, e.g. because its session wasn't connected.
+    pub(crate) fn finished() -> Self {
+        Self {
+            id: 0,
+            schedule: Weak::new(),
+        }
+    }
+
+    /// Returns `false` when the event has been already sent
+    /// or dropped together with its session.
+    pub fn cancel(&self) -> bool {
+        self.schedule
+            .upgrade()
+            .is_some_and(|schedule| schedule.cancel(self.id))
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.schedule
+            .upgrade()
+            .is_some_and(|schedule| schedule.contains(self.id))
+    }
+}
+
+// ------ Schedule ------
+
+// Delayed sends ordered by their deadlines, the remover sends the due ones on every tick.
+#[derive(Default)]
+pub(crate) struct Schedule(Mutex<State>);
+
+#[derive(Default)]
+struct State {
+    next_id: u64,
+    queue: BTreeMap<(Instant, u64), ScheduledEvent>,
+    deadlines: HashMap<u64, Instant>,
+}
+
+struct ScheduledEvent {
+    session_id: SessionId,
+    event: String,
+    data: String,
+}
+
+impl Schedule {
+    pub(crate) fn insert(
+        self: &Arc<Self>,
+        session_id: SessionId,
+        delay: Duration,
+        event: &str,
+        data: &str,
+    ) -> ScheduledSend {
+        let deadline = Instant::now() + delay;
+        let mut state = self.0.lock();
+        state.next_id += 1;
+        let id = state.next_id;
+        state.queue.insert(
+            (deadline, id),
+            ScheduledEvent {
+                session_id,
+                event: event.to_owned(),
+                data: data.to_owned(),
+            },
+        );
+        state.deadlines.insert(id, deadline);
+        ScheduledSend {
+            id,
+            schedule: Arc::downgrade(self),
+        }
+    }
+
+    fn cancel(&self, id: u64) -> bool {
+        let mut state = self.0.lock();
+        let Some(deadline) = state.deadlines.remove(&id) else {
+            return false;
+        };
+        state.queue.remove(&(deadline, id));
+        true
+    }
+
+    fn contains(&self, id: u64) -> bool {
+        self.0.lock().deadlines.contains_key(&id)
+    }
+
+    // Returns the sessions, events and data of the due sends, the earliest first.
+    pub(crate) fn due(&self) -> Vec<(SessionId, String, String)> {
+        let mut state = self.0.lock();
+        // Sends with the deadline `now` are due too.
+        let not_due = state.queue.split_off(&(Instant::now(), u64::MAX));
+        let due = std::mem::replace(&mut state.queue, not_due);
+        due.into_iter()
+            .map(|((_, id), scheduled_event)| {
+                state.deadlines.remove(&id);
+                (
+                    scheduled_event.session_id,
+                    scheduled_event.event,
+                    scheduled_event.data,
+                )
+            })
+            .collect()
+    }
+
+    pub(crate) fn remove_session(&self, session_id: &SessionId) {
+        let state = &mut *self.0.lock();
+        state.queue.retain(|(_, id), scheduled_event| {
+            let keep = scheduled_event.session_id != *session_id;
+            if !keep {
+                state.deadlines.remove(id);
+            }
+            keep
+        });
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut state = self.0.lock();
+        state.queue.clear();
+        state.deadlines.clear();
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_and_cancelled_sends() {
+        // ------ ARRANGE ------
+        let schedule = Arc::<Schedule>::default();
+        let (session_id, removed_session_id) = (SessionId::new(), SessionId::new());
+        let reminder = schedule.insert(session_id, Duration::ZERO, "reminder", "1");
+        let cancelled = schedule.insert(session_id, Duration::ZERO, "reminder", "2");
+        let removed = schedule.insert(removed_session_id, Duration::ZERO, "reminder", "3");
+        let later = schedule.insert(session_id, Duration::from_secs(60), "logout", "");
+
+        // ------ ACT ------
+        let cancels = [cancelled.cancel(), cancelled.cancel()];
+        schedule.remove_session(&removed_session_id);
+        let due = schedule.due();
+
+        // ------ ASSERT ------
+        assert_eq!(cancels, [true, false]);
+        assert_eq!(due, [(session_id, "reminder".to_owned(), "1".to_owned())]);
+        assert!(!reminder.is_pending());
+        assert!(!removed.is_pending());
+        assert!(later.is_pending());
+        assert!(!ScheduledSend::finished().cancel());
+    }
+}
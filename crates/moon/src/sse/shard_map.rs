@@ -0,0 +1,251 @@
+use parking_lot::RwLock;
+use std::borrow::Borrow;
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+use std::thread;
+
+// ------ ShardMap ------
+
+// Concurrent map split into independently locked shards.
+// Callbacks run while their shard is locked, so they mustn't access the same map.
+pub(crate) struct ShardMap<K, V> {
+    hasher: RandomState,
+    shards: Box<[RwLock<HashMap<K, V>>]>,
+}
+
+impl<K: Hash + Eq, V> Default for ShardMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq, V> ShardMap<K, V> {
+    pub(crate) fn new() -> Self {
+        let shard_count = thread::available_parallelism()
+            .map_or(1, |parallelism| parallelism.get() * 4)
+            .next_power_of_two();
+        Self {
+            hasher: RandomState::new(),
+            shards: (0..shard_count).map(|_| RwLock::default()).collect(),
+        }
+    }
+
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> &RwLock<HashMap<K, V>> {
+        let index = self.hasher.hash_one(key) as usize & (self.shards.len() - 1);
+        &self.shards[index]
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().len()).sum()
+    }
+
+    pub(crate) fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).read().contains_key(key)
+    }
+
+    pub(crate) fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.shard(key).read().get(key).cloned()
+    }
+
+    pub(crate) fn get_with<Q, R>(&self, key: &Q, f: impl FnOnce(&V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).read().get(key).map(f)
+    }
+
+    pub(crate) fn insert(&self, key: K, value: V) -> Option<V> {
+        self.shard(&key).write().insert(key, value)
+    }
+
+    pub(crate) fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).write().remove(key)
+    }
+
+    pub(crate) fn upsert(&self, key: K, insert: impl FnOnce() -> V, update: impl FnOnce(&mut V)) {
+        match self.shard(&key).write().entry(key) {
+            Entry::Occupied(mut entry) => update(entry.get_mut()),
+            Entry::Vacant(entry) => {
+                entry.insert(insert());
+            }
+        }
+    }
+
+    // The entry is removed when `f` returns `None`.
+    pub(crate) fn alter(&self, key: K, f: impl FnOnce(Option<V>) -> Option<V>) {
+        let mut shard = self.shard(&key).write();
+        let value = shard.remove(&key);
+        if let Some(value) = f(value) {
+            shard.insert(key, value);
+        }
+    }
+
+    pub(crate) fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    // Returns the removed entries.
+    pub(crate) fn retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) -> Vec<(K, V)>
+    where
+        K: Clone,
+    {
+        (0..self.shard_count())
+            .flat_map(|index| self.retain_shard(index, &mut f))
+            .collect()
+    }
+
+    // Like `retain`, but locks only the shard with the given index.
+    pub(crate) fn retain_shard(
+        &self,
+        index: usize,
+        mut f: impl FnMut(&K, &mut V) -> bool,
+    ) -> Vec<(K, V)>
+    where
+        K: Clone,
+    {
+        let mut shard = self.shards[index].write();
+        let removed_keys = shard
+            .iter_mut()
+            .filter_map(|(key, value)| (!f(key, value)).then(|| key.clone()))
+            .collect::<Vec<_>>();
+        removed_keys
+            .into_iter()
+            .filter_map(|key| shard.remove(&key).map(|value| (key, value)))
+            .collect()
+    }
+
+    pub(crate) fn clear(&self) -> Vec<(K, V)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| mem::take(&mut *shard.write()))
+            .collect()
+    }
+
+    pub(crate) fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        for shard in self.shards.iter() {
+            for (key, value) in shard.read().iter() {
+                f(key, value);
+            }
+        }
+    }
+
+    pub(crate) fn keys(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        let mut keys = Vec::with_capacity(self.len());
+        self.for_each(|key, _| keys.push(key.clone()));
+        keys
+    }
+
+    // Like `values`, but reads only the shard with the given index.
+    pub(crate) fn shard_values(&self, index: usize) -> Vec<V>
+    where
+        V: Clone,
+    {
+        self.shards[index].read().values().cloned().collect()
+    }
+
+    // Allows to work with the values without holding any lock.
+    pub(crate) fn values(&self) -> Vec<V>
+    where
+        V: Clone,
+    {
+        let mut values = Vec::with_capacity(self.len());
+        self.for_each(|_, value| values.push(value.clone()));
+        values
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+
+    #[test]
+    fn test_concurrent_insert_remove_retain() {
+        // ------ ARRANGE ------
+        let map = ShardMap::<u32, u32>::new();
+        let barrier = Barrier::new(3);
+
+        // ------ ACT ------
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                barrier.wait();
+                for key in 0..10_000 {
+                    map.insert(key, key);
+                }
+            });
+            scope.spawn(|| {
+                barrier.wait();
+                for key in (0..10_000).step_by(2) {
+                    while map.remove(&key).is_none() {
+                        thread::yield_now();
+                    }
+                }
+            });
+            scope.spawn(|| {
+                barrier.wait();
+                for _ in 0..100 {
+                    map.retain(|key, value| {
+                        assert_eq!(key, value);
+                        true
+                    });
+                    let _ = map.values();
+                }
+            });
+        });
+
+        // ------ ASSERT ------
+        let mut keys = map.keys();
+        keys.sort_unstable();
+        assert_eq!(keys, (1..10_000).step_by(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_concurrent_upsert_alter() {
+        // ------ ARRANGE ------
+        let map = ShardMap::<&str, Vec<u32>>::new();
+
+        // ------ ACT ------
+        thread::scope(|scope| {
+            for thread_index in 0..4 {
+                let map = &map;
+                scope.spawn(move || {
+                    for value in 0..1_000 {
+                        let value = thread_index * 1_000 + value;
+                        map.upsert("key", || vec![value], |values| values.push(value));
+                    }
+                });
+            }
+        });
+        let mut removed = Vec::new();
+        map.alter("key", |values| {
+            removed = values?;
+            None
+        });
+
+        // ------ ASSERT ------
+        removed.sort_unstable();
+        assert_eq!(removed, (0..4_000).collect::<Vec<_>>());
+        assert_eq!(map.len(), 0);
+    }
+}
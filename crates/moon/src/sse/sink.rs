@@ -0,0 +1,76 @@
+use super::SendError;
+use actix_web::web::Bytes;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time::{sleep, Duration};
+
+// ------ FrameSink ------
+
+/// The transport of a connection created by
+/// [new_connection_with_sink](super::ShareableSSEMethods::new_connection_with_sink),
+/// e.g. a channel feeding the response body of another HTTP server.
+/// All messages of the connection, including keep-alives, are passed to the same sink.
+pub trait FrameSink: Send + Sync {
+    fn try_send(&self, frame: Bytes) -> Result<(), SinkError>;
+
+    /// The connection is removed by the connection remover when its sink is closed.
+    fn is_closed(&self) -> bool;
+
+    /// Resolves when the sink is closed, used by [Connection::closed](super::Connection::closed).
+    /// The default implementation checks [is_closed](Self::is_closed) every 100 ms.
+    fn closed(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(async move {
+            while !self.is_closed() {
+                sleep(Duration::from_millis(100)).await;
+            }
+        })
+    }
+
+    /// Called when `SSE` closes the connection, the response should end after the sent frames.
+    fn close(&self) {}
+}
+
+impl FrameSink for UnboundedSender<Bytes> {
+    fn try_send(&self, frame: Bytes) -> Result<(), SinkError> {
+        self.send(frame).map_err(|error| SinkError::Closed(error.0))
+    }
+
+    fn is_closed(&self) -> bool {
+        UnboundedSender::is_closed(self)
+    }
+
+    fn closed(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(UnboundedSender::closed(self))
+    }
+}
+
+// ------ SinkError ------
+
+#[derive(Debug)]
+pub enum SinkError {
+    Closed(Bytes),
+    Full(Bytes),
+}
+
+impl From<SinkError> for SendError {
+    fn from(error: SinkError) -> Self {
+        match error {
+            SinkError::Closed(frame) => Self::ConnectionClosed(frame),
+            SinkError::Full(frame) => Self::QueueFull(frame),
+        }
+    }
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Closed(_) => write!(f, "SSE frame sink is closed"),
+            Self::Full(_) => write!(f, "SSE frame sink is full"),
+        }
+    }
+}
+
+impl Error for SinkError {}
@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::time::{Duration, Instant};
+
+// ------ SSEStats ------
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SSEStats {
+    pub active_connections: usize,
+    /// Events accepted by connection queues.
+    pub messages_sent: u64,
+    /// Events refused because the connection was closed or its queue was full.
+    pub send_failures: u64,
+    /// Connections removed because the keep-alive message couldn't be sent.
+    pub connections_removed_by_ping: u64,
+    /// Events over the [RateLimit](super::RateLimit) of their connection.
+    pub rate_limited_messages: u64,
+    /// Events evicted from the queue of a slow connection,
+    /// see [ConnectionKind::DropOldest](super::ConnectionKind::DropOldest).
+    pub dropped_messages: u64,
+    /// The longest [idle_duration](super::Connection::idle_duration) of active connections.
+    pub max_idle_duration: Duration,
+    /// The longest queue of active connections, see [Connection::queued_frames](super::Connection::queued_frames).
+    pub max_queued_frames: usize,
+    /// See [SSE::epoch](super::SSE::epoch).
+    pub epoch: u64,
+}
+
+// ------ Counters ------
+
+#[derive(Default)]
+pub(crate) struct Counters {
+    messages_sent: AtomicU64,
+    send_failures: AtomicU64,
+    connections_removed_by_ping: AtomicU64,
+    rate_limited_messages: AtomicU64,
+    dropped_messages: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn message_sent(&self, success: bool) {
+        let counter = if success {
+            &self.messages_sent
+        } else {
+            &self.send_failures
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn connection_removed_by_ping(&self) {
+        self.connections_removed_by_ping
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn message_rate_limited(&self) {
+        self.rate_limited_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn message_dropped(&self) {
+        self.dropped_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(
+        &self,
+        active_connections: usize,
+        max_idle_duration: Duration,
+        max_queued_frames: usize,
+    ) -> SSEStats {
+        SSEStats {
+            active_connections,
+            max_idle_duration,
+            max_queued_frames,
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            send_failures: self.send_failures.load(Ordering::Relaxed),
+            connections_removed_by_ping: self.connections_removed_by_ping.load(Ordering::Relaxed),
+            rate_limited_messages: self.rate_limited_messages.load(Ordering::Relaxed),
+            dropped_messages: self.dropped_messages.load(Ordering::Relaxed),
+            // set by `SSE::stats`
+            epoch: 0,
+        }
+    }
+}
+
+// ------ ConnectionActivity ------
+
+// Shared by the `Connection` and its `EventStream`.
+pub(crate) struct ConnectionActivity {
+    connected_at: Instant,
+    // nanoseconds since `connected_at` + 1, `0` when no message has been written yet
+    last_message_written: AtomicU64,
+    rate_limited_messages: AtomicU64,
+    // nanoseconds since `connected_at`
+    last_client_activity: AtomicU64,
+}
+
+impl Default for ConnectionActivity {
+    fn default() -> Self {
+        Self {
+            connected_at: Instant::now(),
+            last_message_written: AtomicU64::new(0),
+            rate_limited_messages: AtomicU64::new(0),
+            last_client_activity: AtomicU64::new(0),
+        }
+    }
+}
+
+impl ConnectionActivity {
+    pub(crate) fn connected_at(&self) -> Instant {
+        self.connected_at
+    }
+
+    pub(crate) fn message_written(&self) {
+        self.last_message_written
+            .store(self.elapsed_nanos().saturating_add(1), Ordering::Relaxed);
+    }
+
+    pub(crate) fn client_active(&self) {
+        self.last_client_activity
+            .store(self.elapsed_nanos(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn idle_duration(&self) -> Duration {
+        let last_client_activity =
+            Duration::from_nanos(self.last_client_activity.load(Ordering::Relaxed));
+        self.connected_at
+            .elapsed()
+            .saturating_sub(last_client_activity)
+    }
+
+    fn elapsed_nanos(&self) -> u64 {
+        u64::try_from(self.connected_at.elapsed().as_nanos()).unwrap_or(u64::MAX - 1)
+    }
+
+    pub(crate) fn last_message_written_at(&self) -> Option<Instant> {
+        match self.last_message_written.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(self.connected_at + Duration::from_nanos(nanos - 1)),
+        }
+    }
+
+    pub(crate) fn message_rate_limited(&self) {
+        self.rate_limited_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn rate_limited_messages(&self) -> u64 {
+        self.rate_limited_messages.load(Ordering::Relaxed)
+    }
+}
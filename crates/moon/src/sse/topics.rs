@@ -0,0 +1,79 @@
+use super::shard_map::ShardMap;
+use moonlight::SessionId;
+use std::collections::{BTreeSet, HashSet};
+
+// ------ Topics ------
+
+// Topics are subscribed by sessions, so all tabs of the session receive published messages
+// and the session keeps its topics when the client reconnects.
+#[derive(Default)]
+pub(crate) struct Topics {
+    subscribers: ShardMap<String, HashSet<SessionId>>,
+    session_topics: ShardMap<SessionId, BTreeSet<String>>,
+}
+
+impl Topics {
+    pub(crate) fn subscribe(&self, topic: &str, session_id: SessionId) {
+        let mut subscribed = true;
+        self.session_topics.upsert(
+            session_id,
+            || BTreeSet::from([topic.to_owned()]),
+            |topics| subscribed = topics.insert(topic.to_owned()),
+        );
+        if !subscribed {
+            return;
+        }
+        self.subscribers.upsert(
+            topic.to_owned(),
+            || HashSet::from([session_id]),
+            |subscribers| {
+                subscribers.insert(session_id);
+            },
+        );
+    }
+
+    pub(crate) fn unsubscribe(&self, topic: &str, session_id: &SessionId) {
+        let mut unsubscribed = false;
+        self.session_topics.alter(*session_id, |topics| {
+            let mut topics = topics?;
+            unsubscribed = topics.remove(topic);
+            (!topics.is_empty()).then_some(topics)
+        });
+        if unsubscribed {
+            self.remove_subscriber(topic, session_id);
+        }
+    }
+
+    pub(crate) fn unsubscribe_all(&self, session_id: &SessionId) {
+        let topics = self.session_topics.remove(session_id).unwrap_or_default();
+        for topic in topics {
+            self.remove_subscriber(&topic, session_id);
+        }
+    }
+
+    pub(crate) fn subscribers(&self, topic: &str) -> Vec<SessionId> {
+        self.subscribers
+            .get_with(topic, |subscribers| subscribers.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn session_topics(&self, session_id: &SessionId) -> Vec<String> {
+        self.session_topics
+            .get_with(session_id, |topics| topics.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn subscriber_count(&self, topic: &str) -> usize {
+        self.subscribers
+            .get_with(topic, HashSet::len)
+            .unwrap_or_default()
+    }
+
+    fn remove_subscriber(&self, topic: &str, session_id: &SessionId) {
+        self.subscribers.alter(topic.to_owned(), |subscribers| {
+            let mut subscribers = subscribers?;
+            subscribers.remove(session_id);
+            (!subscribers.is_empty()).then_some(subscribers)
+        });
+    }
+}
@@ -25,5 +25,8 @@ pub use down_msg_transporter::{DownMsgTransporterForDe, DownMsgTransporterForSer
 mod entity_id;
 pub use entity_id::EntityId;
 
+mod protocol_event;
+pub use protocol_event::{ProtocolEvent, UnknownProtocolEvent};
+
 mod session_id;
 pub use session_id::SessionId;
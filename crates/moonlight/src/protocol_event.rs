@@ -0,0 +1,119 @@
+use std::{error::Error, fmt, str::FromStr};
+
+// ------ ProtocolEvent ------
+
+/// Events sent by Moon itself and handled by Zoon, user events can't use their names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProtocolEvent {
+    DownMsg,
+    ConnectionId,
+    Reload,
+    Gap,
+    Chunk,
+    ChunkEnd,
+    SessionReplaced,
+    ServerRestarting,
+    PresenceJoined,
+    PresenceLeft,
+    Epoch,
+    StateReset,
+}
+
+impl ProtocolEvent {
+    pub const ALL: [Self; 12] = [
+        Self::DownMsg,
+        Self::ConnectionId,
+        Self::Reload,
+        Self::Gap,
+        Self::Chunk,
+        Self::ChunkEnd,
+        Self::SessionReplaced,
+        Self::ServerRestarting,
+        Self::PresenceJoined,
+        Self::PresenceLeft,
+        Self::Epoch,
+        Self::StateReset,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::DownMsg => "down_msg",
+            Self::ConnectionId => "connection_id",
+            Self::Reload => "reload",
+            Self::Gap => "gap",
+            Self::Chunk => "chunk",
+            Self::ChunkEnd => "chunk_end",
+            Self::SessionReplaced => "session_replaced",
+            Self::ServerRestarting => "server_restarting",
+            Self::PresenceJoined => "presence_joined",
+            Self::PresenceLeft => "presence_left",
+            Self::Epoch => "epoch",
+            Self::StateReset => "state_reset",
+        }
+    }
+}
+
+impl fmt::Display for ProtocolEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ProtocolEvent {
+    type Err = UnknownProtocolEvent;
+
+    fn from_str(event: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|protocol_event| protocol_event.as_str() == event)
+            .ok_or(UnknownProtocolEvent)
+    }
+}
+
+// ------ UnknownProtocolEvent ------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownProtocolEvent;
+
+impl fmt::Display for UnknownProtocolEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown protocol event")
+    }
+}
+
+impl Error for UnknownProtocolEvent {}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire_names() {
+        // ------ ARRANGE ------
+        // Zoon apps already deployed expect exactly these names.
+        let wire_names = [
+            "down_msg",
+            "connection_id",
+            "reload",
+            "gap",
+            "chunk",
+            "chunk_end",
+            "session_replaced",
+            "server_restarting",
+            "presence_joined",
+            "presence_left",
+            "epoch",
+            "state_reset",
+        ];
+
+        // ------ ACT ------
+        let parsed_events = wire_names.map(str::parse::<ProtocolEvent>);
+
+        // ------ ASSERT ------
+        assert_eq!(ProtocolEvent::ALL.map(ProtocolEvent::as_str), wire_names);
+        assert_eq!(parsed_events, ProtocolEvent::ALL.map(Ok));
+        assert_eq!("ping".parse::<ProtocolEvent>(), Err(UnknownProtocolEvent));
+    }
+}
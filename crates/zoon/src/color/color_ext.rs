@@ -0,0 +1,434 @@
+use crate::cssparser::{Parser, ParserInput};
+use crate::*;
+use std::{error::Error, fmt};
+
+// ------ ColorExt ------
+
+/// Fallible color constructors for runtime values like design tokens
+/// and adjustments for hover or pressed states.
+/// Use the [color!] macro for literals, it's checked during compilation.
+///
+/// Adjusted and mixed colors are [Oklch], so the same amount looks the same for all hues.
+/// Colors in other spaces than sRGB (`rgb`, `hsl`, `hwb`) and Oklab / Oklch
+/// are returned unchanged, only their alpha can be adjusted.
+pub trait ColorExt: Sized {
+    /// Parses `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let translucent_white = Color::hex("#ffffff80").unwrap_throw();
+    /// ```
+    fn hex(hex: &str) -> Result<Self, ColorParseError>;
+
+    /// Parses any CSS color, e.g. `rgb(26 115 232)`, `hsl(214 82% 51% / 50%)` or `teal`.
+    fn parse_css(css: &str) -> Result<Self, ColorParseError>;
+
+    fn from_rgb_u8(red: u8, green: u8, blue: u8) -> Self;
+
+    /// Add `amount` to the Oklch lightness, the result is clamped to `0.0..=1.0`.
+    /// # Example
+    /// ```no_run
+    /// use zoon::{named_color::*, *};
+    ///
+    /// let (hovered, hovered_signal) = Mutable::new_and_signal(false);
+    /// let button = Button::new()
+    ///     .s(Background::new().color_signal(
+    ///         hovered_signal.map_bool(|| BLUE_5.into_color().darken(0.08), || BLUE_5.into_color()),
+    ///     ))
+    ///     .on_hovered_change(move |is_hovered| hovered.set_neq(is_hovered))
+    ///     .label("Hover me");
+    /// ```
+    fn lighten(self, amount: impl Into<f64>) -> Self;
+
+    fn darken(self, amount: impl Into<f64>) -> Self;
+
+    /// Multiply the Oklch chroma by `1 + amount`, negative amounts desaturate the color.
+    fn saturate(self, amount: impl Into<f64>) -> Self;
+
+    /// Replace the alpha, it's clamped to `0.0..=1.0`.
+    fn with_alpha(self, alpha: impl Into<f64>) -> Self;
+
+    /// Interpolate between `from` (`ratio` `0.0`) and `to` (`ratio` `1.0`) in Oklab
+    /// with premultiplied alpha like CSS `color-mix`.
+    fn mix(from: impl IntoColor, to: impl IntoColor, ratio: impl Into<f64>) -> Self;
+
+    /// Fade to every new color from the signal with [mix](Self::mix) frames.
+    /// A new color in the middle of a transition starts from the currently displayed color.
+    /// The animation loop runs only during transitions, the first color isn't animated.
+    /// # Example
+    /// ```no_run
+    /// use zoon::{named_color::*, *};
+    ///
+    /// let (dark, dark_signal) = Mutable::new_and_signal(false);
+    /// let element = El::new().s(Background::new().color_signal(Color::signal_transition(
+    ///     dark_signal.map_bool(|| GRAY_9, || GRAY_0),
+    ///     Duration::milliseconds(300),
+    /// )));
+    /// ```
+    fn signal_transition(
+        color: impl Signal<Item = impl IntoColor> + Unpin + 'static,
+        duration: Duration,
+    ) -> impl Signal<Item = Self>;
+}
+
+impl ColorExt for Color {
+    fn hex(hex: &str) -> Result<Self, ColorParseError> {
+        let invalid_hex = || ColorParseError::InvalidHex(hex.to_owned());
+        let digits = hex.strip_prefix('#').ok_or_else(invalid_hex)?;
+        if !digits.is_ascii() {
+            Err(invalid_hex())?;
+        }
+        let channels = match digits.len() {
+            3 | 4 => digits
+                .chars()
+                .map(|digit| digit.to_digit(16).map(|digit| digit as u8 * 17))
+                .collect::<Option<Vec<_>>>(),
+            6 | 8 => (0..digits.len())
+                .step_by(2)
+                .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).ok())
+                .collect::<Option<Vec<_>>>(),
+            _ => None,
+        }
+        .ok_or_else(invalid_hex)?;
+        let alpha = channels.get(3).map_or(1., |alpha| f32::from(*alpha) / 255.);
+        Ok(Rgba::new(channels[0], channels[1], channels[2], alpha).into_color())
+    }
+
+    fn parse_css(css: &str) -> Result<Self, ColorParseError> {
+        Parser::new(&mut ParserInput::new(css))
+            .parse_entirely(Color::parse)
+            .map_err(|_| ColorParseError::InvalidCss(css.to_owned()))
+    }
+
+    fn from_rgb_u8(red: u8, green: u8, blue: u8) -> Self {
+        Rgba::new(red, green, blue, 1.).into_color()
+    }
+
+    fn lighten(self, amount: impl Into<f64>) -> Self {
+        let amount = amount.into() as f32;
+        map_oklch(self, |[lightness, chroma, hue]| {
+            [(lightness + amount).clamp(0., 1.), chroma, hue]
+        })
+    }
+
+    fn darken(self, amount: impl Into<f64>) -> Self {
+        self.lighten(-amount.into())
+    }
+
+    fn saturate(self, amount: impl Into<f64>) -> Self {
+        let factor = (1. + amount.into() as f32).max(0.);
+        map_oklch(self, |[lightness, chroma, hue]| {
+            [lightness, chroma * factor, hue]
+        })
+    }
+
+    fn with_alpha(self, alpha: impl Into<f64>) -> Self {
+        let alpha = alpha.into() as f32;
+        let alpha = if alpha.is_nan() {
+            1.
+        } else {
+            alpha.clamp(0., 1.)
+        };
+        let mut color = self;
+        let color_alpha = match &mut color {
+            Color::Rgba(rgba) => {
+                rgba.alpha = alpha;
+                None
+            }
+            Color::Hsl(hsl) => Some(&mut hsl.alpha),
+            Color::Hwb(hwb) => Some(&mut hwb.alpha),
+            Color::Lab(lab) => Some(&mut lab.alpha),
+            Color::Lch(lch) => Some(&mut lch.alpha),
+            Color::Oklab(oklab) => Some(&mut oklab.alpha),
+            Color::Oklch(oklch) => Some(&mut oklch.alpha),
+            Color::ColorFunction(function) => Some(&mut function.alpha),
+            _ => None,
+        };
+        if let Some(color_alpha) = color_alpha {
+            *color_alpha = Some(alpha);
+        }
+        color
+    }
+
+    fn mix(from: impl IntoColor, to: impl IntoColor, ratio: impl Into<f64>) -> Self {
+        let (from, to) = (from.into_color(), to.into_color());
+        let ratio = (ratio.into() as f32).clamp(0., 1.);
+        let (Some((from, from_alpha)), Some((to, to_alpha))) = (to_oklab(from), to_oklab(to))
+        else {
+            return if ratio < 0.5 { from } else { to };
+        };
+        let alpha = from_alpha + (to_alpha - from_alpha) * ratio;
+        if alpha == 0. {
+            return oklab_to_oklch([0.; 3], 0.);
+        }
+        let mut mixed = [0.; 3];
+        for index in 0..3 {
+            let from = from[index] * from_alpha;
+            let to = to[index] * to_alpha;
+            mixed[index] = (from + (to - from) * ratio) / alpha;
+        }
+        oklab_to_oklch(mixed, alpha)
+    }
+
+    fn signal_transition(
+        color: impl Signal<Item = impl IntoColor> + Unpin + 'static,
+        duration: Duration,
+    ) -> impl Signal<Item = Self> {
+        let oscillator = Oscillator::new(duration);
+        let displayed_color = Mutable::new(None::<Color>);
+        color.switch(move |target| {
+            let target = target.into_color();
+            let from = match displayed_color.get() {
+                Some(displayed) => {
+                    oscillator.jump_to(0);
+                    oscillator.go_to(1);
+                    displayed
+                }
+                None => {
+                    oscillator.jump_to(1);
+                    target
+                }
+            };
+            let displayed_color = displayed_color.clone();
+            oscillator.signal().map(move |ratio| {
+                let color = if ratio >= 1. {
+                    target
+                } else {
+                    Color::mix(from, target, ratio)
+                };
+                displayed_color.set(Some(color));
+                color
+            })
+        })
+    }
+}
+
+// ------ conversions ------
+
+// https://bottosson.github.io/posts/oklab/
+
+fn map_oklch(color: Color, map: impl FnOnce([f32; 3]) -> [f32; 3]) -> Color {
+    let (lch, alpha) = match color {
+        Color::Oklch(oklch) => (
+            [oklch.lightness, oklch.chroma, oklch.hue].map(Option::unwrap_or_default),
+            oklch.alpha.unwrap_or(1.),
+        ),
+        _ => match to_oklab(color) {
+            Some((oklab, alpha)) => (oklab_to_lch(oklab), alpha),
+            None => return color,
+        },
+    };
+    let [lightness, chroma, hue] = map(lch);
+    Oklch::new(Some(lightness), Some(chroma), Some(hue), Some(alpha)).into_color()
+}
+
+// Returns `[lightness, a, b]` and alpha.
+fn to_oklab(color: Color) -> Option<([f32; 3], f32)> {
+    let (rgb, alpha) = match color {
+        Color::Rgba(rgba) => (
+            [rgba.red, rgba.green, rgba.blue].map(|channel| f32::from(channel) / 255.),
+            rgba.alpha,
+        ),
+        Color::Hsl(hsl) => (
+            hsl_to_rgb(
+                hsl.hue.unwrap_or_default(),
+                hsl.saturation.unwrap_or_default(),
+                hsl.lightness.unwrap_or_default(),
+            ),
+            hsl.alpha.unwrap_or(1.),
+        ),
+        Color::Hwb(hwb) => {
+            let (whiteness, blackness) = (
+                hwb.whiteness.unwrap_or_default(),
+                hwb.blackness.unwrap_or_default(),
+            );
+            let rgb = if whiteness + blackness >= 1. {
+                [whiteness / (whiteness + blackness); 3]
+            } else {
+                hsl_to_rgb(hwb.hue.unwrap_or_default(), 1., 0.5)
+                    .map(|channel| channel * (1. - whiteness - blackness) + whiteness)
+            };
+            (rgb, hwb.alpha.unwrap_or(1.))
+        }
+        Color::Oklab(oklab) => {
+            let oklab_values = [oklab.lightness, oklab.a, oklab.b].map(Option::unwrap_or_default);
+            return Some((oklab_values, oklab.alpha.unwrap_or(1.)));
+        }
+        Color::Oklch(oklch) => {
+            let lightness = oklch.lightness.unwrap_or_default();
+            let chroma = oklch.chroma.unwrap_or_default();
+            let hue = oklch.hue.unwrap_or_default().to_radians();
+            let oklab_values = [lightness, chroma * hue.cos(), chroma * hue.sin()];
+            return Some((oklab_values, oklch.alpha.unwrap_or(1.)));
+        }
+        _ => return None,
+    };
+    Some((srgb_to_oklab(rgb), alpha))
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let hue = hue.rem_euclid(360.);
+    let channel = |offset: f32| {
+        let k = (offset + hue / 30.) % 12.;
+        let a = saturation * lightness.min(1. - lightness);
+        lightness - a * (k - 3.).min(9. - k).clamp(-1., 1.)
+    };
+    [channel(0.), channel(8.), channel(4.)]
+}
+
+fn srgb_to_oklab(rgb: [f32; 3]) -> [f32; 3] {
+    let [red, green, blue] = rgb.map(|channel| {
+        if channel <= 0.04045 {
+            channel / 12.92
+        } else {
+            ((channel + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    let l = (0.41222147 * red + 0.53633254 * green + 0.051445993 * blue).cbrt();
+    let m = (0.2119035 * red + 0.6806995 * green + 0.10739696 * blue).cbrt();
+    let s = (0.08830246 * red + 0.28171884 * green + 0.6299787 * blue).cbrt();
+    [
+        0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+    ]
+}
+
+fn oklab_to_lch([lightness, a, b]: [f32; 3]) -> [f32; 3] {
+    [
+        lightness,
+        a.hypot(b),
+        b.atan2(a).to_degrees().rem_euclid(360.),
+    ]
+}
+
+fn oklab_to_oklch(oklab: [f32; 3], alpha: f32) -> Color {
+    let [lightness, chroma, hue] = oklab_to_lch(oklab);
+    Oklch::new(Some(lightness), Some(chroma), Some(hue), Some(alpha)).into_color()
+}
+
+// ------ ColorParseError ------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorParseError {
+    InvalidHex(String),
+    InvalidCss(String),
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHex(hex) => {
+                write!(f, "'{}' is not a hex color with 3, 4, 6 or 8 digits", hex)
+            }
+            Self::InvalidCss(css) => write!(f, "'{}' is not a valid CSS color", css),
+        }
+    }
+}
+
+impl Error for ColorParseError {}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba(color: Color) -> (u8, u8, u8, f32) {
+        match color {
+            Color::Rgba(rgba) => (rgba.red, rgba.green, rgba.blue, rgba.alpha),
+            _ => panic!("'{}' isn't an sRGB color", color.to_css_string()),
+        }
+    }
+
+    // Deterministic pseudo-random channels (xorshift).
+    fn random_channels(count: usize) -> impl Iterator<Item = [u8; 4]> {
+        let mut state = 0x2545_f491_u32;
+        std::iter::repeat_with(move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state.to_le_bytes()
+        })
+        .take(count)
+    }
+
+    #[test]
+    fn test_hex_forms() {
+        // ------ ACT ------
+        let colors = ["#1a73e8", "#1A73E8", "#ffffff80", "#f80", "#f808"]
+            .map(|hex| rgba(Color::hex(hex).unwrap()));
+
+        // ------ ASSERT ------
+        assert_eq!(colors[0], (26, 115, 232, 1.));
+        assert_eq!(colors[1], colors[0]);
+        assert_eq!(colors[2], (255, 255, 255, 128. / 255.));
+        assert_eq!(colors[3], (255, 136, 0, 1.));
+        assert_eq!(colors[4], (255, 136, 0, 136. / 255.));
+    }
+
+    #[test]
+    fn test_invalid_hex() {
+        for hex in [
+            "1a73e8", "#", "#12345", "#1a73e8f", "#ggg", "#ééé", "#1a73e8 ",
+        ] {
+            assert_eq!(
+                Color::hex(hex),
+                Err(ColorParseError::InvalidHex(hex.to_owned())),
+                "{hex}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_css() {
+        // ------ ACT ------
+        let rgb = Color::parse_css("rgb(26 115 232)").unwrap();
+        let legacy_rgba = Color::parse_css("rgba(26, 115, 232, 0.5)").unwrap();
+        let named = Color::parse_css("teal").unwrap();
+        let hsl = Color::parse_css("hsl(214 82% 51% / 50%)").unwrap();
+
+        // ------ ASSERT ------
+        assert_eq!(rgb, Color::hex("#1a73e8").unwrap());
+        assert_eq!(rgba(legacy_rgba), (26, 115, 232, 0.5));
+        assert_eq!(named, Color::from_rgb_u8(0, 128, 128));
+        assert!(matches!(hsl, Color::Hsl(_)));
+        // `hsl` is serialized as `rgba`
+        assert_eq!(
+            rgba(Color::parse_css(&hsl.to_css_string()).unwrap()),
+            (28, 116, 233, 0.5)
+        );
+        for css in ["rgb(26 115)", "teal blue", "", "#1a73e8;"] {
+            assert_eq!(
+                Color::parse_css(css),
+                Err(ColorParseError::InvalidCss(css.to_owned())),
+                "{css}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        for [red, green, blue, alpha] in random_channels(500) {
+            // ------ ARRANGE ------
+            let hex = crate::format!("#{red:02x}{green:02x}{blue:02x}{alpha:02x}");
+
+            // ------ ACT ------
+            let color = Color::hex(&hex).unwrap();
+            let css = color.to_css_string();
+            let parsed = rgba(Color::parse_css(&css).unwrap());
+
+            // ------ ASSERT ------
+            assert_eq!(
+                (parsed.0, parsed.1, parsed.2),
+                (red, green, blue),
+                "{hex} -> {css}"
+            );
+            assert!(
+                (parsed.3 - f32::from(alpha) / 255.).abs() < 0.002,
+                "{hex} -> {css}"
+            );
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::*;
+use std::sync::OnceLock;
+use web_sys::MediaQueryList;
+
+// ------ ColorScheme ------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+// ------ ColorSchemeState ------
+
+struct ColorSchemeState {
+    system: Mutable<ColorScheme>,
+    app: Mutable<Option<ColorScheme>>,
+    _on_system_change: Option<Closure<dyn Fn()>>,
+}
+
+impl ColorSchemeState {
+    fn new(system: ColorScheme) -> Self {
+        Self {
+            system: Mutable::new(system),
+            app: Mutable::new(None),
+            _on_system_change: None,
+        }
+    }
+
+    // The app scheme takes precedence over the system one.
+    fn signal(&self) -> impl Signal<Item = ColorScheme> {
+        map_ref! {
+            let system = self.system.signal(),
+            let app = self.app.signal() => app.unwrap_or(*system)
+        }
+        .dedupe()
+    }
+
+    fn get(&self) -> ColorScheme {
+        self.app.get().unwrap_or_else(|| self.system.get())
+    }
+}
+
+fn color_scheme_state() -> &'static ColorSchemeState {
+    static STATE: OnceLock<SendWrapper<ColorSchemeState>> = OnceLock::new();
+    STATE.get_or_init(|| {
+        let query = window()
+            .match_media("(prefers-color-scheme: dark)")
+            .ok()
+            .flatten();
+        let mut state = ColorSchemeState::new(system_color_scheme(query.as_ref()));
+        state._on_system_change = query.map(|query| {
+            let system = state.system.clone();
+            let on_change = Closure::new({
+                let query = query.clone();
+                move || system.set_neq(system_color_scheme(Some(&query)))
+            });
+            query
+                .add_event_listener_with_callback("change", on_change.as_ref().unchecked_ref())
+                .unwrap_throw();
+            on_change
+        });
+        SendWrapper::new(state)
+    })
+}
+
+// Browsers without `matchMedia` support are treated as light.
+fn system_color_scheme(query: Option<&MediaQueryList>) -> ColorScheme {
+    match query {
+        Some(query) if query.matches() => ColorScheme::Dark,
+        _ => ColorScheme::Light,
+    }
+}
+
+/// The scheme set by [set_color_scheme] or the OS / browser preference
+/// `prefers-color-scheme`, updated when the preference changes.
+/// # Example
+/// ```no_run
+/// use zoon::*;
+///
+/// let element = El::new().child_signal(color_scheme_signal().map(|scheme| match scheme {
+///     ColorScheme::Light => "Light mode",
+///     ColorScheme::Dark => "Dark mode",
+/// }));
+/// ```
+pub fn color_scheme_signal() -> impl Signal<Item = ColorScheme> {
+    color_scheme_state().signal()
+}
+
+pub fn color_scheme() -> ColorScheme {
+    color_scheme_state().get()
+}
+
+/// Override the OS / browser preference, e.g. by an in-app toggle.
+/// `None` follows the preference again.
+pub fn set_color_scheme(color_scheme: impl Into<Option<ColorScheme>>) {
+    color_scheme_state().app.set_neq(color_scheme.into());
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    fn current(signal: impl Signal<Item = ColorScheme>) -> Option<ColorScheme> {
+        let mut context = Context::from_waker(Waker::noop());
+        match pin!(signal).poll_change(&mut context) {
+            Poll::Ready(color_scheme) => color_scheme,
+            Poll::Pending => None,
+        }
+    }
+
+    #[test]
+    fn test_app_color_scheme_overrides_system() {
+        // ------ ARRANGE ------
+        let state = ColorSchemeState::new(ColorScheme::Dark);
+        let system_scheme = state.get();
+
+        // ------ ACT ------
+        state.app.set_neq(Some(ColorScheme::Light));
+        let app_scheme = (state.get(), current(state.signal()));
+        state.system.set_neq(ColorScheme::Light);
+        state.system.set_neq(ColorScheme::Dark);
+        let app_scheme_after_system_change = (state.get(), current(state.signal()));
+        state.app.set_neq(None);
+        let reset_scheme = (state.get(), current(state.signal()));
+
+        // ------ ASSERT ------
+        assert_eq!(system_scheme, ColorScheme::Dark);
+        assert_eq!(app_scheme, (ColorScheme::Light, Some(ColorScheme::Light)));
+        assert_eq!(
+            app_scheme_after_system_change,
+            (ColorScheme::Light, Some(ColorScheme::Light))
+        );
+        assert_eq!(reset_scheme, (ColorScheme::Dark, Some(ColorScheme::Dark)));
+    }
+}
@@ -10,6 +10,9 @@ use std::{
 };
 use web_sys::{Request, RequestInit, Response};
 
+#[cfg(feature = "serde")]
+mod chunk_reassembler;
+
 mod sse;
 use sse::SSE;
 
@@ -41,7 +44,7 @@ impl<DMsg> Clone for DMsgSenders<DMsg> {
 
 pub struct Connection<UMsg, DMsg> {
     session_id: SessionId,
-    _sse: SSE,
+    sse: SSE,
     auth_token_getter:
         Option<Box<dyn Fn() -> Pin<Box<dyn Future<Output = Option<AuthToken>>>> + Send + Sync>>,
     msg_types: PhantomData<(UMsg, DMsg)>,
@@ -73,7 +76,7 @@ impl<UMsg: Serialize, DMsg: DeserializeOwned + 'static> Connection<UMsg, DMsg> {
         let session_id = SessionId::new();
         Self {
             session_id,
-            _sse: SSE::new(session_id, down_msg_handler),
+            sse: SSE::new(session_id, down_msg_handler),
             auth_token_getter: None,
             msg_types: PhantomData,
             d_msg_senders,
@@ -96,6 +99,13 @@ impl<UMsg: Serialize, DMsg: DeserializeOwned + 'static> Connection<UMsg, DMsg> {
         self
     }
 
+    /// Called with protocol events sent by the backend, e.g. to reload the state
+    /// on [ConnectionEvent::StateReset].
+    pub fn connection_event_handler(self, handler: impl FnMut(ConnectionEvent) + 'static) -> Self {
+        self.sse.set_connection_event_handler(handler);
+        self
+    }
+
     pub async fn send_up_msg(&self, up_msg: UMsg) -> Result<CorId, SendUpMsgError> {
         self.send_up_msg_with_options(up_msg, MsgOptions::default())
             .await
@@ -186,6 +196,22 @@ impl<UMsg: Serialize, DMsg: DeserializeOwned + 'static> Connection<UMsg, DMsg> {
     }
 }
 
+// ------ ConnectionEvent ------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// The client has reconnected to a new server process, the state kept by the backend is lost.
+    StateReset,
+    /// The server is shutting down, the client reconnects after `grace_ms` milliseconds.
+    ServerRestarting { grace_ms: u32 },
+    /// Another client has taken over the session, the connection has been closed.
+    SessionReplaced,
+    /// Events following the `last_event_id` have been lost while the client was disconnected.
+    Gap { last_event_id: u64 },
+    /// An event split into chunks by the backend, `DownMsg`s are passed to the `DownMsg` handler.
+    Reassembled { event: String, data: String },
+}
+
 // ------ MsgOptions ------
 
 #[derive(Debug, Clone, Copy)]
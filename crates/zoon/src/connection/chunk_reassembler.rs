@@ -0,0 +1,117 @@
+use crate::*;
+use std::{collections::HashMap, error::Error, fmt};
+
+// ------ ChunkReassembler ------
+
+/// Joins the `chunk` events of an event split by the backend,
+/// the event is complete once its `chunk_end` event has been received.
+/// Chunks of different events may be interleaved.
+#[derive(Default)]
+pub(super) struct ChunkReassembler {
+    fragments: HashMap<u64, Vec<Option<String>>>,
+}
+
+impl ChunkReassembler {
+    pub(super) fn push_chunk(&mut self, chunk: &str) -> Result<(), ChunkError> {
+        let Chunk {
+            id,
+            seq,
+            total,
+            data,
+        } = serde_json::from_str(chunk).map_err(ChunkError::JsonDeserializationFailed)?;
+        let fragments = self
+            .fragments
+            .entry(id)
+            .or_insert_with(|| vec![None; total]);
+        let fragment = fragments
+            .get_mut(seq)
+            .ok_or(ChunkError::InvalidSeq { id, seq })?;
+        *fragment = Some(data);
+        Ok(())
+    }
+
+    /// Returns the event name and the data of the complete event.
+    pub(super) fn finish(&mut self, chunk_end: &str) -> Result<(String, String), ChunkError> {
+        let ChunkEnd { id, event } =
+            serde_json::from_str(chunk_end).map_err(ChunkError::JsonDeserializationFailed)?;
+        // The fragments of an incomplete event are dropped.
+        let data = self
+            .fragments
+            .remove(&id)
+            .and_then(|fragments| fragments.into_iter().collect::<Option<String>>())
+            .ok_or(ChunkError::MissingChunks { id })?;
+        Ok((event, data))
+    }
+}
+
+#[derive(Deserialize)]
+struct Chunk {
+    id: u64,
+    seq: usize,
+    total: usize,
+    data: String,
+}
+
+#[derive(Deserialize)]
+struct ChunkEnd {
+    id: u64,
+    event: String,
+}
+
+// ------ ChunkError ------
+
+#[derive(Debug)]
+pub(super) enum ChunkError {
+    JsonDeserializationFailed(serde_json::Error),
+    InvalidSeq { id: u64, seq: usize },
+    MissingChunks { id: u64 },
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::JsonDeserializationFailed(error) => {
+                write!(f, "failed to JSON deserialize chunk: {:?}", error)
+            }
+            Self::InvalidSeq { id, seq } => {
+                write!(f, "chunk {} of event {} is out of range", seq, id)
+            }
+            Self::MissingChunks { id } => {
+                write!(f, "chunks of event {} are missing", id)
+            }
+        }
+    }
+}
+
+impl Error for ChunkError {}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reassemble_interleaved_chunks() {
+        // ------ ARRANGE ------
+        let mut chunk_reassembler = ChunkReassembler::default();
+        let chunks = [
+            r#"{"id":1,"seq":1,"total":2,"event":"down_msg","data":"lo"}"#,
+            r#"{"id":2,"seq":0,"total":1,"event":"news","data":"🌙"}"#,
+            r#"{"id":1,"seq":0,"total":2,"event":"down_msg","data":"hel"}"#,
+        ];
+
+        // ------ ACT ------
+        for chunk in chunks {
+            chunk_reassembler.push_chunk(chunk).unwrap();
+        }
+        let first = chunk_reassembler.finish(r#"{"id":1,"event":"down_msg"}"#);
+        let second = chunk_reassembler.finish(r#"{"id":2,"event":"news"}"#);
+        let repeated = chunk_reassembler.finish(r#"{"id":2,"event":"news"}"#);
+
+        // ------ ASSERT ------
+        assert_eq!(first.unwrap(), ("down_msg".to_owned(), "hello".to_owned()));
+        assert_eq!(second.unwrap(), ("news".to_owned(), "🌙".to_owned()));
+        assert!(matches!(repeated, Err(ChunkError::MissingChunks { id: 2 })));
+    }
+}
@@ -1,6 +1,9 @@
-use crate::moonlight::{DownMsgTransporterForDe, SessionId};
+#[cfg(feature = "serde")]
+use super::chunk_reassembler::ChunkReassembler;
+use super::ConnectionEvent;
+use crate::moonlight::{DownMsgTransporterForDe, ProtocolEvent, SessionId};
 use crate::{format, *};
-use std::{error::Error, fmt};
+use std::{cell::RefCell, error::Error, fmt, rc::Rc};
 
 #[cfg(feature = "moonlight/serde_with_serde")]
 use moonlight::serde::{self, Deserialize};
@@ -10,10 +13,37 @@ use moonlight::serde::{self, Deserialize};
 pub struct SSE {
     reconnecting_event_source: SendWrapper<ReconnectingEventSource>,
     _down_msg_handler: SendWrapper<Closure<dyn FnMut(JsValue)>>,
+    _connection_id_handler: SendWrapper<Closure<dyn FnMut(JsValue)>>,
+    _epoch_handler: SendWrapper<Closure<dyn FnMut(JsValue)>>,
+    _protocol_event_handler: SendWrapper<Closure<dyn FnMut(JsValue)>>,
+    connection_event_handler: SendWrapper<ConnectionEventHandler>,
+    pagehide_listener: SendWrapper<Closure<dyn FnMut()>>,
 }
 
+type ConnectionEventHandler = Rc<RefCell<Option<Box<dyn FnMut(ConnectionEvent)>>>>;
+
+#[cfg(feature = "serde")]
+type DownMsgDataHandler = Rc<RefCell<dyn FnMut(&str)>>;
+
+// Protocol events dispatched by `protocol_event_handler_closure`.
+#[cfg(feature = "serde")]
+const DISPATCHED_PROTOCOL_EVENTS: [ProtocolEvent; 6] = [
+    ProtocolEvent::StateReset,
+    ProtocolEvent::ServerRestarting,
+    ProtocolEvent::SessionReplaced,
+    ProtocolEvent::Gap,
+    ProtocolEvent::Chunk,
+    ProtocolEvent::ChunkEnd,
+];
+
 impl Drop for SSE {
     fn drop(&mut self) {
+        window()
+            .remove_event_listener_with_callback(
+                "pagehide",
+                self.pagehide_listener.as_ref().unchecked_ref(),
+            )
+            .unwrap_throw();
         self.reconnecting_event_source.close();
     }
 }
@@ -24,46 +54,193 @@ impl SSE {
         session_id: SessionId,
         down_msg_handler: impl FnMut(DMsg, CorId) + 'static,
     ) -> Self {
-        let down_msg_handler = down_msg_handler_closure(down_msg_handler);
+        let down_msg_data_handler = down_msg_data_handler(down_msg_handler);
+        let down_msg_handler = down_msg_handler_closure(Rc::clone(&down_msg_data_handler));
+        let connection_event_handler = ConnectionEventHandler::default();
+
+        // Set by the backend on every (re)connection.
+        let connection_id = Rc::new(RefCell::new(None));
+        let connection_id_handler = connection_id_handler_closure(Rc::clone(&connection_id));
+        let pagehide_listener = pagehide_listener_closure(session_id, connection_id);
 
         let reconnecting_event_source = connect(session_id);
-        reconnecting_event_source
-            .add_event_listener("down_msg", down_msg_handler.as_ref().unchecked_ref());
+        let epoch_handler = epoch_handler_closure(session_id, reconnecting_event_source.clone());
+        let protocol_event_handler = protocol_event_handler_closure(
+            reconnecting_event_source.clone(),
+            down_msg_data_handler,
+            Rc::clone(&connection_event_handler),
+        );
+        reconnecting_event_source.add_event_listener(
+            ProtocolEvent::DownMsg.as_str(),
+            down_msg_handler.as_ref().unchecked_ref(),
+        );
+        reconnecting_event_source.add_event_listener(
+            ProtocolEvent::ConnectionId.as_str(),
+            connection_id_handler.as_ref().unchecked_ref(),
+        );
+        reconnecting_event_source.add_event_listener(
+            ProtocolEvent::Epoch.as_str(),
+            epoch_handler.as_ref().unchecked_ref(),
+        );
+        for event in DISPATCHED_PROTOCOL_EVENTS {
+            reconnecting_event_source.add_event_listener(
+                event.as_str(),
+                protocol_event_handler.as_ref().unchecked_ref(),
+            );
+        }
+        window()
+            .add_event_listener_with_callback(
+                "pagehide",
+                pagehide_listener.as_ref().unchecked_ref(),
+            )
+            .unwrap_throw();
 
         Self {
             reconnecting_event_source: SendWrapper::new(reconnecting_event_source),
             _down_msg_handler: SendWrapper::new(down_msg_handler),
+            _connection_id_handler: SendWrapper::new(connection_id_handler),
+            _epoch_handler: SendWrapper::new(epoch_handler),
+            _protocol_event_handler: SendWrapper::new(protocol_event_handler),
+            connection_event_handler: SendWrapper::new(connection_event_handler),
+            pagehide_listener: SendWrapper::new(pagehide_listener),
         }
     }
+
+    pub fn set_connection_event_handler(&self, handler: impl FnMut(ConnectionEvent) + 'static) {
+        *self.connection_event_handler.borrow_mut() = Some(Box::new(handler));
+    }
 }
 
 #[cfg(feature = "serde")]
-fn down_msg_handler_closure<DMsg: DeserializeOwned>(
-    mut down_msg_handler: impl FnMut(DMsg, CorId) + 'static,
+fn connection_id_handler_closure(
+    connection_id: Rc<RefCell<Option<String>>>,
+) -> Closure<dyn FnMut(JsValue)> {
+    Closure::new(move |event: JsValue| {
+        let id = Reflect::get(&event, &JsValue::from("data"))
+            .unwrap()
+            .as_string();
+        *connection_id.borrow_mut() = id;
+    })
+}
+
+// The last epoch is sent on reconnect, the backend starts with the `state_reset` event
+// when the client had been connected to a previous server process.
+#[cfg(feature = "serde")]
+fn epoch_handler_closure(
+    session_id: SessionId,
+    reconnecting_event_source: ReconnectingEventSource,
 ) -> Closure<dyn FnMut(JsValue)> {
-    Closure::new(
-        move |event: JsValue| match down_msg_transporter_from_event(event) {
+    Closure::new(move |event: JsValue| {
+        let epoch = Reflect::get(&event, &JsValue::from("data"))
+            .unwrap()
+            .as_string();
+        reconnecting_event_source.set_url(&message_sse_url(session_id, epoch.as_deref()));
+    })
+}
+
+// The backend removes the connection immediately
+// instead of waiting for a failed keep-alive message.
+#[cfg(feature = "serde")]
+fn pagehide_listener_closure(
+    session_id: SessionId,
+    connection_id: Rc<RefCell<Option<String>>>,
+) -> Closure<dyn FnMut()> {
+    Closure::new(move || {
+        if let Some(connection_id) = connection_id.borrow().as_deref() {
+            let url = format!("/_api/message_sse/{}/{}/close", session_id, connection_id);
+            let _ = window().navigator().send_beacon(&url);
+        }
+    })
+}
+
+// Handles the data of `down_msg` events, including `down_msg` events reassembled from chunks.
+#[cfg(feature = "serde")]
+fn down_msg_data_handler<DMsg: DeserializeOwned>(
+    mut down_msg_handler: impl FnMut(DMsg, CorId) + 'static,
+) -> DownMsgDataHandler {
+    Rc::new(RefCell::new(
+        move |down_msg_transporter: &str| match serde_json::from_str(down_msg_transporter) {
             Ok(DownMsgTransporterForDe { down_msg, cor_id }) => down_msg_handler(down_msg, cor_id),
-            Err(error) => crate::eprintln!("{:?}", error),
+            Err(error) => {
+                crate::eprintln!("{:?}", DownMsgError::JsonDeserializationFailed(error))
+            }
         },
-    )
+    ))
 }
 
 #[cfg(feature = "serde")]
-fn down_msg_transporter_from_event<DMsg: DeserializeOwned>(
-    event: JsValue,
-) -> Result<DownMsgTransporterForDe<DMsg>, DownMsgError> {
-    let down_msg_transporter = Reflect::get(&event, &JsValue::from("data"))
-        .unwrap()
-        .as_string()
-        .ok_or(DownMsgError::InvalidDataValue)?;
-
-    serde_json::from_str(&down_msg_transporter).map_err(DownMsgError::JsonDeserializationFailed)
+fn down_msg_handler_closure(
+    down_msg_data_handler: DownMsgDataHandler,
+) -> Closure<dyn FnMut(JsValue)> {
+    Closure::new(move |event: JsValue| {
+        match Reflect::get(&event, &JsValue::from("data"))
+            .unwrap()
+            .as_string()
+        {
+            Some(down_msg_transporter) => {
+                (down_msg_data_handler.borrow_mut())(&down_msg_transporter)
+            }
+            None => crate::eprintln!("{:?}", DownMsgError::InvalidDataValue),
+        }
+    })
+}
+
+#[cfg(feature = "serde")]
+fn protocol_event_handler_closure(
+    reconnecting_event_source: ReconnectingEventSource,
+    down_msg_data_handler: DownMsgDataHandler,
+    connection_event_handler: ConnectionEventHandler,
+) -> Closure<dyn FnMut(JsValue)> {
+    let mut chunk_reassembler = ChunkReassembler::default();
+    Closure::new(move |event: JsValue| {
+        let event_string = |key: &str| {
+            Reflect::get(&event, &JsValue::from(key))
+                .unwrap()
+                .as_string()
+                .unwrap_or_default()
+        };
+        let data = event_string("data");
+        let connection_event = match event_string("type").parse() {
+            Ok(ProtocolEvent::StateReset) => ConnectionEvent::StateReset,
+            Ok(ProtocolEvent::ServerRestarting) => ConnectionEvent::ServerRestarting {
+                grace_ms: data.parse().unwrap_or_default(),
+            },
+            Ok(ProtocolEvent::SessionReplaced) => {
+                // Reconnecting would create the replaced session again.
+                reconnecting_event_source.close();
+                ConnectionEvent::SessionReplaced
+            }
+            Ok(ProtocolEvent::Gap) => ConnectionEvent::Gap {
+                last_event_id: data.parse().unwrap_or_default(),
+            },
+            Ok(ProtocolEvent::Chunk) => {
+                if let Err(error) = chunk_reassembler.push_chunk(&data) {
+                    crate::eprintln!("{:?}", error);
+                }
+                return;
+            }
+            Ok(ProtocolEvent::ChunkEnd) => match chunk_reassembler.finish(&data) {
+                Ok((event, data)) if event == ProtocolEvent::DownMsg.as_str() => {
+                    (down_msg_data_handler.borrow_mut())(&data);
+                    return;
+                }
+                Ok((event, data)) => ConnectionEvent::Reassembled { event, data },
+                Err(error) => {
+                    crate::eprintln!("{:?}", error);
+                    return;
+                }
+            },
+            _ => return,
+        };
+        if let Some(handler) = connection_event_handler.borrow_mut().as_mut() {
+            handler(connection_event);
+        }
+    })
 }
 
 fn connect(session_id: SessionId) -> ReconnectingEventSource {
     ReconnectingEventSource::new(
-        &format!("/_api/message_sse/{}", session_id),
+        &message_sse_url(session_id, None),
         Some(ReconnectingEventSourceOptions {
             withCredentials: false,
             max_retry_time: 5000,
@@ -71,6 +248,14 @@ fn connect(session_id: SessionId) -> ReconnectingEventSource {
     )
 }
 
+// `ReconnectingEventSource` appends the `lastEventId` query parameter on reconnect.
+fn message_sse_url(session_id: SessionId, epoch: Option<&str>) -> String {
+    match epoch {
+        Some(epoch) => format!("/_api/message_sse/{}?epoch={}", session_id, epoch),
+        None => format!("/_api/message_sse/{}", session_id),
+    }
+}
+
 // ------ DownMsgError ------
 
 #[derive(Debug)]
@@ -104,6 +289,7 @@ impl Error for DownMsgError {}
 
 #[wasm_bindgen]
 extern "C" {
+    #[derive(Clone)]
     type ReconnectingEventSource;
 
     #[wasm_bindgen(constructor)]
@@ -112,6 +298,9 @@ extern "C" {
     #[wasm_bindgen(method, js_name = addEventListener)]
     fn add_event_listener(this: &ReconnectingEventSource, type_: &str, listener: &js_sys::Function);
 
+    #[wasm_bindgen(method, setter)]
+    fn set_url(this: &ReconnectingEventSource, url: &str);
+
     #[wasm_bindgen(method)]
     fn close(this: &ReconnectingEventSource);
 }
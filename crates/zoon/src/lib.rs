@@ -86,7 +86,8 @@ pub use web_worker_loader::WebWorkerLoader;
 
 #[cfg(feature = "connection")]
 pub use connection::{
-    Connection, ExchangeMsgsError, MsgOptions, ReceiveDownMsgError, SendUpMsgError,
+    Connection, ConnectionEvent, ExchangeMsgsError, MsgOptions, ReceiveDownMsgError,
+    SendUpMsgError,
 };
 
 #[cfg(feature = "routing")]
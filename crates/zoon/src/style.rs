@@ -18,7 +18,7 @@ mod align_content;
 pub use align_content::AlignContent;
 
 mod background;
-pub use background::{Background, BackgroundAlign, BackgroundSize};
+pub use background::{Background, BackgroundAlign, BackgroundLayer, BackgroundSize, Gradient};
 
 mod borders;
 pub use borders::{Border, Borders};
@@ -29,6 +29,9 @@ pub use clip::Clip;
 mod cursor;
 pub use cursor::{Cursor, CursorIcon};
 
+mod filter;
+pub use filter::{BackdropFilter, Filter};
+
 mod font;
 pub use font::{Font, FontFamily, FontLine, FontWeight, IntoOptionLineHeight, LineHeight};
 
@@ -36,7 +39,10 @@ mod height;
 pub use height::Height;
 
 mod layer_index;
-pub use layer_index::LayerIndex;
+pub use layer_index::{LayerIndex, LayerScale};
+
+mod opacity;
+pub use opacity::Opacity;
 
 mod outline;
 pub use outline::Outline;
@@ -44,6 +50,9 @@ pub use outline::Outline;
 mod padding;
 pub use padding::Padding;
 
+mod position;
+pub use position::Position;
+
 mod resizable;
 pub use resizable::Resizable;
 
@@ -65,6 +74,9 @@ pub use snap_items::SnapItems;
 mod gap;
 pub use gap::Gap;
 
+mod theme;
+pub use theme::{set_theme, theme, theme_signal};
+
 mod transitions;
 pub use transitions::{Transition, Transitions};
 
@@ -208,6 +220,25 @@ pub fn pct<'a>(percent: impl IntoCowStr<'a>) -> Cow<'a, str> {
     [&percent.into_cow_str(), "%"].concat().into()
 }
 
+// ------ strings ------
+
+// Quotes, backslashes and line breaks can't end the quoted CSS string.
+pub(crate) fn quoted_css_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\a "),
+            '\r' => quoted.push_str("\\d "),
+            _ => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 // ------ Style ------
 
 /// Trait to be implemented to enable the use for styling.
@@ -720,3 +751,42 @@ fn set_css_property(declaration: &CssStyleDeclaration, name: &str, value: &str,
     }
     panic!("invalid CSS property: `{}: {};`", name, value);
 }
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    /// CSS props of the first style group, dynamic props hold the current value
+    /// of their signal and are missing when the signal yields `None`.
+    pub(crate) fn css_props<'a>(style: impl Style<'a>) -> BTreeMap<String, String> {
+        let mut groups = StyleGroups::default();
+        style.move_to_groups(&mut groups);
+        let group = groups.into_groups().next().unwrap();
+
+        let mut props = group
+            .static_css_props
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value.value.to_string()))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut context = Context::from_waker(Waker::noop());
+        for (name, value) in group.dynamic_css_props {
+            let mut signal =
+                pin!(value.signal_ref(|value| value.as_ref().map(|value| value.value.to_string())));
+            match signal.as_mut().poll_change(&mut context) {
+                Poll::Ready(Some(Some(value))) => {
+                    props.insert(name.into_owned(), value);
+                }
+                Poll::Ready(Some(None)) => (),
+                poll => panic!("the `{name}` signal isn't ready: {poll:?}"),
+            }
+        }
+        props
+    }
+}
@@ -1,21 +1,63 @@
 use crate::*;
-use strum::IntoStaticStr;
+use std::borrow::Cow;
 
-#[derive(Clone, Copy, IntoStaticStr)]
-#[strum(serialize_all = "lowercase")]
+mod background_layer;
+pub use background_layer::BackgroundLayer;
+
+mod gradient;
+pub use gradient::Gradient;
+
+/// `Percent(x, y)` aligns the same point of the image and the element,
+/// e.g. `Percent(100., 0.)` is the top right corner.
+#[derive(Clone, Copy)]
 pub enum BackgroundAlign {
     Top,
     Bottom,
     Left,
     Right,
     Center,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Percent(f64, f64),
+}
+
+impl<'a> IntoCowStr<'a> for BackgroundAlign {
+    fn into_cow_str(self) -> Cow<'a, str> {
+        match self {
+            Self::Top => "top".into(),
+            Self::Bottom => "bottom".into(),
+            Self::Left => "left".into(),
+            Self::Right => "right".into(),
+            Self::Center => "center".into(),
+            Self::TopLeft => "left top".into(),
+            Self::TopRight => "right top".into(),
+            Self::BottomLeft => "left bottom".into(),
+            Self::BottomRight => "right bottom".into(),
+            Self::Percent(x, y) => [pct(x), pct(y)].join(" ").into(),
+        }
+    }
 }
 
+/// `Explicit(width, height)` is in pixels.
 #[derive(Clone, Copy)]
 pub enum BackgroundSize {
     Contain,
     Cover,
     Original,
+    Explicit(u32, u32),
+}
+
+impl<'a> IntoCowStr<'a> for BackgroundSize {
+    fn into_cow_str(self) -> Cow<'a, str> {
+        match self {
+            Self::Contain => "contain".into(),
+            Self::Cover => "cover".into(),
+            Self::Original => "auto".into(),
+            Self::Explicit(width, height) => [px(width), px(height)].join(" ").into(),
+        }
+    }
 }
 
 /// Styling to set the background for an element.
@@ -39,28 +81,66 @@ impl<'a> Background<'a> {
         Self::default()
     }
 
+    /// Stack multiple images or gradients, the first layer is on top.
+    /// The background color is below all layers.
+    /// # Example
+    /// ```no_run
+    /// use zoon::{named_color::*, *};
+    ///
+    /// let element = El::new().s(Background::layers([
+    ///     BackgroundLayer::new()
+    ///         .url("/assets/images/texture.png")
+    ///         .size(BackgroundSize::Explicit(64, 64)),
+    ///     BackgroundLayer::new()
+    ///         .gradient(Gradient::linear().step(BLUE_5, None).step(GREEN_5, None))
+    ///         .no_repeat(),
+    /// ])
+    /// .color(BLUE_5));
+    /// ```
+    pub fn layers(layers: impl IntoIterator<Item = BackgroundLayer>) -> Self {
+        let layers = layers.into_iter().collect::<Vec<_>>();
+        let mut this = Self::default();
+        for (name, value) in background_layer::layers_css_props(&layers) {
+            this.static_css_props.insert(name, value);
+        }
+        this
+    }
+
     pub fn align(mut self, align: impl Into<Option<BackgroundAlign>>) -> Self {
         if let Some(align) = align.into() {
-            self.static_css_props
-                .insert("background-position", <&'static str>::from(align));
+            self.static_css_props.insert("background-position", align);
         }
         self
     }
 
     pub fn size(mut self, size: impl Into<Option<BackgroundSize>>) -> Self {
         if let Some(size) = size.into() {
-            self.static_css_props.insert(
-                "background-size",
-                match size {
-                    BackgroundSize::Contain => "contain",
-                    BackgroundSize::Cover => "cover",
-                    BackgroundSize::Original => "auto",
-                },
-            );
+            self.static_css_props.insert("background-size", size);
         }
         self
     }
 
+    /// Show the image only once.
+    pub fn no_repeat(mut self) -> Self {
+        self.static_css_props
+            .insert("background-repeat", "no-repeat");
+        self
+    }
+
+    /// Repeat the image only horizontally.
+    pub fn repeat_x(mut self) -> Self {
+        self.static_css_props
+            .insert("background-repeat", "repeat-x");
+        self
+    }
+
+    /// Repeat the image only vertically.
+    pub fn repeat_y(mut self) -> Self {
+        self.static_css_props
+            .insert("background-repeat", "repeat-y");
+        self
+    }
+
     /// Set a given color to the background.
     /// # Example
     /// ```no_run
@@ -104,7 +184,49 @@ impl<'a> Background<'a> {
         self
     }
 
+    /// Set the color from the theme registered by [set_theme].
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// #[derive(Clone)]
+    /// struct Theme {
+    ///     surface: Color,
+    /// }
+    ///
+    /// let element = El::new().s(Background::new().color_theme(|theme: &Theme| theme.surface));
+    /// ```
+    pub fn color_theme<T: Clone + 'static, C: IntoOptionColor>(
+        self,
+        mut color: impl FnMut(&T) -> C + 'static,
+    ) -> Self {
+        self.color_signal(
+            theme_signal::<T>()
+                .map(move |theme| theme.and_then(|theme| color(&theme).into_option_color())),
+        )
+    }
+
+    /// Switch between colors by [color_scheme_signal],
+    /// i.e. by the OS / browser dark mode or [set_color_scheme].
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let element = El::new().s(Background::new().color_scheme(NamedColor::White, NamedColor::Black));
+    /// ```
+    pub fn color_scheme(self, light: impl IntoOptionColor, dark: impl IntoOptionColor) -> Self {
+        let (light, dark) = (light.into_option_color(), dark.into_option_color());
+        self.color_signal(
+            color_scheme_signal().map(move |color_scheme| match color_scheme {
+                ColorScheme::Light => light,
+                ColorScheme::Dark => dark,
+            }),
+        )
+    }
+
     /// Can be used to set an image as background.
+    /// The URL is quoted, so it can contain parentheses or quotes.
+    /// The background color is visible while the image is loading.
     /// # Example
     /// ```no_run
     /// use zoon::*;
@@ -112,13 +234,13 @@ impl<'a> Background<'a> {
     /// ```
     pub fn url(mut self, url: impl IntoOptionCowStr<'a>) -> Self {
         if let Some(url) = url.into_option_cow_str() {
-            let url = ["url(", &url, ")"].concat();
+            let url = url_value(&url);
             self.static_css_props.insert("background-image", url);
         }
         self
     }
 
-    /// Can set the url depending of the signal's state.
+    /// Can set the url depending of the signal's state, e.g. for user avatars.
     ///
     /// Here the background changes depending of the click state.
     /// # Example
@@ -139,14 +261,53 @@ impl<'a> Background<'a> {
         mut self,
         url: impl Signal<Item = impl IntoOptionCowStr<'static> + 'static> + Unpin + 'static,
     ) -> Self {
-        let url = url.map(|url| {
-            url.into_option_cow_str()
-                .map(|url| ["url(", &url, ")"].concat())
-        });
+        let url = url.map(|url| url.into_option_cow_str().map(|url| url_value(&url)));
         self.dynamic_css_props
             .insert("background-image".into(), box_css_signal(url));
         self
     }
+
+    /// Set a [Gradient] as the background image, it replaces the [url](Self::url) image.
+    /// The background color is still visible where the gradient isn't supported.
+    /// # Example
+    /// ```no_run
+    /// use zoon::{named_color::*, *};
+    ///
+    /// let element = El::new().s(Background::new()
+    ///     .color(BLUE_5)
+    ///     .gradient(Gradient::linear().angle(135).step(BLUE_5, 0.).step(GREEN_5, 1.)));
+    /// ```
+    pub fn gradient(mut self, gradient: impl Into<Option<Gradient>>) -> Self {
+        if let Some(gradient) = gradient.into() {
+            self.static_css_props.insert("background-image", gradient);
+        }
+        self
+    }
+
+    /// Set the gradient depending of the signal's state, `None` removes the background image.
+    /// # Example
+    /// ```no_run
+    /// use zoon::{named_color::*, *};
+    ///
+    /// let (dark, dark_signal) = Mutable::new_and_signal(false);
+    /// let element = El::new().s(Background::new().gradient_signal(dark_signal.map_bool(
+    ///     || Gradient::radial().step(GRAY_8, None).step(GRAY_9, None),
+    ///     || Gradient::radial().step(GRAY_0, None).step(GRAY_2, None),
+    /// )));
+    /// ```
+    pub fn gradient_signal(
+        mut self,
+        gradient: impl Signal<Item = impl Into<Option<Gradient>>> + Unpin + 'static,
+    ) -> Self {
+        let gradient = gradient.map(|gradient| gradient.into());
+        self.dynamic_css_props
+            .insert("background-image".into(), box_css_signal(gradient));
+        self
+    }
+}
+
+fn url_value(url: &str) -> String {
+    ["url(", &quoted_css_string(url), ")"].concat()
 }
 
 impl<'a> Style<'a> for Background<'a> {
@@ -162,3 +323,71 @@ impl<'a> Style<'a> for Background<'a> {
         });
     }
 }
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::tests::css_props;
+
+    #[test]
+    fn test_layer_values_are_aligned() {
+        // ------ ARRANGE ------
+        let gradient = Gradient::linear()
+            .step(NamedColor::SkyBlue, 0.)
+            .step(NamedColor::SteelBlue, 1.);
+        let gradient_value = gradient.clone().into_cow_str().into_owned();
+
+        // ------ ACT ------
+        let props = css_props(
+            Background::layers([
+                BackgroundLayer::new()
+                    .url("/texture.png")
+                    .size(BackgroundSize::Explicit(64, 64)),
+                BackgroundLayer::new().gradient(gradient),
+                BackgroundLayer::new()
+                    .url("/logo.png")
+                    .align(BackgroundAlign::Center)
+                    .no_repeat(),
+            ])
+            .color(NamedColor::WhiteSmoke),
+        );
+
+        // ------ ASSERT ------
+        assert_eq!(
+            props["background-image"],
+            [
+                r#"url("/texture.png")"#,
+                &gradient_value,
+                r#"url("/logo.png")"#
+            ]
+            .join(", ")
+        );
+        assert_eq!(props["background-size"], "64px 64px, auto, auto");
+        assert_eq!(props["background-position"], "0% 0%, 0% 0%, center");
+        assert_eq!(props["background-repeat"], "repeat, repeat, no-repeat");
+        assert_eq!(
+            props["background-color"],
+            NamedColor::WhiteSmoke.into_color_string()
+        );
+    }
+
+    #[test]
+    fn test_unset_layer_options_are_omitted() {
+        // ------ ACT ------
+        let props = css_props(Background::layers([
+            BackgroundLayer::new().url("/a.png"),
+            BackgroundLayer::new().url("/b.png"),
+        ]));
+
+        // ------ ASSERT ------
+        assert_eq!(
+            Vec::from_iter(props),
+            [(
+                "background-image".to_owned(),
+                r#"url("/a.png"), url("/b.png")"#.to_owned()
+            )]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use super::url_value;
+use crate::*;
+
+// ------ BackgroundLayer ------
+
+/// One of the layers set by [Background::layers], the first layer is on top.
+#[derive(Default, Clone)]
+pub struct BackgroundLayer {
+    image: Option<String>,
+    align: Option<BackgroundAlign>,
+    size: Option<BackgroundSize>,
+    repeat: Option<&'static str>,
+}
+
+impl BackgroundLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn url(mut self, url: impl IntoCowStr<'static>) -> Self {
+        self.image = Some(url_value(&url.into_cow_str()));
+        self
+    }
+
+    pub fn gradient(mut self, gradient: Gradient) -> Self {
+        self.image = Some(gradient.into_cow_str().into_owned());
+        self
+    }
+
+    pub fn align(mut self, align: BackgroundAlign) -> Self {
+        self.align = Some(align);
+        self
+    }
+
+    pub fn size(mut self, size: BackgroundSize) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn no_repeat(mut self) -> Self {
+        self.repeat = Some("no-repeat");
+        self
+    }
+
+    pub fn repeat_x(mut self) -> Self {
+        self.repeat = Some("repeat-x");
+        self
+    }
+
+    pub fn repeat_y(mut self) -> Self {
+        self.repeat = Some("repeat-y");
+        self
+    }
+}
+
+// The property name, the layer value and the CSS initial value.
+type Longhand = (&'static str, fn(&BackgroundLayer) -> Option<String>, &'static str);
+
+// Returns the longhand properties with one value per layer.
+// Layers without the option get the CSS initial value so the values stay aligned with images.
+pub(crate) fn layers_css_props(layers: &[BackgroundLayer]) -> Vec<(&'static str, String)> {
+    let longhands: [Longhand; 4] = [
+        ("background-image", |layer| layer.image.clone(), "none"),
+        (
+            "background-position",
+            |layer| layer.align.map(|align| align.into_cow_str().into_owned()),
+            "0% 0%",
+        ),
+        (
+            "background-size",
+            |layer| layer.size.map(|size| size.into_cow_str().into_owned()),
+            "auto",
+        ),
+        (
+            "background-repeat",
+            |layer| layer.repeat.map(str::to_owned),
+            "repeat",
+        ),
+    ];
+    longhands
+        .into_iter()
+        .filter_map(|(name, value, initial_value)| {
+            let values = layers.iter().map(value).collect::<Vec<_>>();
+            if values.iter().all(Option::is_none) {
+                return None;
+            }
+            let values = values
+                .into_iter()
+                .map(|value| value.unwrap_or_else(|| initial_value.to_owned()))
+                .collect::<Vec<_>>();
+            Some((name, values.join(", ")))
+        })
+        .collect()
+}
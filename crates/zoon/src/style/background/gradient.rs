@@ -0,0 +1,89 @@
+use crate::*;
+use std::borrow::Cow;
+
+// ------ Gradient ------
+
+/// Gradient set by [Background::gradient].
+/// Steps without positions are distributed evenly,
+/// positions are fractions of the gradient length, e.g. `0.5` is the middle.
+/// More information at <https://developer.mozilla.org/en-US/docs/Web/CSS/gradient>.
+///
+/// NOTE: A gradient needs at least two steps to be valid.
+#[derive(Clone)]
+pub struct Gradient {
+    kind: GradientKind,
+    steps: Vec<(String, Option<f64>)>,
+}
+
+#[derive(Clone, Copy)]
+enum GradientKind {
+    Linear { angle: Option<f64> },
+    Radial { circle: bool },
+}
+
+impl Gradient {
+    /// The default direction is from top to bottom.
+    pub fn linear() -> Self {
+        Self::new(GradientKind::Linear { angle: None })
+    }
+
+    /// The default shape is an ellipse matching the element's aspect ratio.
+    pub fn radial() -> Self {
+        Self::new(GradientKind::Radial { circle: false })
+    }
+
+    fn new(kind: GradientKind) -> Self {
+        Self {
+            kind,
+            steps: Vec::new(),
+        }
+    }
+
+    /// The direction of a linear gradient in degrees, `0` is to top, `90` is to right.
+    pub fn angle(mut self, degrees: impl Into<f64>) -> Self {
+        if let GradientKind::Linear { angle } = &mut self.kind {
+            *angle = Some(degrees.into());
+        }
+        self
+    }
+
+    /// Make a radial gradient circular.
+    pub fn circle(mut self) -> Self {
+        if let GradientKind::Radial { circle } = &mut self.kind {
+            *circle = true;
+        }
+        self
+    }
+
+    /// Add a color step, see [Gradient] for `position` values.
+    pub fn step(mut self, color: impl IntoColor, position: impl Into<Option<f64>>) -> Self {
+        self.steps
+            .push((color.into_color_string(), position.into()));
+        self
+    }
+}
+
+impl<'a> IntoCowStr<'a> for Gradient {
+    fn into_cow_str(self) -> Cow<'a, str> {
+        debug_assert!(self.steps.len() >= 2, "gradient needs at least two steps");
+
+        let (function, shape) = match self.kind {
+            GradientKind::Linear { angle } => (
+                "linear-gradient(",
+                angle.map(|angle| crate::format!("{}deg", angle)),
+            ),
+            GradientKind::Radial { circle } => {
+                ("radial-gradient(", circle.then(|| "circle".to_owned()))
+            }
+        };
+        let steps = self
+            .steps
+            .into_iter()
+            .map(|(color, position)| match position {
+                Some(position) => [color, pct(position * 100.).into_owned()].join(" "),
+                None => color,
+            });
+        let arguments = shape.into_iter().chain(steps).collect::<Vec<_>>();
+        [function, &arguments.join(", "), ")"].concat().into()
+    }
+}
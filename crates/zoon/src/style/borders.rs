@@ -17,6 +17,19 @@ impl<'a> Borders<'a> {
         Self::default()
     }
 
+    /// Remove all borders, e.g. the default borders of an element or
+    /// borders set by another style group.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    /// let button = Button::new().s(Borders::none()).label("I have no borders");
+    /// ```
+    pub fn none() -> Self {
+        let mut this = Self::default();
+        this.static_css_props.insert("border", "none");
+        this
+    }
+
     /// Set properties for bottom, left, right and top borders together.
     ///
     /// ```no_run
@@ -4,6 +4,7 @@ use crate::*;
 #[derive(Default, Clone)]
 pub struct Clip<'a> {
     static_css_props: StaticCSSProps<'a>,
+    dynamic_css_props: DynamicCSSProps,
 }
 
 impl<'a> Clip<'a> {
@@ -73,13 +74,40 @@ impl<'a> Clip<'a> {
         this.static_css_props.insert("overflow-y", "hidden");
         this
     }
+
+    /// The element gets clipped by its parent vertically and horizontally
+    /// while the signal is `true`, e.g. to lock the page scrolling while a modal is open.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let (modal_open, modal_open_signal) = Mutable::new_and_signal(false);
+    /// let page = Column::new()
+    ///     .s(Clip::both_signal(modal_open_signal))
+    ///     .item(Button::new().label("Open").on_press(move || modal_open.set(true)));
+    /// ```
+    pub fn both_signal(clip: impl Signal<Item = bool> + Unpin + 'static) -> Self {
+        let mut this = Self::default();
+        let clip = clip.broadcast();
+        for name in ["overflow-x", "overflow-y"] {
+            this.dynamic_css_props.insert(
+                name.into(),
+                box_css_signal(clip.signal().map_true(|| "hidden")),
+            );
+        }
+        this
+    }
 }
 
 impl<'a> Style<'a> for Clip<'a> {
     fn move_to_groups(self, groups: &mut StyleGroups<'a>) {
         groups.update_first(|mut group| {
-            let Self { static_css_props } = self;
+            let Self {
+                static_css_props,
+                dynamic_css_props,
+            } = self;
             group.static_css_props.extend(static_css_props);
+            group.dynamic_css_props.extend(dynamic_css_props);
             group
         });
     }
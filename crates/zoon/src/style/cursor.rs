@@ -27,6 +27,16 @@ impl<'a> Cursor<'a> {
         this
     }
 
+    /// Hide the cursor.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    /// let video = El::new().s(Cursor::hidden());
+    /// ```
+    pub fn hidden() -> Self {
+        Self::new(CursorIcon::None)
+    }
+
     /// Create a new cursor depending of the signal's state.
     /// # Example
     /// This example updates the cursor when the user clicks the button.
@@ -113,17 +123,39 @@ pub enum CursorIcon<'a> {
     UpDownArrow,
     UpRightDownLeftArrow,
     UpLeftDownRightArrow,
+    // -- Zooming --
+    ZoomIn,
+    ZoomOut,
     Custom(Cow<'a, str>),
 }
 
 impl<'a> CursorIcon<'a> {
+    /// Create a cursor from an image, `Auto` is used when the image can't be loaded.
     pub fn new(url: &str, hotspot: impl Into<Option<(u32, u32)>>) -> Self {
-        let url = url.into_cow_str();
+        Self::custom(url, hotspot, CursorIcon::Auto)
+    }
+
+    /// Create a cursor from an image with the `fallback` used when the image can't be loaded.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    /// let pen = CursorIcon::custom("/_api/public/pen.png", (0, 24), CursorIcon::Crosshair);
+    /// ```
+    pub fn custom(
+        url: &str,
+        hotspot: impl Into<Option<(u32, u32)>>,
+        fallback: CursorIcon<'a>,
+    ) -> Self {
         let hotspot = match hotspot.into() {
             Some((x, y)) => crate::format!(" {} {}", x, y),
             None => String::new(),
         };
-        let cursor_value = crate::format!("url({}){}, auto", url, hotspot);
+        let cursor_value = crate::format!(
+            "url({}){}, {}",
+            quoted_css_string(url),
+            hotspot,
+            fallback.into_cow_str()
+        );
         CursorIcon::Custom(cursor_value.into())
     }
 }
@@ -161,8 +193,8 @@ impl<'a> IntoCowStr<'a> for CursorIcon<'a> {
             // -- Arrows --
             CursorIcon::UpArrow => "n-resize".into(),
             CursorIcon::RightArrow => "e-resize".into(),
-            CursorIcon::LeftArrow => "s-resize".into(),
-            CursorIcon::DownArrow => "w-resize".into(),
+            CursorIcon::LeftArrow => "w-resize".into(),
+            CursorIcon::DownArrow => "s-resize".into(),
             CursorIcon::UpRightArrow => "ne-resize".into(),
             CursorIcon::UpLeftArrow => "nw-resize".into(),
             CursorIcon::DownRightArrow => "se-resize".into(),
@@ -171,6 +203,9 @@ impl<'a> IntoCowStr<'a> for CursorIcon<'a> {
             CursorIcon::UpDownArrow => "ns-resize".into(),
             CursorIcon::UpRightDownLeftArrow => "nesw-resize".into(),
             CursorIcon::UpLeftDownRightArrow => "nwse-resize".into(),
+            // -- Zooming --
+            CursorIcon::ZoomIn => "zoom-in".into(),
+            CursorIcon::ZoomOut => "zoom-out".into(),
             CursorIcon::Custom(cursor_value) => cursor_value.into(),
         }
     }
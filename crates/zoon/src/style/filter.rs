@@ -0,0 +1,134 @@
+use crate::*;
+use std::borrow::Cow;
+
+macro_rules! make_filter_style {
+    ($(#[$meta:meta])* $name:ident, $css_name:literal) => {
+        $(#[$meta])*
+        #[derive(Default, Clone)]
+        pub struct $name {
+            functions: Vec<Cow<'static, str>>,
+            self_signal: Option<Broadcaster<LocalBoxSignal<'static, Option<Self>>>>,
+        }
+
+        impl $name {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Apply filters depending of signal's state, `None` removes the property.
+            pub fn with_signal_self(
+                filter: impl Signal<Item = impl Into<Option<Self>>> + Unpin + 'static,
+            ) -> Self {
+                let mut this = Self::default();
+                let filter = filter.map(|filter| filter.into());
+                this.self_signal = Some(filter.boxed_local().broadcast());
+                this
+            }
+
+            /// Blur radius in pixels.
+            pub fn blur(mut self, radius: u32) -> Self {
+                self.functions.push(crate::format!("blur({}px)", radius).into());
+                self
+            }
+
+            /// `0` is black, `1` keeps the original brightness.
+            pub fn brightness(mut self, amount: impl Into<f64>) -> Self {
+                let amount = non_negative(amount.into());
+                self.functions.push(crate::format!("brightness({})", amount).into());
+                self
+            }
+
+            /// `0` is fully desaturated, `1` keeps the original saturation.
+            pub fn saturate(mut self, amount: impl Into<f64>) -> Self {
+                let amount = non_negative(amount.into());
+                self.functions.push(crate::format!("saturate({})", amount).into());
+                self
+            }
+
+            /// `1` is fully grayscale, `0` keeps the original colors.
+            pub fn grayscale(mut self, amount: impl Into<f64>) -> Self {
+                let amount = non_negative(amount.into()).min(1.);
+                self.functions.push(crate::format!("grayscale({})", amount).into());
+                self
+            }
+
+            /// [Shadow::spread] and [Shadow::inner] aren't supported by `drop-shadow`
+            /// and they're ignored.
+            pub fn drop_shadow(mut self, shadow: Shadow) -> Self {
+                self.functions
+                    .push(crate::format!("drop-shadow({})", shadow.into_drop_shadow_value()).into());
+                self
+            }
+        }
+
+        impl<'a> Style<'a> for $name {
+            fn move_to_groups(self, groups: &mut StyleGroups<'a>) {
+                groups.update_first(|group| {
+                    let Self {
+                        functions,
+                        self_signal,
+                    } = self;
+
+                    if let Some(self_signal) = self_signal {
+                        group.style_signal(
+                            $css_name,
+                            self_signal.signal_ref(|filter| {
+                                filter
+                                    .as_ref()
+                                    .map(|filter| functions_to_value(&filter.functions))
+                            }),
+                        )
+                    } else {
+                        group.style($css_name, functions_to_value(&functions))
+                    }
+                });
+            }
+        }
+    };
+}
+
+make_filter_style!(
+    /// Graphical effects like blur or grayscale applied to the element.
+    /// More information at <https://developer.mozilla.org/en-US/docs/Web/CSS/filter>.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let (disabled, disabled_signal) = Mutable::new_and_signal(true);
+    /// let thumbnail = Image::new()
+    ///     .url("/_api/public/thumbnail.png")
+    ///     .description("thumbnail")
+    ///     .s(Filter::with_signal_self(
+    ///         disabled_signal.map_true(|| Filter::new().grayscale(1)),
+    ///     ));
+    /// ```
+    Filter,
+    "filter"
+);
+
+make_filter_style!(
+    /// Graphical effects like blur applied to the area behind the element.
+    /// More information at <https://developer.mozilla.org/en-US/docs/Web/CSS/backdrop-filter>.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let panel = El::new()
+    ///     .s(BackdropFilter::new().blur(12).saturate(1.2))
+    ///     .child("Glass");
+    /// ```
+    BackdropFilter,
+    "backdrop-filter"
+);
+
+fn non_negative(amount: f64) -> f64 {
+    debug_assert!(amount >= 0., "filter amount cannot be negative");
+    amount.max(0.)
+}
+
+fn functions_to_value(functions: &[Cow<'static, str>]) -> Cow<'static, str> {
+    if functions.is_empty() {
+        return "none".into();
+    }
+    functions.join(" ").into()
+}
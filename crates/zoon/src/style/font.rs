@@ -39,6 +39,13 @@ enum StyleName {
     TextAlign,
     FontFamily,
     LetterSpacing,
+    TextShadow,
+    WordSpacing,
+    FontVariationSettings,
+    FontVariantNumeric,
+    FontVariantCaps,
+    FontVariantLigatures,
+    FontFeatureSettings,
 }
 
 impl Font<'static> {
@@ -99,7 +106,8 @@ impl<'a> Font<'a> {
         self
     }
 
-    /// Define the font weight depending of signal's state..
+    /// Define the font weight depending of signal's state.
+    /// Variable fonts interpolate the weight when it's combined with [Transitions].
     /// # Example
     /// ```no_run
     /// use zoon::*;
@@ -172,6 +180,29 @@ impl<'a> Font<'a> {
         self
     }
 
+    /// Set the color from the theme registered by [set_theme].
+    pub fn color_theme<T: Clone + 'static, C: IntoOptionColor>(
+        self,
+        mut color: impl FnMut(&T) -> C + 'static,
+    ) -> Self {
+        self.color_signal(
+            theme_signal::<T>()
+                .map(move |theme| theme.and_then(|theme| color(&theme).into_option_color())),
+        )
+    }
+
+    /// Switch between colors by [color_scheme_signal],
+    /// i.e. by the OS / browser dark mode or [set_color_scheme].
+    pub fn color_scheme(self, light: impl IntoOptionColor, dark: impl IntoOptionColor) -> Self {
+        let (light, dark) = (light.into_option_color(), dark.into_option_color());
+        self.color_signal(
+            color_scheme_signal().map(move |color_scheme| match color_scheme {
+                ColorScheme::Light => light,
+                ColorScheme::Dark => dark,
+            }),
+        )
+    }
+
     /// Set the font size.
     /// # Example
     /// ```no_run
@@ -208,7 +239,7 @@ impl<'a> Font<'a> {
         self
     }
 
-    /// Set the text line height in pixels.
+    /// Set the text line height in pixels or as a multiplier of the font size.
     /// # Example
     /// ```no_run
     /// use zoon::*;
@@ -217,14 +248,18 @@ impl<'a> Font<'a> {
     ///     .s(Font::new().line_height(150))
     ///     .content("Neque porro quisquam est qui dolorem ipsum quia dolor sit amet, consectetur, adipisci velit...");
     /// ```
+    ///
+    /// Prefer [LineHeight::Multiplier] when nested elements have other font sizes.
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let paragraph = Paragraph::new()
+    ///     .s(Font::new().line_height(LineHeight::Multiplier(1.5)))
+    ///     .content("Neque porro quisquam est qui dolorem ipsum quia dolor sit amet, consectetur, adipisci velit...");
+    /// ```
     pub fn line_height(mut self, line_height: impl Into<LineHeight>) -> Self {
-        self.static_css_props.insert(
-            StyleName::LineHeight.into(),
-            match line_height.into() {
-                LineHeight::Px(pixels) => px(pixels),
-                LineHeight::Normal => "normal".into_cow_str(),
-            },
-        );
+        self.static_css_props
+            .insert(StyleName::LineHeight.into(), line_height.into());
         self
     }
 
@@ -232,14 +267,7 @@ impl<'a> Font<'a> {
         mut self,
         line_height: impl Signal<Item = impl IntoOptionLineHeight> + Unpin + 'static,
     ) -> Self {
-        let line_height = line_height.map(|line_height| {
-            line_height
-                .into_option_line_height()
-                .map(|line_height| match line_height {
-                    LineHeight::Px(pixels) => px(pixels),
-                    LineHeight::Normal => "normal".into_cow_str(),
-                })
-        });
+        let line_height = line_height.map(|line_height| line_height.into_option_line_height());
         self.dynamic_css_props.insert(
             Cow::Borrowed(StyleName::LineHeight.into()),
             box_css_signal(line_height),
@@ -264,6 +292,118 @@ impl<'a> Font<'a> {
         self
     }
 
+    /// Slant the font, the angle is clamped to `-90..=90` degrees.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let paragraph = Paragraph::new()
+    ///     .s(Font::new().oblique(10))
+    ///     .content("Neque porro quisquam est qui dolorem ipsum quia dolor sit amet, consectetur, adipisci velit...");
+    /// ```
+    pub fn oblique(mut self, degrees: impl Into<f64>) -> Self {
+        let degrees = degrees.into();
+        debug_assert!(
+            (-90. ..=90.).contains(&degrees),
+            "oblique angle has to be in the range -90..=90 degrees"
+        );
+        let degrees = degrees.clamp(-90., 90.);
+        self.static_css_props.insert(
+            StyleName::FontStyle.into(),
+            crate::format!("oblique {}deg", degrees),
+        );
+        self
+    }
+
+    /// Set a variable font axis, e.g. `wght` or `slnt`.
+    /// The axis tag has 4 ASCII characters, the last value of the same axis wins.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let button = Button::new()
+    ///     .s(Font::new().variation("wght", 650).variation("wdth", 87.5))
+    ///     .label("Click me");
+    /// ```
+    pub fn variation(self, axis: &str, value: impl Into<f64>) -> Self {
+        debug_assert!(
+            axis.len() == 4 && axis.is_ascii(),
+            "font variation axis tag has to have 4 ASCII characters"
+        );
+        let variation = crate::format!("{} {}", quoted_css_string(axis), value.into());
+        self.append_value(StyleName::FontVariationSettings, &variation, ", ")
+    }
+
+    /// Use figures with the same width, e.g. to align numbers in table columns.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let price = El::new()
+    ///     .s(Font::new().tabular_numbers())
+    ///     .child("1 111.00");
+    /// ```
+    pub fn tabular_numbers(self) -> Self {
+        self.append_value(StyleName::FontVariantNumeric, "tabular-nums", " ")
+    }
+
+    pub fn small_caps(mut self) -> Self {
+        self.static_css_props
+            .insert(StyleName::FontVariantCaps.into(), "small-caps");
+        self
+    }
+
+    /// `false` disables all ligatures, `true` restores the font's default ones.
+    pub fn ligatures(mut self, enabled: bool) -> Self {
+        self.static_css_props.insert(
+            StyleName::FontVariantLigatures.into(),
+            if enabled { "normal" } else { "none" },
+        );
+        self
+    }
+
+    /// Turn on or off an OpenType feature, e.g. the stylistic set `ss01`.
+    /// The feature tag has 4 ASCII characters, features are combined,
+    /// the last value of the same feature wins.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let paragraph = Paragraph::new()
+    ///     .s(Font::new().feature("ss01", true).feature("liga", false))
+    ///     .content("Neque porro quisquam est qui dolorem ipsum quia dolor sit amet, consectetur, adipisci velit...");
+    /// ```
+    pub fn feature(self, tag: &str, enabled: bool) -> Self {
+        debug_assert!(
+            tag.len() == 4 && tag.is_ascii(),
+            "font feature tag has to have 4 ASCII characters"
+        );
+        let feature = [
+            quoted_css_string(tag).as_str(),
+            if enabled { " on" } else { " off" },
+        ]
+        .concat();
+        self.append_value(StyleName::FontFeatureSettings, &feature, ", ")
+    }
+
+    // Properties with lists of values are combined instead of replaced by the next call.
+    fn append_value(mut self, style_name: StyleName, value: &str, separator: &str) -> Self {
+        let name: &str = style_name.into();
+        let values = match self.static_css_props.remove(name) {
+            Some(values) if values.value.split(separator).any(|added| added == value) => {
+                values.value.to_string()
+            }
+            Some(values) => [&**values.value, separator, value].concat(),
+            None => value.to_owned(),
+        };
+        self.static_css_props.insert(name, values);
+        self
+    }
+
     /// Don't wrap the text according to its parent boundaries.
     ///
     /// # Example
@@ -390,6 +530,19 @@ impl<'a> Font<'a> {
         self
     }
 
+    /// Set the family font depending of signal's state.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let (dyslexic, dyslexic_signal) = Mutable::new_and_signal(false);
+    /// let paragraph = Paragraph::new()
+    ///     .s(Font::new().family_signal(dyslexic_signal.map_bool(
+    ///         || vec![FontFamily::new("OpenDyslexic"), FontFamily::SansSerif],
+    ///         || vec![FontFamily::new("Fira Sans"), FontFamily::SystemUi],
+    ///     )))
+    ///     .content("Neque porro quisquam est qui dolorem ipsum quia dolor sit amet, consectetur, adipisci velit...");
+    /// ```
     pub fn family_signal(
         mut self,
         family: impl Signal<Item = impl IntoIterator<Item = FontFamily<'a>>> + Unpin + 'static,
@@ -435,6 +588,112 @@ impl<'a> Font<'a> {
             .insert(StyleName::LetterSpacing.into(), px(tracking));
         self
     }
+
+    /// Set the space between letters in pixels,
+    /// negative values make the text tighter, e.g. for large headlines.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let headline = El::new()
+    ///     .s(Font::new().size(48).letter_spacing(-1.5))
+    ///     .child("Headline");
+    /// ```
+    pub fn letter_spacing(mut self, spacing: impl Into<f64>) -> Self {
+        self.static_css_props
+            .insert(StyleName::LetterSpacing.into(), px(spacing.into()));
+        self
+    }
+
+    pub fn letter_spacing_signal(
+        mut self,
+        spacing: impl Signal<Item = impl Into<Option<f64>>> + Unpin + 'static,
+    ) -> Self {
+        let spacing = spacing.map(|spacing| spacing.into().map(px));
+        self.dynamic_css_props.insert(
+            Cow::Borrowed(StyleName::LetterSpacing.into()),
+            box_css_signal(spacing),
+        );
+        self
+    }
+
+    /// Set the additional space between words in pixels.
+    pub fn word_spacing(mut self, spacing: impl Into<f64>) -> Self {
+        self.static_css_props
+            .insert(StyleName::WordSpacing.into(), px(spacing.into()));
+        self
+    }
+
+    pub fn word_spacing_signal(
+        mut self,
+        spacing: impl Signal<Item = impl Into<Option<f64>>> + Unpin + 'static,
+    ) -> Self {
+        let spacing = spacing.map(|spacing| spacing.into().map(px));
+        self.dynamic_css_props.insert(
+            Cow::Borrowed(StyleName::WordSpacing.into()),
+            box_css_signal(spacing),
+        );
+        self
+    }
+
+    /// Add a shadow to the text.
+    /// [Shadow::inner] and [Shadow::spread] aren't supported by `text-shadow`.
+    /// # Example
+    /// ```no_run
+    /// use zoon::{named_color::*, *};
+    ///
+    /// let headline = El::new()
+    ///     .s(Font::new().shadow(Shadow::new().color(GRAY_5).x(2).y(2).blur(4)))
+    ///     .child("Headline");
+    /// ```
+    pub fn shadow(self, shadow: Shadow) -> Self {
+        self.shadows([shadow])
+    }
+
+    /// Add multiple shadow layers to the text, the first one is on top.
+    /// No shadows set `text-shadow: none`.
+    pub fn shadows(mut self, shadows: impl IntoIterator<Item = Shadow>) -> Self {
+        self.static_css_props
+            .insert(StyleName::TextShadow.into(), text_shadows_value(shadows));
+        self
+    }
+
+    /// Set text shadows depending of signal's state.
+    /// Both `Option<Shadow>` and collections of shadows are accepted.
+    /// # Example
+    /// ```no_run
+    /// use zoon::{named_color::*, *};
+    ///
+    /// let (hovered, hover_signal) = Mutable::new_and_signal(false);
+    /// let button = Button::new()
+    ///     .s(Font::new().shadow_signal(
+    ///         hover_signal.map_true(|| Shadow::new().color(BLUE_3).blur(8)),
+    ///     ))
+    ///     .on_hovered_change(move |is_hovered| hovered.set_neq(is_hovered))
+    ///     .label("Hover me");
+    /// ```
+    pub fn shadow_signal(
+        mut self,
+        shadows: impl Signal<Item = impl IntoIterator<Item = Shadow>> + Unpin + 'static,
+    ) -> Self {
+        let shadows = shadows.map(|shadows| Some(text_shadows_value(shadows)));
+        self.dynamic_css_props.insert(
+            Cow::Borrowed(StyleName::TextShadow.into()),
+            box_css_signal(shadows),
+        );
+        self
+    }
+}
+
+fn text_shadows_value(shadows: impl IntoIterator<Item = Shadow>) -> String {
+    let shadows = shadows
+        .into_iter()
+        .map(Shadow::into_text_shadow_value)
+        .collect::<Vec<_>>();
+    if shadows.is_empty() {
+        return "none".to_owned();
+    }
+    shadows.join(", ")
 }
 
 impl<'a> Style<'a> for Font<'a> {
@@ -450,3 +709,77 @@ impl<'a> Style<'a> for Font<'a> {
         });
     }
 }
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::tests::css_props;
+
+    #[test]
+    fn test_line_height_and_spacing_formatting() {
+        // ------ ACT ------
+        let multiplier = css_props(Font::new().line_height(LineHeight::Multiplier(1.5)));
+        let pixels = css_props(Font::new().line_height(24));
+        let spacing = css_props(Font::new().letter_spacing(-1.5).word_spacing(4));
+        let signal_spacing = css_props(
+            Font::new()
+                .line_height_signal(always(Some(LineHeight::Multiplier(1.2))))
+                .letter_spacing_signal(always(Some(-0.5)))
+                .word_spacing_signal(always(None::<f64>)),
+        );
+
+        // ------ ASSERT ------
+        assert_eq!(multiplier["line-height"], "1.5");
+        assert_eq!(pixels["line-height"], "24px");
+        assert_eq!(spacing["letter-spacing"], "-1.5px");
+        assert_eq!(spacing["word-spacing"], "4px");
+        assert_eq!(signal_spacing["line-height"], "1.2");
+        assert_eq!(signal_spacing["letter-spacing"], "-0.5px");
+        assert!(!signal_spacing.contains_key("word-spacing"));
+    }
+
+    #[test]
+    fn test_weight_oblique_and_variation_formatting() {
+        // ------ ACT ------
+        let named_weight = css_props(Font::new().weight(FontWeight::Bold));
+        let variable_font = css_props(
+            Font::new()
+                .weight_signal(always(Some(FontWeight::Number(650))))
+                .oblique(-12.5)
+                .variation("wght", 650)
+                .variation("wdth", 87.5),
+        );
+
+        // ------ ASSERT ------
+        assert_eq!(named_weight["font-weight"], "700");
+        assert_eq!(variable_font["font-weight"], "650");
+        assert_eq!(variable_font["font-style"], "oblique -12.5deg");
+        assert_eq!(
+            variable_font["font-variation-settings"],
+            r#""wght" 650, "wdth" 87.5"#
+        );
+    }
+
+    #[test]
+    fn test_font_variants_and_features_are_merged() {
+        // ------ ACT ------
+        let props = css_props(
+            Font::new()
+                .tabular_numbers()
+                .feature("ss01", true)
+                .small_caps()
+                .feature("liga", false)
+                .ligatures(false)
+                .feature("ss01", true)
+                .tabular_numbers(),
+        );
+
+        // ------ ASSERT ------
+        assert_eq!(props["font-variant-numeric"], "tabular-nums");
+        assert_eq!(props["font-variant-caps"], "small-caps");
+        assert_eq!(props["font-variant-ligatures"], "none");
+        assert_eq!(props["font-feature-settings"], r#""ss01" on, "liga" off"#);
+    }
+}
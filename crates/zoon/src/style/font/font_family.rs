@@ -3,11 +3,14 @@ use std::borrow::Cow;
 
 // ------ FontFamily ------
 
+/// Generic families are emitted unquoted, [FontFamily::new] names are quoted
+/// when they aren't a plain CSS identifier, e.g. `"Fira Sans"` or `Arial`.
 #[derive(Debug, Clone)]
 pub enum FontFamily<'a> {
     Serif,
     SansSerif,
     Monospace,
+    SystemUi,
     Custom(Cow<'a, str>),
 }
 
@@ -23,7 +26,48 @@ impl<'a> IntoCowStr<'a> for FontFamily<'a> {
             FontFamily::Serif => "serif".into(),
             FontFamily::SansSerif => "sans-serif".into(),
             FontFamily::Monospace => "monospace".into(),
-            FontFamily::Custom(family) => ["\"", family.as_ref(), "\""].concat().into(),
+            FontFamily::SystemUi => "system-ui".into(),
+            FontFamily::Custom(family) if is_plain_name(&family) => family,
+            FontFamily::Custom(family) => quoted_css_string(&family).into(),
         }
     }
 }
+
+// Generic families and CSS-wide keywords have to be quoted
+// to be treated as names, e.g. a font called "Serif".
+const KEYWORDS: [&str; 19] = [
+    "serif",
+    "sans-serif",
+    "monospace",
+    "cursive",
+    "fantasy",
+    "system-ui",
+    "ui-serif",
+    "ui-sans-serif",
+    "ui-monospace",
+    "ui-rounded",
+    "math",
+    "emoji",
+    "fangsong",
+    "inherit",
+    "initial",
+    "unset",
+    "revert",
+    "revert-layer",
+    "default",
+];
+
+fn is_plain_name(family: &str) -> bool {
+    let is_name_char = |character: char| {
+        character.is_ascii_alphanumeric() || matches!(character, '-' | '_') || !character.is_ascii()
+    };
+    let name_start = family.strip_prefix('-').unwrap_or(family);
+    let starts_like_ident = name_start.chars().next().is_some_and(|character| {
+        is_name_char(character) && !character.is_ascii_digit() && character != '-'
+    });
+    starts_like_ident
+        && family.chars().all(is_name_char)
+        && !KEYWORDS
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(family))
+}
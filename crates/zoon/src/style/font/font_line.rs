@@ -1,20 +1,74 @@
 use crate::*;
 
+const LINE: &str = "text-decoration-line";
+
+const CSS_PROP_NAMES: [&str; 5] = [
+    LINE,
+    "text-decoration-color",
+    "text-decoration-style",
+    "text-decoration-thickness",
+    "text-underline-offset",
+];
+
+/// Text decoration set by [Font::line].
+/// Lines are combined, e.g. `underline` with `strike` emits `underline line-through`.
 #[derive(Default, Clone)]
 pub struct FontLine<'a> {
     pub(crate) static_css_props: StaticCSSProps<'a>,
     pub(crate) dynamic_css_props: DynamicCSSProps,
 }
 
+impl FontLine<'static> {
+    /// Switch the whole decoration depending of signal's state,
+    /// `None` removes all decoration properties.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let (hovered, hover_signal) = Mutable::new_and_signal(false);
+    /// let link = Link::new()
+    ///     .s(Font::new().line(FontLine::with_signal_self(
+    ///         hover_signal.map_bool(|| FontLine::new().underline(), || FontLine::new().none()),
+    ///     )))
+    ///     .on_hovered_change(move |is_hovered| hovered.set_neq(is_hovered))
+    ///     .label("Home")
+    ///     .to("/");
+    /// ```
+    pub fn with_signal_self(
+        line: impl Signal<Item = impl Into<Option<Self>>> + Unpin + 'static,
+    ) -> Self {
+        let mut this = Self::default();
+        let line = line.map(|line| line.into()).broadcast();
+        for name in CSS_PROP_NAMES {
+            this.dynamic_css_props.insert(
+                name.into(),
+                line.signal_ref(move |line: &Option<FontLine>| {
+                    if let Some(line) = line {
+                        if let Some(value) = line.static_css_props.0.get(name) {
+                            return always(Some(value.clone())).boxed_local();
+                        }
+                        if let Some(value) = line.dynamic_css_props.get(name) {
+                            return value.signal_cloned().boxed_local();
+                        }
+                    }
+                    always(None).boxed_local()
+                })
+                .flatten()
+                .boxed_local()
+                .broadcast(),
+            );
+        }
+        this
+    }
+}
+
 impl FontLine<'_> {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn underline(mut self) -> Self {
-        self.static_css_props
-            .insert("text-decoration-line", "underline");
-        self
+    pub fn underline(self) -> Self {
+        self.add_line("underline")
     }
 
     pub fn underline_signal(
@@ -23,20 +77,41 @@ impl FontLine<'_> {
     ) -> Self {
         let underline = underline.map_bool(|| "underline", || "none");
         self.dynamic_css_props
-            .insert("text-decoration-line".into(), box_css_signal(underline));
+            .insert(LINE.into(), box_css_signal(underline));
         self
     }
 
-    pub fn strike(mut self) -> Self {
-        self.static_css_props
-            .insert("text-decoration-line", "line-through");
-        self
+    pub fn strike(self) -> Self {
+        self.add_line("line-through")
     }
 
     pub fn strike_signal(mut self, strike: impl Signal<Item = bool> + Unpin + 'static) -> Self {
         let strike = strike.map_bool(|| "line-through", || "none");
         self.dynamic_css_props
-            .insert("text-decoration-line".into(), box_css_signal(strike));
+            .insert(LINE.into(), box_css_signal(strike));
+        self
+    }
+
+    /// Remove all lines, e.g. the default underline of links.
+    pub fn none(mut self) -> Self {
+        self.static_css_props.insert(LINE, "none");
+        self
+    }
+
+    fn add_line(mut self, line: &'static str) -> Self {
+        let mut lines = self
+            .static_css_props
+            .remove(LINE)
+            .map(|lines| lines.value.to_string())
+            .filter(|lines| lines != "none")
+            .unwrap_or_default();
+        if !lines.split(' ').any(|added_line| added_line == line) {
+            if !lines.is_empty() {
+                lines.push(' ');
+            }
+            lines.push_str(line);
+        }
+        self.static_css_props.insert(LINE, lines);
         self
     }
 
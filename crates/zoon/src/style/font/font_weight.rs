@@ -1,5 +1,6 @@
 // ------ FontWeight ------
 
+/// `Number` is clamped to `1..=1000`, any value in the range works with variable fonts.
 #[derive(Clone, Copy, PartialEq, PartialOrd)]
 pub enum FontWeight {
     ExtraHeavy,
@@ -16,6 +17,9 @@ pub enum FontWeight {
 }
 
 impl FontWeight {
+    pub const MIN: u32 = 1;
+    pub const MAX: u32 = 1000;
+
     pub fn number(&self) -> u32 {
         match self {
             Self::ExtraHeavy => 1000,
@@ -28,7 +32,13 @@ impl FontWeight {
             Self::Light => 300,
             Self::ExtraLight => 200,
             Self::Hairline => 100,
-            Self::Number(number) => *number,
+            Self::Number(number) => {
+                debug_assert!(
+                    (Self::MIN..=Self::MAX).contains(number),
+                    "font weight has to be in the range 1..=1000"
+                );
+                (*number).clamp(Self::MIN, Self::MAX)
+            }
         }
     }
 }
@@ -1,7 +1,14 @@
+use crate::*;
+use std::borrow::Cow;
+
 // ------ LineHeight ------
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+
+/// `Multiplier` is relative to the font size and it's emitted without units,
+/// so nested elements with other font sizes inherit the ratio, not the computed pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LineHeight {
     Px(u32),
+    Multiplier(f64),
     Normal,
 }
 
@@ -17,6 +24,16 @@ impl From<u32> for LineHeight {
     }
 }
 
+impl<'a> IntoCowStr<'a> for LineHeight {
+    fn into_cow_str(self) -> Cow<'a, str> {
+        match self {
+            Self::Px(pixels) => px(pixels),
+            Self::Multiplier(multiplier) => multiplier.into_cow_str(),
+            Self::Normal => "normal".into(),
+        }
+    }
+}
+
 // ------ IntoOptionLineHeight ------
 
 pub trait IntoOptionLineHeight {
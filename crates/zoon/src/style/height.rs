@@ -107,6 +107,36 @@ impl<'a> Height<'a> {
         )
     }
 
+    /// Set the height to `0` without a unit, e.g. for CSS calculations
+    /// where `0px` isn't accepted.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let spacer = El::new().s(Height::zero());
+    /// ```
+    pub fn zero() -> Self {
+        let mut this = Self::default();
+        this.css_props.insert(CssName::Height, into_prop_value("0"));
+        this.height_mode = HeightMode::ExactHeight;
+        this
+    }
+
+    /// Set the element height to fit its content.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let button = Button::new().s(Height::fit_content()).label("Click me");
+    /// ```
+    pub fn fit_content() -> Self {
+        let mut this = Self::default();
+        this.css_props
+            .insert(CssName::Height, into_prop_value("fit-content"));
+        this.height_mode = HeightMode::ExactHeight;
+        this
+    }
+
     pub fn growable() -> Self {
         Self::growable_with_factor::<f64>(None)
     }
@@ -1,4 +1,7 @@
 use crate::*;
+use std::sync::OnceLock;
+
+static LAYER_SCALE: OnceLock<LayerScale> = OnceLock::new();
 
 /// Styling to define the value on the `z` axis for an element. It does
 /// translate to z-index in css.
@@ -67,6 +70,36 @@ impl<'a> LayerIndex<'a> {
             .insert("z-index".into(), box_css_signal(index));
         this
     }
+
+    /// Set the [LayerScale::dropdown] layer index, e.g. for menus and tooltips.
+    pub fn dropdown() -> Self {
+        Self::new(LayerScale::get().dropdown)
+    }
+
+    /// Set the [LayerScale::modal] layer index.
+    pub fn modal() -> Self {
+        Self::new(LayerScale::get().modal)
+    }
+
+    /// Set the [LayerScale::toast] layer index, e.g. for notifications above modals.
+    pub fn toast() -> Self {
+        Self::new(LayerScale::get().toast)
+    }
+
+    /// Create a new stacking context, so layer indices of descendants
+    /// don't interfere with elements outside of the element.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let card = Column::new()
+    ///     .s(LayerIndex::new(1).isolate())
+    ///     .item(El::new().s(LayerIndex::new(100)).child("Still inside the card"));
+    /// ```
+    pub fn isolate(mut self) -> Self {
+        self.static_css_props.insert("isolation", "isolate");
+        self
+    }
 }
 
 impl<'a> Style<'a> for LayerIndex<'a> {
@@ -82,3 +115,45 @@ impl<'a> Style<'a> for LayerIndex<'a> {
         });
     }
 }
+
+// ------ LayerScale ------
+
+/// Layer indices of the named layers like [LayerIndex::modal].
+/// # Example
+/// ```no_run
+/// use zoon::*;
+///
+/// // Before the first named layer is created.
+/// let _ = LayerScale {
+///     modal: 5000,
+///     ..LayerScale::default()
+/// }
+/// .init();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerScale {
+    pub dropdown: i32,
+    pub modal: i32,
+    pub toast: i32,
+}
+
+impl Default for LayerScale {
+    fn default() -> Self {
+        Self {
+            dropdown: 1000,
+            modal: 2000,
+            toast: 3000,
+        }
+    }
+}
+
+impl LayerScale {
+    /// Returns the scale back when the named layers have been already used or initialized.
+    pub fn init(self) -> Result<(), Self> {
+        LAYER_SCALE.set(self)
+    }
+
+    pub fn get() -> Self {
+        *LAYER_SCALE.get_or_init(Self::default)
+    }
+}
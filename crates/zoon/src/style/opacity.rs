@@ -0,0 +1,105 @@
+use crate::*;
+
+/// Define the element opacity, values are clamped to `0.0..=1.0`.
+/// More information at <https://developer.mozilla.org/en-US/docs/Web/CSS/opacity>.
+#[derive(Default, Clone)]
+pub struct Opacity<'a> {
+    static_css_props: StaticCSSProps<'a>,
+    dynamic_css_props: DynamicCSSProps,
+}
+
+impl<'a> Opacity<'a> {
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let button = Button::new().s(Opacity::new(0.5)).label("Click me");
+    /// ```
+    pub fn new(opacity: impl Into<f64>) -> Self {
+        let mut this = Self::default();
+        this.static_css_props
+            .insert("opacity", clamp_opacity(opacity.into()));
+        this
+    }
+
+    /// Set the opacity depending of signal's state,
+    /// `None` removes the `opacity` property.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let (hovered, hovered_signal) = Mutable::new_and_signal(false);
+    /// let button = Button::new()
+    ///     .s(Opacity::with_signal(hovered_signal.map_false(|| 0.7)))
+    ///     .on_hovered_change(move |is_hovered| hovered.set_neq(is_hovered))
+    ///     .label("Hover me");
+    /// ```
+    pub fn with_signal(
+        opacity: impl Signal<Item = impl Into<Option<f64>>> + Unpin + 'static,
+    ) -> Self {
+        let mut this = Self::default();
+        let opacity = opacity.map(|opacity| opacity.into().map(clamp_opacity));
+        this.dynamic_css_props
+            .insert("opacity".into(), box_css_signal(opacity));
+        this
+    }
+}
+
+// `NaN` is treated as fully opaque, the CSS default.
+fn clamp_opacity(opacity: f64) -> f64 {
+    if opacity.is_nan() {
+        return 1.;
+    }
+    opacity.clamp(0., 1.)
+}
+
+impl<'a> Style<'a> for Opacity<'a> {
+    fn move_to_groups(self, groups: &mut StyleGroups<'a>) {
+        groups.update_first(|mut group| {
+            let Self {
+                static_css_props,
+                dynamic_css_props,
+            } = self;
+            group.static_css_props.extend(static_css_props);
+            group.dynamic_css_props.extend(dynamic_css_props);
+            group
+        });
+    }
+}
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::tests::css_props;
+
+    #[test]
+    fn test_opacity_is_clamped() {
+        // ------ ACT ------
+        let opacities = [0.25, 1.5, -0.2, f64::NAN].map(|opacity| css_props(Opacity::new(opacity)));
+        let signal_opacity = css_props(Opacity::with_signal(always(Some(2.))));
+
+        // ------ ASSERT ------
+        assert_eq!(
+            opacities.map(|props| props["opacity"].clone()),
+            ["0.25", "1", "0", "1"]
+        );
+        assert_eq!(signal_opacity["opacity"], "1");
+    }
+
+    #[test]
+    fn test_none_removes_opacity() {
+        // ------ ARRANGE ------
+        let opacity = Mutable::new(Some(0.5));
+
+        // ------ ACT ------
+        let half_transparent = css_props(Opacity::with_signal(opacity.signal()));
+        opacity.set(None);
+        let default_opacity = css_props(Opacity::with_signal(opacity.signal()));
+
+        // ------ ASSERT ------
+        assert_eq!(half_transparent["opacity"], "0.5");
+        assert!(default_opacity.is_empty());
+    }
+}
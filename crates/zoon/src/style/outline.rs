@@ -1,12 +1,16 @@
 use crate::*;
 use std::borrow::Cow;
 
+/// Outline drawn around the element without affecting its layout, e.g. a focus indicator.
+/// It's independent of [Borders], so both can be applied to the same element.
 #[derive(Clone)]
 pub struct Outline {
     width: u32,
     style: OutlineStyle,
     color: Color,
     inner: bool,
+    offset: Option<i32>,
+    none: bool,
     self_signal: Option<Broadcaster<LocalBoxSignal<'static, Option<Self>>>>,
 }
 
@@ -17,12 +21,41 @@ impl Default for Outline {
             style: OutlineStyle::Solid,
             color: oklch().l(0).c(0).h(0).a(1).into_color(),
             inner: false,
+            offset: None,
+            none: false,
             self_signal: None,
         }
     }
 }
 
 impl Outline {
+    /// The same as [outer](Self::outer).
+    /// # Example
+    /// ```no_run
+    /// use zoon::{named_color::*, *};
+    ///
+    /// let (focused, focused_signal) = Mutable::new_and_signal(false);
+    /// let button = Button::new()
+    ///     .s(Outline::with_signal_self(focused_signal.map_true(|| {
+    ///         Outline::new().width(2).solid().color(BLUE_5).offset(2)
+    ///     })))
+    ///     .on_focused_change(move |is_focused| focused.set_neq(is_focused))
+    ///     .label("Focus me");
+    /// ```
+    pub fn new() -> Self {
+        Self::outer()
+    }
+
+    /// Remove the outline, e.g. the browser's focus ring.
+    /// Focused elements need another visible indicator then, e.g. a [Background] color,
+    /// so a warning is logged in debug builds as a reminder.
+    pub fn none() -> Self {
+        Self {
+            none: true,
+            ..Self::default()
+        }
+    }
+
     pub fn inner() -> Self {
         let mut this = Self::default();
         this.inner = true;
@@ -75,7 +108,17 @@ impl Outline {
         self
     }
 
+    /// Set the distance between the outline and the element's border,
+    /// it replaces the offset set by [inner](Self::inner).
+    pub fn offset(mut self, offset: i32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
     fn to_css_outline_value(&self) -> Cow<'static, str> {
+        if self.none {
+            return "none".into();
+        }
         crate::format!(
             "{}px {} {}",
             self.width,
@@ -86,6 +129,9 @@ impl Outline {
     }
 
     fn to_css_outline_offset_value(&self) -> Cow<'static, str> {
+        if let Some(offset) = self.offset {
+            return px(offset);
+        }
         if self.inner {
             return px(self.width as i32 * -1);
         }
@@ -114,6 +160,12 @@ impl<'a> Style<'a> for Outline {
                     })),
                 );
             } else {
+                if cfg!(debug_assertions) && self.none {
+                    crate::eprintln!(
+                        "`Outline::none` removes the focus indicator, \
+                        make sure focused elements are styled differently"
+                    );
+                }
                 group
                     .static_css_props
                     .insert("outline", self.to_css_outline_value());
@@ -0,0 +1,168 @@
+use crate::*;
+
+const INSETS: [&str; 4] = ["top", "right", "bottom", "left"];
+
+/// Position the element by its containing block, the viewport or the scroll position.
+/// Offsets move the element toward the center, negative offsets are allowed.
+/// More information at <https://developer.mozilla.org/en-US/docs/Web/CSS/position>.
+#[derive(Default, Clone)]
+pub struct Position<'a> {
+    /// Static css properties used by zoon.
+    static_css_props: StaticCSSProps<'a>,
+    /// Customizable css properties which can be added.
+    dynamic_css_props: DynamicCSSProps,
+    sticky: bool,
+}
+
+impl<'a> Position<'a> {
+    pub fn relative() -> Self {
+        Self::new("relative")
+    }
+
+    /// Position the element by its nearest positioned ancestor.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let card = El::new()
+    ///     .s(Position::relative())
+    ///     .child(El::new().s(Position::absolute().top(-8).right(-8)).child("3"));
+    /// ```
+    pub fn absolute() -> Self {
+        Self::new("absolute")
+    }
+
+    /// Position the element by the viewport.
+    pub fn fixed() -> Self {
+        Self::new("fixed")
+    }
+
+    /// Keep the element in the viewport while its parent is scrolled,
+    /// at least one offset has to be set.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let header = El::new().s(Position::sticky().top(0)).child("Header");
+    /// ```
+    pub fn sticky() -> Self {
+        let mut this = Self::new("sticky");
+        this.sticky = true;
+        this
+    }
+
+    fn new(position: &'a str) -> Self {
+        let mut this = Self::default();
+        this.static_css_props.insert("position", position);
+        this
+    }
+
+    pub fn top(self, top: i32) -> Self {
+        self.inset("top", px(top))
+    }
+
+    pub fn top_percent(self, top: impl Into<f64>) -> Self {
+        self.inset("top", pct(top.into()))
+    }
+
+    /// Set the top offset depending of signal's state, `None` removes it.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let panel_top = Mutable::new(100);
+    /// let panel = El::new()
+    ///     .s(Position::fixed().left(20).top_signal(panel_top.signal()))
+    ///     .child("Drag me");
+    /// ```
+    pub fn top_signal(
+        self,
+        top: impl Signal<Item = impl Into<Option<i32>>> + Unpin + 'static,
+    ) -> Self {
+        self.inset_signal("top", top)
+    }
+
+    pub fn right(self, right: i32) -> Self {
+        self.inset("right", px(right))
+    }
+
+    pub fn right_percent(self, right: impl Into<f64>) -> Self {
+        self.inset("right", pct(right.into()))
+    }
+
+    pub fn right_signal(
+        self,
+        right: impl Signal<Item = impl Into<Option<i32>>> + Unpin + 'static,
+    ) -> Self {
+        self.inset_signal("right", right)
+    }
+
+    pub fn bottom(self, bottom: i32) -> Self {
+        self.inset("bottom", px(bottom))
+    }
+
+    pub fn bottom_percent(self, bottom: impl Into<f64>) -> Self {
+        self.inset("bottom", pct(bottom.into()))
+    }
+
+    pub fn bottom_signal(
+        self,
+        bottom: impl Signal<Item = impl Into<Option<i32>>> + Unpin + 'static,
+    ) -> Self {
+        self.inset_signal("bottom", bottom)
+    }
+
+    pub fn left(self, left: i32) -> Self {
+        self.inset("left", px(left))
+    }
+
+    pub fn left_percent(self, left: impl Into<f64>) -> Self {
+        self.inset("left", pct(left.into()))
+    }
+
+    pub fn left_signal(
+        self,
+        left: impl Signal<Item = impl Into<Option<i32>>> + Unpin + 'static,
+    ) -> Self {
+        self.inset_signal("left", left)
+    }
+
+    fn inset(mut self, name: &'a str, value: impl IntoCowStr<'a>) -> Self {
+        self.static_css_props.insert(name, value);
+        self
+    }
+
+    fn inset_signal(
+        mut self,
+        name: &'static str,
+        value: impl Signal<Item = impl Into<Option<i32>>> + Unpin + 'static,
+    ) -> Self {
+        let value = value.map(|value| value.into().map(px));
+        self.dynamic_css_props
+            .insert(name.into(), box_css_signal(value));
+        self
+    }
+}
+
+impl<'a> Style<'a> for Position<'a> {
+    fn move_to_groups(self, groups: &mut StyleGroups<'a>) {
+        let Self {
+            static_css_props,
+            dynamic_css_props,
+            sticky,
+        } = self;
+        if cfg!(debug_assertions) && sticky {
+            let has_inset = INSETS.iter().any(|name| {
+                static_css_props.0.contains_key(name) || dynamic_css_props.contains_key(*name)
+            });
+            if not(has_inset) {
+                crate::eprintln!("`Position::sticky` doesn't do anything without an offset");
+            }
+        }
+        groups.update_first(|mut group| {
+            group.static_css_props.extend(static_css_props);
+            group.dynamic_css_props.extend(dynamic_css_props);
+            group
+        });
+    }
+}
@@ -87,6 +87,10 @@ impl Default for RadiusSignal {
 // ------ RoundedCorners ------
 /// Define rounded corners. It does translate to css `border-radius` for web.
 /// More information at <https://developer.mozilla.org/en-US/docs/Web/CSS/border-radius>.
+///
+/// Each corner keeps the last set radius, e.g. `RoundedCorners::all(8).top_left(0)`
+/// has a sharp top left corner. All corners are written to one `border-radius` value,
+/// so the result doesn't depend on the order of CSS properties.
 #[derive(Default, Clone)]
 pub struct RoundedCorners {
     top_left: RadiusSignal,
@@ -649,3 +653,47 @@ fn compute_radii(
 //                 .bottom_left(10)
 //         )
 // }
+
+// ====== ====== TESTS ====== ======
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::tests::css_props;
+
+    #[test]
+    fn test_per_corner_radius_overrides_all() {
+        // ------ ACT ------
+        let overridden = css_props(RoundedCorners::all(8).top_left(0).bottom_right(2));
+        let overridden_by_all = css_props(RoundedCorners::new().top_left(0).top(8));
+
+        // ------ ASSERT ------
+        assert_eq!(
+            Vec::from_iter(overridden),
+            [("border-radius".to_owned(), "0px 8px 2px 8px".to_owned())]
+        );
+        assert_eq!(
+            Vec::from_iter(overridden_by_all),
+            [("border-radius".to_owned(), "8px 8px 0px 0px".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_max_radius_fits_element_size() {
+        // ------ ACT ------
+        let pill = compute_radii(Radius::Max, Radius::Max, Radius::Max, Radius::Max, 100, 40);
+        let mixed = compute_radii(
+            Radius::Max,
+            Radius::Px(10),
+            Radius::Px(10),
+            Radius::Px(0),
+            100,
+            40,
+        );
+
+        // ------ ASSERT ------
+        assert_eq!(pill, "20px 20px 20px 20px");
+        // the left side is shared with the bottom left radius
+        assert_eq!(mixed, "30px 10px 0px 10px");
+    }
+}
@@ -34,7 +34,9 @@ impl<'a> Scrollbars<'a> {
     /// ```
     pub fn both() -> Self {
         let mut this = Self::default();
-        this.static_css_props.insert("overflow", "auto");
+        // Longhands, so they can be combined with `Clip::x` or `Clip::y`.
+        this.static_css_props.insert("overflow-x", "auto");
+        this.static_css_props.insert("overflow-y", "auto");
         this
     }
 
@@ -82,6 +84,23 @@ impl<'a> Scrollbars<'a> {
         this
     }
 
+    /// Don't scroll the parents when the scrolling reaches the end of the element,
+    /// e.g. for nested scrollable areas.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let dropdown = Column::new()
+    ///     .s(Scrollbars::y_and_clip_x().overscroll_contain())
+    ///     .s(Height::exact(200))
+    ///     .items((0..100).map(|index| El::new().child(index)));
+    /// ```
+    pub fn overscroll_contain(mut self) -> Self {
+        self.static_css_props
+            .insert("overscroll-behavior", "contain");
+        self
+    }
+
     pub fn visible(mut self, visible: bool) -> Self {
         self.visible = visible;
         self
@@ -53,6 +53,8 @@ impl<'a> Shadows<'a> {
     /// Add new shadows.
     /// The method accepts an iterator of [Shadow] so multiple effects can be
     /// used. More information at <https://developer.mozilla.org/en-US/docs/Web/CSS/box-shadow>
+    ///
+    /// No shadows set `box-shadow: none` to remove the shadows of other style groups.
     /// # Example
     /// ```no_run
     /// use zoon::{named_color::*, *};
@@ -73,16 +75,13 @@ impl<'a> Shadows<'a> {
             .map(|shadow| shadow.into_cow_str())
             .collect::<Cow<_>>();
         let mut this = Self::default();
-        if shadows.is_empty() {
-            return this;
-        }
         this.static_css_props
-            .insert("box-shadow", shadows.join(", "));
+            .insert("box-shadow", shadows_value(&shadows));
         this
     }
     /// Add new shadows depending of signal's state.
     /// The method accepts an iterator of [Shadow] so multiple effects can be
-    /// used.
+    /// used. No shadows set `box-shadow: none` like in [Shadows::new].
     /// # Example
     /// ```no_run
     /// use zoon::{named_color::*, *};
@@ -102,15 +101,11 @@ impl<'a> Shadows<'a> {
         shadows: impl Signal<Item = impl IntoIterator<Item = Shadow>> + Unpin + 'static,
     ) -> Self {
         let shadows = shadows.map(|shadows| {
-            let shadow_style = shadows
+            let shadows = shadows
                 .into_iter()
                 .map(|shadow| shadow.into_cow_str())
-                .collect::<Cow<_>>()
-                .join(", ");
-            if shadow_style.is_empty() {
-                None?;
-            }
-            Some(shadow_style)
+                .collect::<Vec<_>>();
+            Some(shadows_value(&shadows))
         });
         let mut this = Self::default();
         this.dynamic_css_props.insert(
@@ -121,6 +116,13 @@ impl<'a> Shadows<'a> {
     }
 }
 
+fn shadows_value(shadows: &[Cow<str>]) -> String {
+    if shadows.is_empty() {
+        return "none".to_owned();
+    }
+    shadows.join(", ")
+}
+
 impl<'a> Style<'a> for Shadows<'a> {
     fn move_to_groups(self, groups: &mut StyleGroups<'a>) {
         groups.update_first(|mut group| {
@@ -265,6 +267,25 @@ impl Shadow {
         }
         self
     }
+
+    // `drop-shadow` doesn't support `inset` and the spread radius.
+    pub(crate) fn into_drop_shadow_value(self) -> String {
+        let mut value = [px(self.x), px(self.y), px(self.blur)].join(" ");
+        if let Some(color) = self.color {
+            value.push(' ');
+            value.push_str(&color.into_color_string());
+        }
+        value
+    }
+
+    // `text-shadow` doesn't support `inset` and the spread radius either.
+    pub(crate) fn into_text_shadow_value(self) -> String {
+        debug_assert!(
+            !self.inner && self.spread == 0,
+            "text shadow cannot be inner or have spread"
+        );
+        self.into_drop_shadow_value()
+    }
 }
 
 impl<'a> IntoCowStr<'a> for Shadow {
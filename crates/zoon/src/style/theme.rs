@@ -0,0 +1,63 @@
+use crate::*;
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// ------ theme ------
+
+type Themes = SendWrapper<RefCell<HashMap<TypeId, Box<dyn Any>>>>;
+
+// One `Mutable` per theme type, it's `None` until `set_theme` is called.
+fn theme_mutable<T: Clone + 'static>() -> Mutable<Option<T>> {
+    static THEMES: OnceLock<Themes> = OnceLock::new();
+    let themes = THEMES.get_or_init(|| SendWrapper::new(RefCell::new(HashMap::new())));
+    themes
+        .borrow_mut()
+        .entry(TypeId::of::<T>())
+        .or_insert_with(|| Box::new(Mutable::new(None::<T>)))
+        .downcast_ref::<Mutable<Option<T>>>()
+        .unwrap_throw()
+        .clone()
+}
+
+/// Set or switch the app theme, a struct with design tokens like colors or spacing.
+/// All styles using [theme_signal] or `*_theme` methods like [Background::color_theme]
+/// are updated.
+///
+/// Styles can't see the element tree, so a subtree with another theme
+/// keeps its own `Mutable` with the theme and uses the `*_signal` style methods instead,
+/// e.g. `Background::new().color_signal(local_theme.signal_ref(|theme| theme.surface))`.
+/// # Example
+/// ```no_run
+/// use zoon::*;
+///
+/// #[derive(Clone)]
+/// struct Theme {
+///     surface: Color,
+///     text: Color,
+/// }
+///
+/// set_theme(Theme {
+///     surface: NamedColor::White.into_color(),
+///     text: NamedColor::Black.into_color(),
+/// });
+///
+/// let card = El::new()
+///     .s(Background::new().color_theme(|theme: &Theme| theme.surface))
+///     .s(Font::new().color_theme(|theme: &Theme| theme.text))
+///     .child("Card");
+/// ```
+pub fn set_theme<T: Clone + 'static>(theme: T) {
+    theme_mutable::<T>().set(Some(theme));
+}
+
+/// `None` until the theme is set by [set_theme].
+pub fn theme<T: Clone + 'static>() -> Option<T> {
+    theme_mutable::<T>().get_cloned()
+}
+
+/// `None` until the theme is set by [set_theme].
+pub fn theme_signal<T: Clone + 'static>() -> impl Signal<Item = Option<T>> {
+    theme_mutable::<T>().signal_cloned()
+}
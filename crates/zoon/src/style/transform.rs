@@ -16,6 +16,7 @@ impl Transform {
     }
 
     /// Apply transformations depending of signal's state.
+    /// `None` removes the `transform` property.
     /// # Example
     /// ```no_run
     /// use zoon::*;
@@ -24,7 +25,7 @@ impl Transform {
     /// let page = El::new().s(Height::screen()).child(
     ///     Button::new()
     ///         .s(Align::center())
-    ///         .s(Transform::with_signal(hover_signal.map_bool(
+    ///         .s(Transform::with_signal_self(hover_signal.map_bool(
     ///             || Transform::new().rotate(45),
     ///             || Transform::new(),
     ///         )))
@@ -15,6 +15,7 @@ pub struct Transitions<'a> {
 
 impl<'a> Transitions<'a> {
     /// Apply a transformation to an element.
+    /// No transitions set `transition: none`.
     /// # Example
     /// ```no_run
     /// use zoon::*;
@@ -28,13 +29,9 @@ impl<'a> Transitions<'a> {
     ///     .label("Hover me");
     /// ```
     pub fn new(transitions: impl IntoIterator<Item = Transition>) -> Self {
-        let transitions = transitions
-            .into_iter()
-            .map(|shadow| shadow.into_cow_str())
-            .collect::<Cow<_>>()
-            .join(", ");
         let mut this = Self::default();
-        this.static_css_props.insert("transition", transitions);
+        this.static_css_props
+            .insert("transition", transitions_value(transitions));
         this
     }
 
@@ -42,13 +39,7 @@ impl<'a> Transitions<'a> {
     pub fn with_signal(
         transitions: impl Signal<Item = impl IntoIterator<Item = Transition>> + Unpin + 'static,
     ) -> Self {
-        let transitions = transitions.map(|transitions| {
-            transitions
-                .into_iter()
-                .map(|shadow| shadow.into_cow_str())
-                .collect::<Cow<_>>()
-                .join(", ")
-        });
+        let transitions = transitions.map(|transitions| transitions_value(transitions));
         let mut this = Self::default();
         this.dynamic_css_props
             .insert("transition".into(), box_css_signal(transitions));
@@ -56,6 +47,17 @@ impl<'a> Transitions<'a> {
     }
 }
 
+fn transitions_value(transitions: impl IntoIterator<Item = Transition>) -> String {
+    let transitions = transitions
+        .into_iter()
+        .map(|transition| transition.into_cow_str())
+        .collect::<Vec<_>>();
+    if transitions.is_empty() {
+        return "none".to_owned();
+    }
+    transitions.join(", ")
+}
+
 impl<'a> Style<'a> for Transitions<'a> {
     fn move_to_groups(self, groups: &mut StyleGroups<'a>) {
         groups.update_first(|mut group| {
@@ -75,6 +77,8 @@ impl<'a> Style<'a> for Transitions<'a> {
 pub struct Transition {
     property: Cow<'static, str>,
     duration: u32,
+    timing_function: Option<Cow<'static, str>>,
+    delay: u32,
 }
 
 impl Default for Transition {
@@ -82,6 +86,8 @@ impl Default for Transition {
         Self {
             property: "all".into(),
             duration: 1000,
+            timing_function: None,
+            delay: 0,
         }
     }
 }
@@ -122,10 +128,74 @@ impl Transition {
         self.duration = ms;
         self
     }
+
+    /// Wait before the transition starts.
+    pub fn delay(mut self, ms: u32) -> Self {
+        self.delay = ms;
+        self
+    }
+
+    pub fn linear(self) -> Self {
+        self.timing_function("linear")
+    }
+
+    pub fn ease(self) -> Self {
+        self.timing_function("ease")
+    }
+
+    pub fn ease_in(self) -> Self {
+        self.timing_function("ease-in")
+    }
+
+    pub fn ease_out(self) -> Self {
+        self.timing_function("ease-out")
+    }
+
+    pub fn ease_in_out(self) -> Self {
+        self.timing_function("ease-in-out")
+    }
+
+    /// Custom easing, more information at
+    /// <https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function#cubic_b%C3%A9zier_easing_function>.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    /// let transition = Transition::transform()
+    ///     .duration(300)
+    ///     .cubic_bezier(0.68, -0.6, 0.32, 1.6);
+    /// ```
+    pub fn cubic_bezier(
+        self,
+        x1: impl Into<f64>,
+        y1: impl Into<f64>,
+        x2: impl Into<f64>,
+        y2: impl Into<f64>,
+    ) -> Self {
+        self.timing_function(crate::format!(
+            "cubic-bezier({}, {}, {}, {})",
+            x1.into(),
+            y1.into(),
+            x2.into(),
+            y2.into()
+        ))
+    }
+
+    fn timing_function(mut self, timing_function: impl IntoCowStr<'static>) -> Self {
+        self.timing_function = Some(timing_function.into_cow_str());
+        self
+    }
 }
 
 impl<'a> IntoCowStr<'a> for Transition {
     fn into_cow_str(self) -> Cow<'a, str> {
-        crate::format!("{} {}ms", self.property, self.duration).into()
+        let mut transition = crate::format!("{} {}ms", self.property, self.duration);
+        if let Some(timing_function) = self.timing_function {
+            transition.push(' ');
+            transition.push_str(&timing_function);
+        }
+        if self.delay > 0 {
+            transition.push_str(&crate::format!(" {}ms", self.delay));
+        }
+        transition.into()
     }
 }
@@ -130,6 +130,36 @@ impl<'a> Width<'a> {
         )
     }
 
+    /// Set the width to `0` without a unit, e.g. for CSS calculations
+    /// where `0px` isn't accepted.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let spacer = El::new().s(Width::zero());
+    /// ```
+    pub fn zero() -> Self {
+        let mut this = Self::default();
+        this.css_props.insert(CssName::Width, into_prop_value("0"));
+        this.width_mode = WidthMode::ExactWidth;
+        this
+    }
+
+    /// Set the element width to fit its content.
+    /// # Example
+    /// ```no_run
+    /// use zoon::*;
+    ///
+    /// let button = Button::new().s(Width::fit_content()).label("Click me");
+    /// ```
+    pub fn fit_content() -> Self {
+        let mut this = Self::default();
+        this.css_props
+            .insert(CssName::Width, into_prop_value("fit-content"));
+        this.width_mode = WidthMode::ExactWidth;
+        this
+    }
+
     /// Set the element width to fill its container.
     /// # Example
     /// ```no_run